@@ -0,0 +1,171 @@
+//! Measures the vertex and index upload strategies and command encoding over synthetic frames
+//! from `StressConfig`, on a headless device. Run with `cargo bench --features stress`; the
+//! benches are skipped when no adapter is available.
+//!
+//! `encode` times only the CPU side of a frame: appending the draw data, uploading it and
+//! recording the render pass. `frame` also submits it and waits for the GPU.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use imgui_wgpu_rs::{Renderer, RendererConfig, StressConfig};
+use std::time::{Duration, Instant};
+
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+const SIZE: (u32, u32) = (1920, 1080);
+
+// (windows, vertices per window)
+const FRAME_SIZES: &[(usize, usize)] = &[(4, 1_000), (16, 10_000), (64, 40_000)];
+
+#[derive(Clone, Copy, Debug)]
+enum Upload {
+    // `Queue::write_buffer` for every buffer.
+    Queue,
+    // Staging buffers copied by an encoder from `set_upload_encoder`.
+    Encoder,
+    // `RendererConfig::mapped_buffers`.
+    Mapped,
+}
+
+struct Gpu {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    target: wgpu::TextureView,
+}
+
+fn gpu() -> Option<Gpu> {
+    let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::Default,
+        compatible_surface: None,
+    }))?;
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            features: adapter.features() & wgpu::Features::MAPPABLE_PRIMARY_BUFFERS,
+            limits: wgpu::Limits::default(),
+            shader_validation: true,
+        },
+        None,
+    ))
+    .ok()?;
+    let target = device
+        .create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: SIZE.0,
+                height: SIZE.1,
+                depth: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+        })
+        .create_view(&wgpu::TextureViewDescriptor::default());
+    Some(Gpu {
+        device,
+        queue,
+        target,
+    })
+}
+
+// Records one frame into a command buffer, returning it with the time recording took.
+fn encode(
+    gpu: &Gpu,
+    renderer: &mut Renderer,
+    upload: Upload,
+    snapshot: &imgui_wgpu_rs::DrawDataSnapshot,
+) -> (Vec<wgpu::CommandBuffer>, Duration) {
+    let start = Instant::now();
+    if let Upload::Encoder = upload {
+        renderer.set_upload_encoder(
+            gpu.device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None }),
+        );
+    }
+    let mut encoder = gpu
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: &gpu.target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+        renderer
+            .render_snapshot(&gpu.device, &gpu.queue, &mut render_pass, snapshot)
+            .unwrap();
+    }
+    let mut command_buffers: Vec<_> = renderer
+        .take_upload_encoder()
+        .map(|upload_encoder| upload_encoder.finish())
+        .into_iter()
+        .collect();
+    command_buffers.push(encoder.finish());
+    (command_buffers, start.elapsed())
+}
+
+fn render(c: &mut Criterion) {
+    let gpu = match gpu() {
+        Some(gpu) => gpu,
+        None => {
+            eprintln!("no adapter available, skipping the render benches");
+            return;
+        }
+    };
+    let mappable = gpu
+        .device
+        .features()
+        .contains(wgpu::Features::MAPPABLE_PRIMARY_BUFFERS);
+    for &upload in &[Upload::Queue, Upload::Encoder, Upload::Mapped] {
+        if let (Upload::Mapped, false) = (upload, mappable) {
+            continue;
+        }
+        let mut config = RendererConfig::new(FORMAT);
+        config.mapped_buffers = matches!(upload, Upload::Mapped);
+        let mut renderer = Renderer::without_fonts(&gpu.device, config);
+        let pixels = vec![255; 64 * 64 * 4];
+        let textures = (0..4)
+            .map(|_| {
+                renderer
+                    .upload_texture(&gpu.device, &gpu.queue, 64, 64, &pixels)
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        for &(windows, vertices_per_window) in FRAME_SIZES {
+            let snapshot =
+                StressConfig::new(windows, vertices_per_window, textures.clone()).generate();
+            let id = format!("{:?}/{}x{}", upload, windows, vertices_per_window);
+
+            c.bench_with_input(BenchmarkId::new("encode", &id), &snapshot, |b, snapshot| {
+                b.iter_custom(|iterations| {
+                    let mut total = Duration::default();
+                    for _ in 0..iterations {
+                        let (command_buffers, elapsed) =
+                            encode(&gpu, &mut renderer, upload, snapshot);
+                        total += elapsed;
+                        gpu.queue.submit(command_buffers);
+                        gpu.device.poll(wgpu::Maintain::Wait);
+                    }
+                    total
+                })
+            });
+            c.bench_with_input(BenchmarkId::new("frame", &id), &snapshot, |b, snapshot| {
+                b.iter(|| {
+                    let (command_buffers, _) = encode(&gpu, &mut renderer, upload, snapshot);
+                    gpu.queue.submit(command_buffers);
+                    gpu.device.poll(wgpu::Maintain::Wait);
+                })
+            });
+        }
+    }
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
@@ -0,0 +1,156 @@
+//! A window showing some UI, a custom texture and the frame rate.
+//!
+//! Run with `cargo run --example winit --features winit`.
+
+use imgui::{im_str, Condition, FontConfig, FontSource, Image, Window};
+use imgui_wgpu_rs::{ImguiWgpu, RendererConfig};
+use winit::dpi::LogicalSize;
+use winit::event::{Event, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoop};
+use winit::window::WindowBuilder;
+
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
+
+fn main() {
+    let event_loop = EventLoop::new();
+    let window = WindowBuilder::new()
+        .with_title("imgui-wgpu-rs")
+        .with_inner_size(LogicalSize::new(1024.0, 768.0))
+        .build(&event_loop)
+        .unwrap();
+
+    let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
+    let surface = unsafe { instance.create_surface(&window) };
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::Default,
+        compatible_surface: Some(&surface),
+    }))
+    .expect("no suitable adapter");
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            features: wgpu::Features::empty(),
+            limits: wgpu::Limits::default(),
+            shader_validation: true,
+        },
+        None,
+    ))
+    .unwrap();
+
+    let size = window.inner_size();
+    let mut swap_chain_descriptor = wgpu::SwapChainDescriptor {
+        usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+        format: FORMAT,
+        width: size.width,
+        height: size.height,
+        present_mode: wgpu::PresentMode::Fifo,
+    };
+    let mut swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
+
+    let mut gui = ImguiWgpu::new(&window, &device, &queue, RendererConfig::new(FORMAT)).unwrap();
+    gui.imgui.set_ini_filename(None);
+
+    // Rasterize the font at the window's scale factor so text stays sharp on hi-DPI screens.
+    let hidpi_factor = gui.platform.hidpi_factor();
+    let font_size = (13.0 * hidpi_factor) as f32;
+    gui.imgui.io_mut().font_global_scale = (1.0 / hidpi_factor) as f32;
+    gui.imgui.fonts().clear();
+    gui.imgui.fonts().add_font(&[FontSource::DefaultFontData {
+        config: Some(FontConfig {
+            size_pixels: font_size,
+            ..FontConfig::default()
+        }),
+    }]);
+    gui.renderer
+        .reload_font_texture(&mut gui.imgui, &device, &queue)
+        .unwrap();
+
+    let checkerboard: Vec<u8> = (0..64 * 64)
+        .flat_map(|i| {
+            if (i % 64 / 8 + i / 64 / 8) % 2 == 0 {
+                vec![255, 255, 255, 255]
+            } else {
+                vec![40, 120, 200, 255]
+            }
+        })
+        .collect();
+    let checkerboard = gui
+        .renderer
+        .upload_texture(&device, &queue, 64, 64, &checkerboard)
+        .unwrap();
+
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = ControlFlow::Poll;
+        gui.handle_event(&window, &event);
+        match event {
+            Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } => *control_flow = ControlFlow::Exit,
+            // Also sent when the scale factor changes.
+            Event::WindowEvent {
+                event: WindowEvent::Resized(size),
+                ..
+            } => {
+                swap_chain_descriptor.width = size.width;
+                swap_chain_descriptor.height = size.height;
+                // Minimizing resizes to zero, which a swap chain can't have; the next resize
+                // on restore recreates it.
+                if size.width > 0 && size.height > 0 {
+                    swap_chain = device.create_swap_chain(&surface, &swap_chain_descriptor);
+                }
+            }
+            Event::MainEventsCleared => window.request_redraw(),
+            Event::RedrawRequested(_) => {
+                // A minimized window has no swap chain images to draw into.
+                if swap_chain_descriptor.width == 0 || swap_chain_descriptor.height == 0 {
+                    return;
+                }
+                gui.new_frame(&window).unwrap();
+                let frame = match swap_chain.get_current_frame() {
+                    Ok(frame) => frame,
+                    Err(error) => {
+                        eprintln!("dropped frame: {:?}", error);
+                        return;
+                    }
+                };
+                let mut encoder =
+                    device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+                {
+                    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                            attachment: &frame.output.view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color {
+                                    r: 0.1,
+                                    g: 0.2,
+                                    b: 0.3,
+                                    a: 1.0,
+                                }),
+                                store: true,
+                            },
+                        }],
+                        depth_stencil_attachment: None,
+                    });
+                    gui.render(&window, &device, &queue, &mut render_pass, |ui| {
+                        Window::new(im_str!("Hello"))
+                            .size([320.0, 240.0], Condition::FirstUseEver)
+                            .build(ui, || {
+                                ui.text(format!(
+                                    "{:.1} fps ({:.2} ms)",
+                                    ui.io().framerate,
+                                    1000.0 / ui.io().framerate
+                                ));
+                                ui.separator();
+                                Image::new(checkerboard, [128.0, 128.0]).build(ui);
+                            });
+                        ui.show_demo_window(&mut true);
+                    })
+                    .unwrap();
+                }
+                queue.submit(Some(encoder.finish()));
+            }
+            _ => {}
+        }
+    });
+}
@@ -0,0 +1,145 @@
+//! Renders structured random frames: textures of random sizes and contents, and draw lists
+//! whose display rectangle, clip rects, vertex positions and texture ids may be anything,
+//! including negative, NaN, infinite and huge values. Draw command ranges are kept within
+//! their buffers, as imgui guarantees, so the renderer's own math is what gets exercised.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use imgui::DrawVert;
+use imgui_wgpu_rs::{DrawCmdSnapshot, DrawDataSnapshot, DrawListSnapshot};
+use imgui_wgpu_rs_fuzz::Harness;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    textures: Vec<TextureInput>,
+    display_pos: [f32; 2],
+    display_size: [f32; 2],
+    framebuffer_scale: [f32; 2],
+    draw_lists: Vec<DrawListInput>,
+}
+
+#[derive(Arbitrary, Debug)]
+struct TextureInput {
+    width: u8,
+    height: u8,
+    pixels: Vec<u8>,
+    // Pads or truncates `pixels` to the texture's size, so uploads usually succeed.
+    fit_pixels: bool,
+}
+
+#[derive(Arbitrary, Debug)]
+struct DrawListInput {
+    vertices: Vec<([f32; 2], [f32; 2], [u8; 4])>,
+    indices: Vec<u16>,
+    commands: Vec<CommandInput>,
+}
+
+#[derive(Arbitrary, Debug)]
+enum CommandInput {
+    Elements {
+        count: u16,
+        clip_rect: [f32; 4],
+        texture: TextureRef,
+        vtx_offset: u16,
+        idx_offset: u16,
+    },
+    ResetRenderState,
+}
+
+#[derive(Arbitrary, Debug)]
+enum TextureRef {
+    // One of the textures uploaded for this input.
+    Uploaded(u8),
+    // Any id, most likely one the renderer doesn't know.
+    Raw(usize),
+}
+
+// Turns a draw list input into one whose commands stay within its buffers, rewriting the
+// indices they cover to fit their vertex offset.
+fn draw_list(input: DrawListInput, texture_ids: &[imgui::TextureId]) -> DrawListSnapshot {
+    let vtx_buffer: Vec<DrawVert> = input
+        .vertices
+        .into_iter()
+        .map(|(pos, uv, col)| DrawVert { pos, uv, col })
+        .collect();
+    let mut idx_buffer = input.indices;
+    let mut commands = Vec::new();
+    for command in input.commands {
+        match command {
+            CommandInput::Elements {
+                count,
+                clip_rect,
+                texture,
+                vtx_offset,
+                idx_offset,
+            } => {
+                if vtx_buffer.is_empty() {
+                    continue;
+                }
+                let vtx_offset = vtx_offset as usize % vtx_buffer.len();
+                let idx_offset = idx_offset as usize % (idx_buffer.len() + 1);
+                let count = (count as usize).min(idx_buffer.len() - idx_offset);
+                let vertices = (vtx_buffer.len() - vtx_offset).min(u16::MAX as usize + 1);
+                // Taking the remainder never raises an index, so earlier commands sharing
+                // these indices stay in range.
+                for index in &mut idx_buffer[idx_offset..idx_offset + count] {
+                    *index = (*index as usize % vertices) as u16;
+                }
+                let texture_id = match texture {
+                    TextureRef::Uploaded(index) if !texture_ids.is_empty() => {
+                        texture_ids[index as usize % texture_ids.len()]
+                    }
+                    TextureRef::Uploaded(_) => imgui::TextureId::new(0),
+                    TextureRef::Raw(id) => imgui::TextureId::new(id),
+                };
+                commands.push(DrawCmdSnapshot::Elements {
+                    count,
+                    clip_rect,
+                    texture_id,
+                    vtx_offset,
+                    idx_offset,
+                });
+            }
+            CommandInput::ResetRenderState => commands.push(DrawCmdSnapshot::ResetRenderState),
+        }
+    }
+    DrawListSnapshot {
+        vtx_buffer,
+        idx_buffer,
+        commands,
+    }
+}
+
+fuzz_target!(|input: Input| {
+    Harness::with(|harness| {
+        let mut texture_ids = Vec::new();
+        for texture in input.textures {
+            let (width, height) = (texture.width as u32, texture.height as u32);
+            let mut pixels = texture.pixels;
+            if texture.fit_pixels {
+                pixels.resize(width as usize * height as usize * 4, 0);
+            }
+            if let Ok(texture_id) = harness.upload_texture(width, height, &pixels) {
+                texture_ids.push(texture_id);
+            }
+        }
+        let snapshot = DrawDataSnapshot {
+            display_pos: input.display_pos,
+            display_size: input.display_size,
+            framebuffer_scale: input.framebuffer_scale,
+            draw_lists: input
+                .draw_lists
+                .into_iter()
+                .map(|input| draw_list(input, &texture_ids))
+                .collect(),
+        };
+        harness.render(|renderer, device, queue, render_pass| {
+            renderer.render_snapshot(device, queue, render_pass, &snapshot)
+        });
+        for texture_id in texture_ids {
+            harness.remove_texture(texture_id);
+        }
+    });
+});
@@ -0,0 +1,15 @@
+//! Decodes arbitrary bytes as a serialized frame and renders whatever decodes, covering
+//! `DrawDataSnapshot::read_from` along with the renderer.
+
+#![no_main]
+
+use imgui_wgpu_rs_fuzz::Harness;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|bytes: &[u8]| {
+    Harness::with(|harness| {
+        harness.render(|renderer, device, queue, render_pass| {
+            renderer.render_serialized(device, queue, render_pass, bytes)
+        });
+    });
+});
@@ -0,0 +1,123 @@
+//! The headless device and renderer the fuzz targets share. Run a target with
+//! `cargo fuzz run draw_data` or `cargo fuzz run snapshot` from the repository root; both need
+//! an adapter, e.g. a software Vulkan driver such as lavapipe on CI.
+//!
+//! wgpu panics on validation errors, so libFuzzer reports them like any other crash.
+
+use imgui_wgpu_rs::{Renderer, RendererConfig, RendererResult};
+use std::cell::RefCell;
+
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+// Small, so every iteration stays fast; the draw data's display size is independent of it.
+const TARGET_SIZE: u32 = 64;
+
+pub struct Harness {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    target: wgpu::TextureView,
+    renderer: Renderer,
+}
+impl Harness {
+    fn new() -> Option<Self> {
+        let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::Default,
+            compatible_surface: None,
+        }))?;
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                features: wgpu::Features::empty(),
+                limits: wgpu::Limits::default(),
+                shader_validation: true,
+            },
+            None,
+        ))
+        .ok()?;
+        let target = device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: None,
+                size: wgpu::Extent3d {
+                    width: TARGET_SIZE,
+                    height: TARGET_SIZE,
+                    depth: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: FORMAT,
+                usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+            })
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        // No imgui context is involved, so there is no font atlas to upload.
+        let renderer = Renderer::without_fonts(&device, RendererConfig::new(FORMAT));
+        Some(Self {
+            device,
+            queue,
+            target,
+            renderer,
+        })
+    }
+    /// Runs `f` with the harness of this thread, created on first use. Panics without an
+    /// adapter, since fuzzing without one would only report false successes.
+    pub fn with<T, F: FnOnce(&mut Harness) -> T>(f: F) -> T {
+        thread_local! {
+            static HARNESS: RefCell<Option<Harness>> = const { RefCell::new(None) };
+        }
+        HARNESS.with(|harness| {
+            let mut harness = harness.borrow_mut();
+            let harness = harness
+                .get_or_insert_with(|| Harness::new().expect("no adapter to fuzz the renderer on"));
+            f(harness)
+        })
+    }
+    /// Uploads tightly packed RGBA pixels, passing on the renderer's validation errors.
+    pub fn upload_texture(
+        &mut self,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+    ) -> RendererResult<imgui::TextureId> {
+        self.renderer
+            .upload_texture(&self.device, &self.queue, width, height, pixels)
+    }
+    pub fn remove_texture(&mut self, texture_id: imgui::TextureId) {
+        let _ = self.renderer.remove_texture(texture_id);
+    }
+    /// Records `draw` into a render pass on the target, submits it and waits for the GPU, so
+    /// validation errors surface within the iteration that caused them. Errors returned by
+    /// the renderer are expected for malformed input and ignored.
+    pub fn render<F>(&mut self, draw: F)
+    where
+        F: for<'a> FnOnce(
+            &'a mut Renderer,
+            &wgpu::Device,
+            &wgpu::Queue,
+            &mut wgpu::RenderPass<'a>,
+        ) -> RendererResult<()>,
+    {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: &self.target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+            let _ = draw(
+                &mut self.renderer,
+                &self.device,
+                &self.queue,
+                &mut render_pass,
+            );
+        }
+        self.queue.submit(Some(encoder.finish()));
+        self.device.poll(wgpu::Maintain::Wait);
+    }
+}
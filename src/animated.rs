@@ -0,0 +1,232 @@
+use crate::{Renderer, RendererResult};
+use std::time::Duration;
+
+/// Why `AnimatedTexture::decode` failed.
+#[cfg(feature = "animated")]
+#[derive(Debug)]
+pub enum AnimationError {
+    Image(image::ImageError),
+    Renderer(crate::RendererError),
+    NoFrames,
+}
+#[cfg(feature = "animated")]
+impl std::fmt::Display for AnimationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnimationError::Image(error) => write!(f, "failed to decode animation: {}", error),
+            AnimationError::Renderer(error) => error.fmt(f),
+            AnimationError::NoFrames => write!(f, "animation has no frames"),
+        }
+    }
+}
+#[cfg(feature = "animated")]
+impl std::error::Error for AnimationError {}
+#[cfg(feature = "animated")]
+impl From<image::ImageError> for AnimationError {
+    fn from(error: image::ImageError) -> Self {
+        AnimationError::Image(error)
+    }
+}
+#[cfg(feature = "animated")]
+impl From<crate::RendererError> for AnimationError {
+    fn from(error: crate::RendererError) -> Self {
+        AnimationError::Renderer(error)
+    }
+}
+
+/// One frame of an `AnimatedTexture`: tightly packed RGBA pixels covering the whole image.
+pub struct AnimationFrame {
+    pub pixels: Vec<u8>,
+    pub delay: Duration,
+}
+
+/// A texture that cycles through the frames of an animation under a single id. Frames stay in
+/// memory and are streamed to the GPU as they come up, uploading only the rectangle that
+/// differs from the frame on screen.
+pub struct AnimatedTexture {
+    texture_id: imgui::TextureId,
+    width: u32,
+    height: u32,
+    frames: Vec<AnimationFrame>,
+    current: usize,
+    // Time already spent on the current frame.
+    elapsed: Duration,
+}
+impl AnimatedTexture {
+    /// Uploads the first of `frames`, which must each hold `width * height * 4` bytes. Fails
+    /// with `RendererError::EmptyTexture` if there are no frames.
+    pub fn new(
+        renderer: &mut Renderer,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        frames: Vec<AnimationFrame>,
+    ) -> RendererResult<Self> {
+        if frames.is_empty() {
+            return Err(crate::RendererError::EmptyTexture);
+        }
+        crate::validate_texture_size(width, height)?;
+        let expected = width as usize * height as usize * 4;
+        if let Some(frame) = frames.iter().find(|frame| frame.pixels.len() != expected) {
+            return Err(crate::RendererError::TextureDataSize {
+                expected,
+                actual: frame.pixels.len(),
+            });
+        }
+        let texture_id =
+            renderer.upload_texture(device, queue, width, height, &frames[0].pixels)?;
+        Ok(Self {
+            texture_id,
+            width,
+            height,
+            frames,
+            current: 0,
+            elapsed: Duration::default(),
+        })
+    }
+    /// Decodes an animated GIF or APNG; any other image becomes a single frame.
+    #[cfg(feature = "animated")]
+    pub fn decode(
+        renderer: &mut Renderer,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bytes: &[u8],
+    ) -> Result<Self, AnimationError> {
+        use image::AnimationDecoder;
+
+        let animation = match image::guess_format(bytes)? {
+            image::ImageFormat::Gif => {
+                Some(image::codecs::gif::GifDecoder::new(bytes)?.into_frames())
+            }
+            image::ImageFormat::Png => {
+                let decoder = image::codecs::png::PngDecoder::new(bytes)?;
+                if decoder.is_apng() {
+                    Some(decoder.apng().into_frames())
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+        let decoded = match animation {
+            Some(frames) => frames,
+            None => {
+                let image = image::load_from_memory(bytes)?.into_rgba8();
+                image::Frames::new(Box::new(std::iter::once(Ok(image::Frame::new(image)))))
+            }
+        };
+        let mut size = (0, 0);
+        let mut frames = Vec::new();
+        for frame in decoded {
+            let frame = frame?;
+            let (numerator, denominator) = frame.delay().numer_denom_ms();
+            let delay = match numerator / denominator.max(1) {
+                // Browsers show frames without a meaningful delay for 100ms.
+                0..=10 => Duration::from_millis(100),
+                millis => Duration::from_millis(millis as u64),
+            };
+            let image = frame.into_buffer();
+            size = image.dimensions();
+            frames.push(AnimationFrame {
+                pixels: image.into_raw(),
+                delay,
+            });
+        }
+        if frames.is_empty() {
+            return Err(AnimationError::NoFrames);
+        }
+        Ok(Self::new(renderer, device, queue, size.0, size.1, frames)?)
+    }
+    pub fn texture_id(&self) -> imgui::TextureId {
+        self.texture_id
+    }
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+    /// The index of the frame on screen.
+    pub fn frame(&self) -> usize {
+        self.current
+    }
+    /// Moves the animation `dt` forward, looping at the end, and uploads the new frame if it
+    /// changed.
+    pub fn advance(&mut self, renderer: &Renderer, queue: &wgpu::Queue, dt: Duration) {
+        let total: Duration = self.frames.iter().map(|frame| frame.delay).sum();
+        if total == Duration::default() {
+            return;
+        }
+        let mut next = self.current;
+        // Whole loops change nothing, so skip them before stepping through the frames.
+        let mut elapsed =
+            Duration::from_nanos(((self.elapsed + dt).as_nanos() % total.as_nanos()) as u64);
+        while elapsed >= self.frames[next].delay {
+            elapsed -= self.frames[next].delay;
+            next = (next + 1) % self.frames.len();
+        }
+        self.elapsed = elapsed;
+        self.show(renderer, queue, next);
+    }
+    /// Shows frame `index` right away, restarting its delay.
+    pub fn set_frame(&mut self, renderer: &Renderer, queue: &wgpu::Queue, index: usize) {
+        self.elapsed = Duration::default();
+        self.show(renderer, queue, index % self.frames.len());
+    }
+    fn show(&mut self, renderer: &Renderer, queue: &wgpu::Queue, index: usize) {
+        if index == self.current {
+            return;
+        }
+        let previous = &self.frames[self.current].pixels;
+        let pixels = &self.frames[index].pixels;
+        self.current = index;
+        let (x, y, width, height) = match changed_rect(previous, pixels, self.width) {
+            Some(rect) => rect,
+            None => return,
+        };
+        // The texture was removed, e.g. by the texture GC.
+        let texture = match renderer.textures.get(self.texture_id) {
+            Some(texture) => texture,
+            None => return,
+        };
+        let row_bytes = (self.width * 4) as usize;
+        let mut region = Vec::with_capacity((width * height * 4) as usize);
+        for row in pixels
+            .chunks(row_bytes)
+            .skip(y as usize)
+            .take(height as usize)
+        {
+            region.extend_from_slice(&row[(x * 4) as usize..((x + width) * 4) as usize]);
+        }
+        texture.write_region(queue, x, y, width, height, &region);
+    }
+}
+
+// The bounding box of the pixels that differ between two frames, as `(x, y, width, height)`.
+fn changed_rect(a: &[u8], b: &[u8], width: u32) -> Option<(u32, u32, u32, u32)> {
+    let mut min = (u32::MAX, u32::MAX);
+    let mut max = (0, 0);
+    let row_bytes = (width * 4) as usize;
+    for (y, (row_a, row_b)) in a.chunks(row_bytes).zip(b.chunks(row_bytes)).enumerate() {
+        if row_a == row_b {
+            continue;
+        }
+        let first = row_a
+            .chunks(4)
+            .zip(row_b.chunks(4))
+            .position(|(a, b)| a != b)
+            .unwrap_or(0) as u32;
+        let last = row_a
+            .chunks(4)
+            .zip(row_b.chunks(4))
+            .rposition(|(a, b)| a != b)
+            .unwrap_or(0) as u32;
+        min = (min.0.min(first), min.1.min(y as u32));
+        max = (max.0.max(last), y as u32);
+    }
+    if min.1 == u32::MAX {
+        return None;
+    }
+    Some((min.0, min.1, max.0 - min.0 + 1, max.1 - min.1 + 1))
+}
@@ -0,0 +1,163 @@
+use crate::{Frame, RendererError, RendererResult, Texture};
+use std::collections::HashMap;
+
+/// Device features `RendererConfig::bindless` needs.
+pub const BINDLESS_FEATURES: wgpu::Features = wgpu::Features::from_bits_truncate(
+    wgpu::Features::SAMPLED_TEXTURE_BINDING_ARRAY.bits()
+        | wgpu::Features::SAMPLED_TEXTURE_ARRAY_NON_UNIFORM_INDEXING.bits(),
+);
+
+// Must match the array size in imgui.vert and imgui.frag.
+pub(crate) const TEXTURE_ARRAY_SIZE: u32 = 256;
+
+/// The textures drawn by recent frames, bound together as one array so draw commands select
+/// their texture through the instance index instead of a bind group change. Slots are kept
+/// across frames, and the bind group is only rebuilt when a frame draws a texture without one.
+pub(crate) struct TextureArray {
+    layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    // Fills the unused slots, since the bind group must provide every array element.
+    placeholder: wgpu::Texture,
+    slots: HashMap<imgui::TextureId, u32>,
+    bind_group: Option<wgpu::BindGroup>,
+}
+impl TextureArray {
+    pub(crate) fn new(device: &wgpu::Device) -> Self {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::SampledTexture {
+                        multisampled: false,
+                        component_type: wgpu::TextureComponentType::Float,
+                        dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: std::num::NonZeroU32::new(TEXTURE_ARRAY_SIZE),
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler { comparison: false },
+                    count: None,
+                },
+            ],
+        });
+        let sampler = device.create_sampler(&crate::linear_sampler_descriptor());
+        let placeholder = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsage::SAMPLED,
+        });
+        Self {
+            layout,
+            sampler,
+            placeholder,
+            slots: HashMap::new(),
+            bind_group: None,
+        }
+    }
+    pub(crate) fn layout(&self) -> &wgpu::BindGroupLayout {
+        &self.layout
+    }
+    /// Forgets all slots, e.g. after a texture was replaced under an existing id.
+    pub(crate) fn invalidate(&mut self) {
+        self.slots.clear();
+        self.bind_group = None;
+    }
+    /// Gives every texture drawn by `frames` a slot, rebuilding the bind group if needed.
+    pub(crate) fn update<'f>(
+        &mut self,
+        device: &wgpu::Device,
+        frames: impl Iterator<Item = &'f Frame<'f>>,
+        textures: &imgui::Textures<Texture>,
+    ) -> RendererResult<()> {
+        let mut used = Vec::new();
+        for draw_cmd in frames
+            .flat_map(|frame| frame.draw_lists.iter())
+            .flat_map(|draw_list| draw_list.commands())
+        {
+            if let imgui::DrawCmd::Elements { cmd_params, .. } = draw_cmd {
+                if !used.contains(&cmd_params.texture_id) {
+                    used.push(cmd_params.texture_id);
+                }
+            }
+        }
+        let missing = used
+            .iter()
+            .filter(|texture_id| !self.slots.contains_key(texture_id))
+            .count();
+        if missing == 0 && self.bind_group.is_some() {
+            return Ok(());
+        }
+        // Rebuilding renumbers the slots, dropping textures that were removed since.
+        let mut ids: Vec<imgui::TextureId> = self
+            .slots
+            .keys()
+            .copied()
+            .filter(|&texture_id| textures.get(texture_id).is_some() && !used.contains(&texture_id))
+            .collect();
+        if ids.len() + used.len() > TEXTURE_ARRAY_SIZE as usize {
+            // Start over with only the textures this frame needs.
+            ids.clear();
+        }
+        if used.len() > TEXTURE_ARRAY_SIZE as usize {
+            return Err(RendererError::TooManyTextures);
+        }
+        ids.extend(used);
+
+        self.slots.clear();
+        let mut views = Vec::with_capacity(TEXTURE_ARRAY_SIZE as usize);
+        for (slot, texture_id) in ids.into_iter().enumerate() {
+            let texture = textures
+                .get(texture_id)
+                .and_then(Texture::texture)
+                .ok_or(RendererError::BadTexture(texture_id))?;
+            views.push(texture.create_view(&wgpu::TextureViewDescriptor::default()));
+            self.slots.insert(texture_id, slot as u32);
+        }
+        while views.len() < TEXTURE_ARRAY_SIZE as usize {
+            views.push(
+                self.placeholder
+                    .create_view(&wgpu::TextureViewDescriptor::default()),
+            );
+        }
+        self.bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureViewArray(&views),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        }));
+        Ok(())
+    }
+    /// The bind group of the last `update`.
+    pub(crate) fn bind_group(&self) -> &wgpu::BindGroup {
+        self.bind_group
+            .as_ref()
+            .expect("texture array used before update")
+    }
+    /// Fails for a texture dropped from the array since, which a cached frame may still use.
+    pub(crate) fn slot(&self, texture_id: imgui::TextureId) -> RendererResult<u32> {
+        self.slots
+            .get(&texture_id)
+            .copied()
+            .ok_or(RendererError::BadTexture(texture_id))
+    }
+}
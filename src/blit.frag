@@ -0,0 +1,13 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+layout(location = 0) in vec2 fragUv;
+layout(location = 0) out vec4 outColor;
+
+layout(set = 0, binding = 0) uniform texture2D ui;
+layout(set = 0, binding = 1) uniform sampler uiSampler;
+
+// The UI was rendered over transparent black, so the texture holds premultiplied alpha.
+void main() {
+    outColor = texture(sampler2D(ui, uiSampler), fragUv);
+}
@@ -0,0 +1,22 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+layout(location = 0) in vec2 fragUv;
+layout(location = 0) out vec4 outColor;
+
+layout(set = 0, binding = 0) uniform texture2D source;
+layout(set = 0, binding = 1) uniform sampler sourceSampler;
+layout(set = 0, binding = 2) uniform BlurPass {
+    vec2 step;
+    float gamma;
+} blur;
+
+// One direction of a 9-tap gaussian, folded into 5 fetches by sampling between texels.
+void main() {
+    vec4 color = texture(sampler2D(source, sourceSampler), fragUv) * 0.2270270270;
+    color += texture(sampler2D(source, sourceSampler), fragUv + blur.step * 1.3846153846) * 0.3162162162;
+    color += texture(sampler2D(source, sourceSampler), fragUv - blur.step * 1.3846153846) * 0.3162162162;
+    color += texture(sampler2D(source, sourceSampler), fragUv + blur.step * 3.2307692308) * 0.0702702703;
+    color += texture(sampler2D(source, sourceSampler), fragUv - blur.step * 3.2307692308) * 0.0702702703;
+    outColor = vec4(pow(color.rgb, vec3(1.0 / blur.gamma)), color.a);
+}
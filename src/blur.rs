@@ -0,0 +1,262 @@
+use crate::fullscreen_pass;
+use crate::shaders::Shader;
+use crate::{RenderTarget, Renderer, Texture};
+
+// The horizontal pass writes here, in linear color with headroom against banding.
+const INTERMEDIATE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct BlurPass {
+    step: [f32; 2],
+    gamma: f32,
+    _padding: f32,
+}
+
+unsafe impl bytemuck::Zeroable for BlurPass {}
+
+unsafe impl bytemuck::Pod for BlurPass {}
+
+struct BlurTargets {
+    size: (u32, u32),
+    intermediate: wgpu::TextureView,
+    intermediate_bind_group: wgpu::BindGroup,
+    horizontal_uniforms: wgpu::Buffer,
+    vertical_uniforms: wgpu::Buffer,
+    texture_id: imgui::TextureId,
+}
+
+/// Blurs the scene behind the UI into a texture that windows can use as a frosted-glass
+/// background. Draw `texture_id` behind a window, e.g. with the window draw list's
+/// `add_image` before its contents, with UVs of the window's rectangle divided by the display
+/// size.
+pub struct BackgroundBlur {
+    scene_gamma: f32,
+    downsample: u32,
+    spread: f32,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    horizontal: wgpu::RenderPipeline,
+    vertical: wgpu::RenderPipeline,
+    targets: Option<BlurTargets>,
+}
+impl BackgroundBlur {
+    /// `scene_format` is the format of the scene views passed to `update`. The scene is
+    /// blurred at `1 / downsample` of its resolution, and `spread` scales the blur radius in
+    /// downsampled texels.
+    pub fn new(
+        device: &wgpu::Device,
+        scene_format: wgpu::TextureFormat,
+        downsample: u32,
+        spread: f32,
+    ) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::SampledTexture {
+                        multisampled: false,
+                        component_type: wgpu::TextureComponentType::Float,
+                        dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler { comparison: false },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::UniformBuffer {
+                        dynamic: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let create_pipeline = |format| {
+            fullscreen_pass::pipeline(device, &bind_group_layout, Shader::BlurFragment, format)
+        };
+        let horizontal = create_pipeline(INTERMEDIATE_FORMAT);
+        let vertical = create_pipeline(crate::upload_descriptor(1, 1).format);
+        Self {
+            scene_gamma: RenderTarget {
+                format: scene_format,
+                sample_count: 1,
+            }
+            .gamma(),
+            downsample: downsample.max(1),
+            spread,
+            bind_group_layout,
+            sampler: device.create_sampler(&crate::linear_sampler_descriptor()),
+            horizontal,
+            vertical,
+            targets: None,
+        }
+    }
+    /// The blurred scene, once `update` has run.
+    pub fn texture_id(&self) -> Option<imgui::TextureId> {
+        self.targets.as_ref().map(|targets| targets.texture_id)
+    }
+    pub fn set_spread(&mut self, spread: f32) {
+        self.spread = spread;
+    }
+    /// Records the blur of `scene` into `encoder`, before the UI is rendered. `scene` must
+    /// have `SAMPLED` usage, so render the scene into an off-screen texture rather than the
+    /// swapchain image. Returns the id of the blurred texture, which keeps its id across
+    /// resizes unless the texture GC evicts it.
+    pub fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        scene: &wgpu::TextureView,
+        scene_size: (u32, u32),
+    ) -> imgui::TextureId {
+        let size = (
+            (scene_size.0 / self.downsample).max(1),
+            (scene_size.1 / self.downsample).max(1),
+        );
+        let current = self.targets.as_ref().filter(|targets| {
+            targets.size == size && renderer.textures.get(targets.texture_id).is_some()
+        });
+        if current.is_none() {
+            self.targets = Some(self.create_targets(renderer, device, size));
+        }
+        let targets = self.targets.as_ref().unwrap();
+
+        let step = |x, y| BlurPass {
+            step: [
+                x * self.spread / size.0 as f32,
+                y * self.spread / size.1 as f32,
+            ],
+            gamma: 1.0,
+            _padding: 0.0,
+        };
+        queue.write_buffer(
+            &targets.horizontal_uniforms,
+            0,
+            bytemuck::bytes_of(&step(1.0, 0.0)),
+        );
+        queue.write_buffer(
+            &targets.vertical_uniforms,
+            0,
+            bytemuck::bytes_of(&BlurPass {
+                gamma: self.scene_gamma,
+                ..step(0.0, 1.0)
+            }),
+        );
+
+        let scene_bind_group = self.bind_group(device, scene, &targets.horizontal_uniforms);
+        let output = renderer
+            .textures
+            .get(targets.texture_id)
+            .and_then(Texture::view)
+            .expect("blur target is registered");
+        for (pipeline, bind_group, view) in [
+            (&self.horizontal, &scene_bind_group, &targets.intermediate),
+            (&self.vertical, &targets.intermediate_bind_group, output),
+        ]
+        .iter()
+        {
+            fullscreen_pass::draw(
+                encoder,
+                view,
+                wgpu::Color::TRANSPARENT,
+                pipeline,
+                bind_group,
+            );
+        }
+        targets.texture_id
+    }
+    fn create_targets(
+        &self,
+        renderer: &mut Renderer,
+        device: &wgpu::Device,
+        size: (u32, u32),
+    ) -> BlurTargets {
+        let extent = wgpu::Extent3d {
+            width: size.0,
+            height: size.1,
+            depth: 1,
+        };
+        let intermediate = device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: None,
+                size: extent,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: INTERMEDIATE_FORMAT,
+                usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT | wgpu::TextureUsage::SAMPLED,
+            })
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let uniforms = || {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: std::mem::size_of::<BlurPass>() as u64,
+                usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+        let horizontal_uniforms = uniforms();
+        let vertical_uniforms = uniforms();
+        let intermediate_bind_group = self.bind_group(device, &intermediate, &vertical_uniforms);
+
+        let mut descriptor = crate::upload_descriptor(size.0, size.1);
+        descriptor.usage |= wgpu::TextureUsage::OUTPUT_ATTACHMENT;
+        let texture = Texture::with_sampler(
+            device,
+            renderer.texture_layout(),
+            &descriptor,
+            &self.sampler,
+        );
+        let texture_id = match &self.targets {
+            Some(targets) if renderer.textures.get(targets.texture_id).is_some() => {
+                renderer.replace_texture(targets.texture_id, texture);
+                targets.texture_id
+            }
+            _ => renderer.insert_texture(texture),
+        };
+        BlurTargets {
+            size,
+            intermediate,
+            intermediate_bind_group,
+            horizontal_uniforms,
+            vertical_uniforms,
+            texture_id,
+        }
+    }
+    fn bind_group(
+        &self,
+        device: &wgpu::Device,
+        source: &wgpu::TextureView,
+        uniforms: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(source),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Buffer(uniforms.slice(..)),
+                },
+            ],
+        })
+    }
+}
@@ -0,0 +1,64 @@
+use imgui::sys;
+use std::os::raw::c_void;
+
+/// Custom wgpu drawing injected between UI elements with `push_draw_callback`.
+///
+/// Resources borrowed from `self` live as long as the render pass, so implementors can bind
+/// their own pipelines and buffers. The renderer restores its own state afterwards.
+pub trait DrawCallback {
+    fn draw<'r>(
+        &'r self,
+        render_pass: &mut wgpu::RenderPass<'r>,
+        cmd_params: &imgui::DrawCmdParams,
+    );
+}
+impl<F> DrawCallback for F
+where
+    F: Fn(&mut wgpu::RenderPass, &imgui::DrawCmdParams),
+{
+    fn draw<'r>(
+        &'r self,
+        render_pass: &mut wgpu::RenderPass<'r>,
+        cmd_params: &imgui::DrawCmdParams,
+    ) {
+        self(render_pass, cmd_params)
+    }
+}
+
+/// Adds a command to the current window's draw list that invokes the callback registered
+/// with `Renderer::add_draw_callback` under `id`.
+pub fn push_draw_callback(_ui: &imgui::Ui, id: usize) {
+    unsafe {
+        sys::ImDrawList_AddCallback(
+            sys::igGetWindowDrawList(),
+            Some(draw_callback),
+            id as *mut c_void,
+        );
+    }
+}
+
+// Never called; the renderer recognizes commands carrying this function and dispatches them.
+unsafe extern "C" fn draw_callback(_: *const sys::ImDrawList, _: *const sys::ImDrawCmd) {}
+
+/// Returns the registered callback id if `callback` was added with `push_draw_callback`.
+pub(crate) unsafe fn draw_callback_id(
+    callback: unsafe extern "C" fn(*const sys::ImDrawList, *const sys::ImDrawCmd),
+    raw_cmd: *const sys::ImDrawCmd,
+) -> Option<(usize, imgui::DrawCmdParams)> {
+    if callback as usize != draw_callback as *const () as usize {
+        return None;
+    }
+    let raw_cmd = &*raw_cmd;
+    let cmd_params = imgui::DrawCmdParams {
+        clip_rect: [
+            raw_cmd.ClipRect.x,
+            raw_cmd.ClipRect.y,
+            raw_cmd.ClipRect.z,
+            raw_cmd.ClipRect.w,
+        ],
+        texture_id: imgui::TextureId::new(raw_cmd.TextureId as usize),
+        vtx_offset: raw_cmd.VtxOffset as usize,
+        idx_offset: raw_cmd.IdxOffset as usize,
+    };
+    Some((raw_cmd.UserCallbackData as usize, cmd_params))
+}
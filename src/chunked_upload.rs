@@ -0,0 +1,121 @@
+use crate::{Renderer, RendererError, RendererResult};
+use wgpu::util::DeviceExt;
+
+/// A texture whose pixels are copied a few rows at a time, so a huge image doesn't stall a
+/// single frame. Created with `Renderer::begin_chunked_upload`; the texture id is valid right
+/// away, but its contents are undefined until `is_complete` returns true.
+pub struct ChunkedUpload {
+    texture_id: imgui::TextureId,
+    pixels: Vec<u8>,
+    width: u32,
+    height: u32,
+    rows_per_chunk: u32,
+    next_row: u32,
+}
+impl ChunkedUpload {
+    pub fn texture_id(&self) -> imgui::TextureId {
+        self.texture_id
+    }
+    /// The fraction of rows uploaded so far, from 0.0 to 1.0.
+    pub fn progress(&self) -> f32 {
+        self.next_row as f32 / self.height as f32
+    }
+    pub fn is_complete(&self) -> bool {
+        self.next_row == self.height
+    }
+    /// Records the copy of the next chunk of rows into `encoder`; call once per frame until
+    /// the upload is complete.
+    pub fn upload_next(
+        &mut self,
+        renderer: &mut Renderer,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+    ) -> RendererResult<()> {
+        if self.is_complete() {
+            return Ok(());
+        }
+        let storage = renderer
+            .textures
+            .get(self.texture_id)
+            .and_then(|texture| texture.storage.as_ref())
+            .ok_or(RendererError::BadTexture(self.texture_id))?;
+        let rows = self.rows_per_chunk.min(self.height - self.next_row);
+        let row_bytes = self.width * 4;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_row_bytes = row_bytes.div_ceil(align) * align;
+        let mut staging = vec![0; (padded_row_bytes * rows) as usize];
+        let first_byte = (self.next_row * row_bytes) as usize;
+        for (padded_row, row) in staging
+            .chunks_mut(padded_row_bytes as usize)
+            .zip(self.pixels[first_byte..].chunks(row_bytes as usize))
+        {
+            padded_row[..row.len()].copy_from_slice(row);
+        }
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: &staging,
+            usage: wgpu::BufferUsage::COPY_SRC,
+        });
+        encoder.copy_buffer_to_texture(
+            wgpu::BufferCopyView {
+                buffer: &buffer,
+                layout: wgpu::TextureDataLayout {
+                    offset: 0,
+                    bytes_per_row: padded_row_bytes,
+                    rows_per_image: rows,
+                },
+            },
+            wgpu::TextureCopyView {
+                texture: &storage.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: 0,
+                    y: self.next_row,
+                    z: 0,
+                },
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: rows,
+                depth: 1,
+            },
+        );
+        self.next_row += rows;
+        if self.is_complete() {
+            self.pixels = Vec::new();
+            renderer.texture_gc.track(self.texture_id);
+        }
+        Ok(())
+    }
+}
+
+impl Renderer {
+    /// Registers a `width` x `height` RGBA texture to be filled from `pixels`
+    /// `rows_per_chunk` rows at a time by `ChunkedUpload::upload_next`.
+    pub fn begin_chunked_upload(
+        &mut self,
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+        rows_per_chunk: u32,
+    ) -> RendererResult<ChunkedUpload> {
+        crate::validate_texture_size(width, height)?;
+        let expected = width as usize * height as usize * 4;
+        if pixels.len() != expected {
+            return Err(RendererError::TextureDataSize {
+                expected,
+                actual: pixels.len(),
+            });
+        }
+        let texture = self.allocate_texture(device, width, height);
+        Ok(ChunkedUpload {
+            texture_id: self.textures.insert(texture),
+            pixels,
+            width,
+            height,
+            rows_per_chunk: rows_per_chunk.max(1),
+            next_row: 0,
+        })
+    }
+}
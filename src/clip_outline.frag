@@ -0,0 +1,9 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+layout(location = 0) out vec4 outColor;
+
+// Drawn through one-pixel scissor strips along the edges of each clip rect.
+void main() {
+    outColor = vec4(1.0, 0.0, 1.0, 1.0);
+}
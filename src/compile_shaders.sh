@@ -0,0 +1,38 @@
+#!/bin/sh
+# Regenerates the SPIR-V checked in next to this script from the GLSL sources, one output per
+# `Shader` variant in shaders.rs. Needs `glslangValidator` from the Vulkan SDK or glslang.
+set -e
+cd "$(dirname "$0")"
+
+compile() {
+    source=$1
+    output=$2
+    shift 2
+    glslangValidator -V "$@" -o "$output" "$source"
+}
+
+compile imgui.vert imgui.vert.spv
+compile imgui.vert imgui.push.vert.spv -DPUSH_CONSTANTS
+compile imgui.frag imgui.frag.spv
+compile imgui.frag imgui.push.frag.spv -DPUSH_CONSTANTS
+compile imgui.vert imgui.bindless.vert.spv -DBINDLESS
+compile imgui.frag imgui.bindless.frag.spv -DBINDLESS
+compile imgui.vert imgui.bgra.vert.spv -DBGRA
+compile imgui.vert imgui.push.bgra.vert.spv -DPUSH_CONSTANTS -DBGRA
+compile imgui.vert imgui.bindless.bgra.vert.spv -DBINDLESS -DBGRA
+compile imgui.frag imgui.sdf.frag.spv -DSDF
+compile imgui.frag imgui.push.sdf.frag.spv -DPUSH_CONSTANTS -DSDF
+compile imgui.frag imgui.bindless.sdf.frag.spv -DBINDLESS -DSDF
+compile imgui.frag imgui.checker.frag.spv -DCHECKERBOARD
+compile imgui.frag imgui.push.checker.frag.spv -DPUSH_CONSTANTS -DCHECKERBOARD
+compile imgui.frag imgui.bindless.checker.frag.spv -DBINDLESS -DCHECKERBOARD
+compile fullscreen.vert fullscreen.vert.spv
+compile yuv.frag yuv.frag.spv
+compile blit.frag blit.frag.spv
+compile overdraw.frag overdraw.frag.spv
+compile clip_outline.frag clip_outline.frag.spv
+compile blur.frag blur.frag.spv
+compile depth.frag depth.frag.spv
+compile integer.frag integer.uint.frag.spv
+compile integer.frag integer.sint.frag.spv -DSIGNED
+compile swizzle.frag swizzle.frag.spv
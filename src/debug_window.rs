@@ -0,0 +1,82 @@
+use crate::{DrawCmdSnapshot, Renderer, MAIN_VIEWPORT_ID};
+
+// The longest side of a texture thumbnail, and of the font atlas preview.
+const THUMBNAIL_SIZE: f32 = 64.0;
+const FONT_ATLAS_SIZE: f32 = 256.0;
+
+impl Renderer {
+    /// Draws a window with the renderer's buffer usage, the draw calls of the last frame
+    /// rendered into the main viewport, the registered textures with thumbnails and the font
+    /// atlases, e.g. behind a debug menu entry.
+    pub fn show_debug_window(&self, ui: &imgui::Ui) {
+        imgui::Window::new(imgui::im_str!("imgui-wgpu renderer")).build(ui, || {
+            let stats = self.buffer_stats();
+            ui.text("Buffers");
+            ui.separator();
+            ui.text(format!(
+                "vertices: {} of {} bytes",
+                stats.vertex_high_water, stats.vertex_capacity
+            ));
+            ui.text(format!(
+                "indices: {} of {} bytes",
+                stats.index_high_water, stats.index_capacity
+            ));
+            ui.text(format!(
+                "chunks: {}, grown {} times",
+                stats.chunks, stats.grow_events
+            ));
+
+            ui.text("Last frame");
+            ui.separator();
+            ui.text(format!("viewports: {}", self.viewports.len()));
+            if let Some(resources) = self.viewports.get(&MAIN_VIEWPORT_ID) {
+                let draw_lists = resources.cached_frame.draw_lists();
+                let draw_calls = draw_lists
+                    .clone()
+                    .flat_map(|draw_list| &draw_list.commands)
+                    .filter(|command| matches!(command, DrawCmdSnapshot::Elements { .. }))
+                    .count();
+                ui.text(format!(
+                    "draw lists: {}, draw calls: {}",
+                    draw_lists.count(),
+                    draw_calls
+                ));
+            }
+
+            let user_textures = self.user_texture_ids();
+            ui.text(format!("Textures ({})", user_textures.len()));
+            ui.separator();
+            for texture_id in user_textures {
+                self.texture_row(ui, texture_id, THUMBNAIL_SIZE);
+            }
+
+            ui.text("Font atlas");
+            ui.separator();
+            for &texture_id in &self.font_textures {
+                self.texture_row(ui, texture_id, FONT_ATLAS_SIZE);
+            }
+        });
+    }
+    // A texture's id and size, followed by the texture scaled to fit `max_size`.
+    fn texture_row(&self, ui: &imgui::Ui, texture_id: imgui::TextureId, max_size: f32) {
+        let size = match self.textures.get(texture_id) {
+            Some(texture) => texture.size(),
+            None => return,
+        };
+        match size {
+            Some(size) => {
+                ui.text(format!(
+                    "{}: {}x{}",
+                    texture_id.id(),
+                    size.width,
+                    size.height
+                ));
+                let scale = max_size / size.width.max(size.height) as f32;
+                let size = [size.width as f32 * scale, size.height as f32 * scale];
+                imgui::Image::new(texture_id, size).build(ui);
+            }
+            // A bind group inserted without its texture, so its size is unknown.
+            None => ui.text(format!("{}: bind group", texture_id.id())),
+        }
+    }
+}
@@ -0,0 +1,24 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+layout(location = 0) in vec2 fragUv;
+layout(location = 0) out vec4 outColor;
+
+layout(set = 0, binding = 0) uniform DepthRange {
+    float near;
+    float far;
+    // Non-zero to map perspective depth back to view distance between near and far.
+    float linearize;
+} range;
+layout(set = 0, binding = 1) uniform sampler depthSampler;
+layout(set = 0, binding = 2) uniform texture2D depth;
+
+void main() {
+    // Fetched rather than sampled, since depth formats can't always be filtered.
+    float d = texelFetch(sampler2D(depth, depthSampler), ivec2(gl_FragCoord.xy), 0).r;
+    if (range.linearize != 0.0) {
+        float distance = range.near * range.far / (range.far - d * (range.far - range.near));
+        d = (distance - range.near) / (range.far - range.near);
+    }
+    outColor = vec4(vec3(clamp(d, 0.0, 1.0)), 1.0);
+}
@@ -0,0 +1,69 @@
+use crate::fullscreen_pass::FullscreenPass;
+use crate::shaders::Shader;
+use crate::Renderer;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct DepthRange {
+    near: f32,
+    far: f32,
+    linearize: f32,
+    _padding: f32,
+}
+
+unsafe impl bytemuck::Zeroable for DepthRange {}
+
+unsafe impl bytemuck::Pod for DepthRange {}
+
+/// Shows a depth buffer with `imgui::Image` by converting it to grayscale, near black and far
+/// white, into a texture registered with the renderer.
+pub struct DepthView {
+    pass: FullscreenPass<DepthRange>,
+}
+impl DepthView {
+    /// `depth` is a view of a `width` x `height` depth texture with `SAMPLED` usage, such as a
+    /// `Depth32Float` one. Create a new `DepthView` when the depth texture is recreated.
+    pub fn new(
+        device: &wgpu::Device,
+        renderer: &mut Renderer,
+        depth: &wgpu::TextureView,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        Self {
+            pass: FullscreenPass::new(
+                device,
+                renderer,
+                depth,
+                wgpu::TextureComponentType::Float,
+                width,
+                height,
+                Shader::DepthFragment,
+            ),
+        }
+    }
+    pub fn texture_id(&self) -> imgui::TextureId {
+        self.pass.texture_id()
+    }
+    /// Records the conversion of the depth buffer's current contents into `encoder`.
+    /// `linearize` takes the `(near, far)` planes of a perspective projection and spreads
+    /// view distances between them evenly; without it the stored depth is shown as is.
+    pub fn update(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        linearize: Option<(f32, f32)>,
+    ) {
+        let (near, far) = linearize.unwrap_or((0.0, 1.0));
+        self.pass.update(
+            queue,
+            encoder,
+            &DepthRange {
+                near,
+                far,
+                linearize: if linearize.is_some() { 1.0 } else { 0.0 },
+                _padding: 0.0,
+            },
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{
+    recycle, DrawCmdSnapshot, DrawDataSnapshot, DrawListSnapshot, Frame, FrameScratch, Layer,
+    RenderTarget, Renderer, RendererResult, Uniforms, MAIN_VIEWPORT_ID,
+};
+
+/// The draw commands of the frame last uploaded for a viewport, with the buffer offsets and
+/// uniforms they were uploaded with, so the frame can be drawn again from the same buffers.
+#[derive(Default)]
+pub(crate) struct CachedFrame {
+    pub(crate) target: Option<RenderTarget>,
+    // The layers' commands; their vertices and indices are only in the uploaded buffers.
+    frames: Vec<DrawDataSnapshot>,
+    pub(crate) offsets: Vec<(usize, u64, u64)>,
+    pub(crate) uniforms: Vec<Uniforms>,
+    pub(crate) clip_quad_bases: Vec<u32>,
+}
+impl CachedFrame {
+    /// Replaces the cached frame, reusing the memory of the previous one.
+    pub(crate) fn record(
+        &mut self,
+        layers: &[Layer],
+        target: RenderTarget,
+        scratch: &FrameScratch,
+    ) {
+        self.target = Some(target);
+        self.frames.resize_with(layers.len(), || DrawDataSnapshot {
+            display_pos: [0.0; 2],
+            display_size: [0.0; 2],
+            framebuffer_scale: [0.0; 2],
+            draw_lists: Vec::new(),
+        });
+        for (cached, layer) in self.frames.iter_mut().zip(layers) {
+            let frame = &layer.frame;
+            cached.display_pos = frame.display_pos;
+            cached.display_size = frame.display_size;
+            cached.framebuffer_scale = frame.framebuffer_scale;
+            cached
+                .draw_lists
+                .resize_with(frame.draw_lists.len(), || DrawListSnapshot {
+                    vtx_buffer: Vec::new(),
+                    idx_buffer: Vec::new(),
+                    commands: Vec::new(),
+                });
+            for (cached, draw_list) in cached.draw_lists.iter_mut().zip(frame.draw_lists.iter()) {
+                cached.commands.clear();
+                cached.commands.extend(
+                    draw_list
+                        .commands()
+                        .filter_map(DrawCmdSnapshot::from_draw_cmd),
+                );
+            }
+        }
+        self.offsets.clone_from(&scratch.offsets);
+        self.uniforms.clone_from(&scratch.uniforms);
+        self.clip_quad_bases.clone_from(&scratch.clip_quad_bases);
+    }
+    /// The draw lists of all layers of the cached frame, with their commands only.
+    pub(crate) fn draw_lists(&self) -> impl Iterator<Item = &DrawListSnapshot> + Clone {
+        self.frames.iter().flat_map(|frame| &frame.draw_lists)
+    }
+}
+
+impl Renderer {
+    /// Draws the frame last rendered into the main viewport again from the buffers it
+    /// uploaded, without new draw data, e.g. while the UI thread hitches or imgui updates are
+    /// throttled. The render pass must match the render target of that frame. Raw callbacks
+    /// and draw callbacks are not replayed.
+    pub fn render_cached<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+    ) -> RendererResult<()> {
+        self.render_cached_viewport(render_pass, MAIN_VIEWPORT_ID)
+    }
+    /// `render_cached` for the frame last rendered with `render_viewport`.
+    pub fn render_cached_viewport<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        viewport_id: u32,
+    ) -> RendererResult<()> {
+        let cached_frame = match self.viewports.get(&viewport_id) {
+            Some(resources) if resources.cached_frame.target.is_some() => &resources.cached_frame,
+            _ => return Ok(()),
+        };
+        let scratch = std::mem::take(&mut self.scratch.draw.lock().unwrap().layers);
+        let mut layers: Vec<Layer> = recycle(scratch);
+        layers.extend(cached_frame.frames.iter().map(|snapshot| Layer {
+            frame: Frame::from(snapshot),
+            projection: None,
+        }));
+        // The indirect buffer still holds these draws from when the frame was rendered.
+        self.draw_frame(render_pass, &layers, viewport_id)?;
+        self.scratch.draw.lock().unwrap().layers = recycle(layers);
+        Ok(())
+    }
+}
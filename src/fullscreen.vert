@@ -0,0 +1,13 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+layout(location = 0) out vec2 fragUv;
+
+out gl_PerVertex {
+    vec4 gl_Position;
+};
+// A single triangle covering the whole target.
+void main() {
+    fragUv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+    gl_Position = vec4(fragUv * vec2(2.0, -2.0) + vec2(-1.0, 1.0), 0.0, 1.0);
+}
@@ -0,0 +1,214 @@
+use crate::shaders::{self, Shader};
+use crate::{Renderer, Texture};
+
+// A pipeline drawing one triangle over the whole target with `fragment`, which overwrites
+// the target's contents. Its bindings are the single group described by `bind_group_layout`.
+pub(crate) fn pipeline(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    fragment: Shader,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: None,
+        bind_group_layouts: &[bind_group_layout],
+        push_constant_ranges: &[],
+    });
+    let vs_module = shaders::create_module(device, Shader::FullscreenVertex);
+    let fs_module = shaders::create_module(device, fragment);
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: None,
+        layout: Some(&pipeline_layout),
+        vertex_stage: wgpu::ProgrammableStageDescriptor {
+            module: &vs_module,
+            entry_point: "main",
+        },
+        fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
+            module: &fs_module,
+            entry_point: "main",
+        }),
+        rasterization_state: None,
+        primitive_topology: wgpu::PrimitiveTopology::TriangleList,
+        color_states: &[wgpu::ColorStateDescriptor {
+            format,
+            color_blend: wgpu::BlendDescriptor::REPLACE,
+            alpha_blend: wgpu::BlendDescriptor::REPLACE,
+            write_mask: wgpu::ColorWrite::ALL,
+        }],
+        depth_stencil_state: None,
+        vertex_state: wgpu::VertexStateDescriptor {
+            index_format: wgpu::IndexFormat::Uint16,
+            vertex_buffers: &[],
+        },
+        sample_count: 1,
+        sample_mask: !0,
+        alpha_to_coverage_enabled: false,
+    })
+}
+
+// Records a pass clearing `target` to `clear` and drawing `pipeline` over it.
+pub(crate) fn draw(
+    encoder: &mut wgpu::CommandEncoder,
+    target: &wgpu::TextureView,
+    clear: wgpu::Color,
+    pipeline: &wgpu::RenderPipeline,
+    bind_group: &wgpu::BindGroup,
+) {
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+            attachment: target,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(clear),
+                store: true,
+            },
+        }],
+        depth_stencil_attachment: None,
+    });
+    render_pass.set_pipeline(pipeline);
+    render_pass.set_bind_group(0, bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+}
+
+// Registers a `width` x `height` RGBA texture that passes can draw into, returning the view
+// to draw through and its id.
+pub(crate) fn output_texture(
+    device: &wgpu::Device,
+    renderer: &mut Renderer,
+    width: u32,
+    height: u32,
+) -> (wgpu::TextureView, imgui::TextureId) {
+    let mut descriptor = crate::upload_descriptor(width, height);
+    descriptor.usage |= wgpu::TextureUsage::OUTPUT_ATTACHMENT;
+    let texture = Texture::with_sampler(
+        device,
+        renderer.texture_layout(),
+        &descriptor,
+        renderer.samplers.get(renderer.default_sampler),
+    );
+    let output = texture
+        .texture()
+        .unwrap()
+        .create_view(&wgpu::TextureViewDescriptor::default());
+    (output, renderer.insert_texture(texture))
+}
+
+/// Converts a source texture into a texture registered with the renderer through a fragment
+/// shader that reads `Uniforms` at binding 0, a nearest sampler at binding 1 and the source
+/// at binding 2.
+pub(crate) struct FullscreenPass<Uniforms> {
+    output: wgpu::TextureView,
+    uniforms: wgpu::Buffer,
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    texture_id: imgui::TextureId,
+    uniforms_type: std::marker::PhantomData<Uniforms>,
+}
+impl<Uniforms: bytemuck::Pod> FullscreenPass<Uniforms> {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        renderer: &mut Renderer,
+        source: &wgpu::TextureView,
+        component_type: wgpu::TextureComponentType,
+        width: u32,
+        height: u32,
+        fragment: Shader,
+    ) -> Self {
+        let uniforms_size = std::mem::size_of::<Uniforms>() as u64;
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: uniforms_size,
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::UniformBuffer {
+                        dynamic: false,
+                        min_binding_size: wgpu::BufferSize::new(uniforms_size),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler { comparison: false },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::SampledTexture {
+                        multisampled: false,
+                        component_type,
+                        dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        // The shaders fetch texels, so integer, depth and unfilterable float formats work,
+        // but GLSL still wants a sampler; a nearest one never asks for filtering.
+        let sampler_id = renderer.cached_sampler(
+            device,
+            &wgpu::SamplerDescriptor {
+                mag_filter: wgpu::FilterMode::Nearest,
+                min_filter: wgpu::FilterMode::Nearest,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                ..crate::linear_sampler_descriptor()
+            },
+        );
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(uniforms.slice(..)),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(renderer.samplers.get(sampler_id)),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(source),
+                },
+            ],
+        });
+        let format = crate::upload_descriptor(width, height).format;
+        let pipeline = pipeline(device, &bind_group_layout, fragment, format);
+        let (output, texture_id) = output_texture(device, renderer, width, height);
+        Self {
+            output,
+            uniforms,
+            pipeline,
+            bind_group,
+            texture_id,
+            uniforms_type: std::marker::PhantomData,
+        }
+    }
+    pub(crate) fn texture_id(&self) -> imgui::TextureId {
+        self.texture_id
+    }
+    // Records the conversion of the source texture's current contents with `uniforms`.
+    pub(crate) fn update(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        uniforms: &Uniforms,
+    ) {
+        queue.write_buffer(&self.uniforms, 0, bytemuck::bytes_of(uniforms));
+        draw(
+            encoder,
+            &self.output,
+            wgpu::Color::BLACK,
+            &self.pipeline,
+            &self.bind_group,
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{Renderer, RendererError};
+use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub enum TextureFileError {
+    Image(image::ImageError),
+    Watch(notify::Error),
+    Renderer(RendererError),
+}
+impl std::fmt::Display for TextureFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextureFileError::Image(error) => write!(f, "failed to load texture file: {}", error),
+            TextureFileError::Watch(error) => write!(f, "failed to watch texture file: {}", error),
+            TextureFileError::Renderer(error) => error.fmt(f),
+        }
+    }
+}
+impl std::error::Error for TextureFileError {}
+impl From<image::ImageError> for TextureFileError {
+    fn from(error: image::ImageError) -> Self {
+        TextureFileError::Image(error)
+    }
+}
+impl From<RendererError> for TextureFileError {
+    fn from(error: RendererError) -> Self {
+        TextureFileError::Renderer(error)
+    }
+}
+impl From<notify::Error> for TextureFileError {
+    fn from(error: notify::Error) -> Self {
+        TextureFileError::Watch(error)
+    }
+}
+
+/// The texture files registered with `Renderer::register_texture_file`.
+pub(crate) struct TextureFiles {
+    watcher: RecommendedWatcher,
+    events: Receiver<DebouncedEvent>,
+    directories: Vec<PathBuf>,
+    files: HashMap<PathBuf, imgui::TextureId>,
+}
+impl TextureFiles {
+    fn new() -> Result<Self, TextureFileError> {
+        let (sender, events) = channel();
+        Ok(Self {
+            watcher: notify::watcher(sender, DEBOUNCE)?,
+            events,
+            directories: Vec::new(),
+            files: HashMap::new(),
+        })
+    }
+    // Editors often save by replacing the file, which ends a watch on the file itself, so the
+    // containing directory is watched instead.
+    fn watch(
+        &mut self,
+        path: PathBuf,
+        texture_id: imgui::TextureId,
+    ) -> Result<(), TextureFileError> {
+        if let Some(directory) = path.parent() {
+            if !self.directories.iter().any(|watched| watched == directory) {
+                self.watcher.watch(directory, RecursiveMode::NonRecursive)?;
+                self.directories.push(directory.to_owned());
+            }
+        }
+        self.files.insert(path, texture_id);
+        Ok(())
+    }
+    fn changed(&self) -> Vec<(PathBuf, imgui::TextureId)> {
+        let mut changed = Vec::new();
+        for event in self.events.try_iter() {
+            let path = match event {
+                DebouncedEvent::Write(path)
+                | DebouncedEvent::Create(path)
+                | DebouncedEvent::Rename(_, path) => path,
+                _ => continue,
+            };
+            if let Some(&texture_id) = self.files.get(&path) {
+                if !changed.iter().any(|(_, id)| *id == texture_id) {
+                    changed.push((path, texture_id));
+                }
+            }
+        }
+        changed
+    }
+}
+
+fn load(path: &Path) -> Result<image::RgbaImage, TextureFileError> {
+    Ok(image::open(path)?.into_rgba8())
+}
+
+impl Renderer {
+    /// Uploads the image at `path` and reloads it under the same id whenever the file changes
+    /// on disk; call `reload_texture_files` once per frame to pick up changes.
+    pub fn register_texture_file<P: AsRef<Path>>(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        path: P,
+    ) -> Result<imgui::TextureId, TextureFileError> {
+        let path = path
+            .as_ref()
+            .canonicalize()
+            .map_err(image::ImageError::IoError)?;
+        let image = load(&path)?;
+        let (width, height) = image.dimensions();
+        let texture_id = self.upload_texture(device, queue, width, height, &image)?;
+        if self.texture_files.is_none() {
+            self.texture_files = Some(TextureFiles::new()?);
+        }
+        self.texture_files
+            .as_mut()
+            .unwrap()
+            .watch(path, texture_id)?;
+        Ok(texture_id)
+    }
+    /// Re-uploads the registered texture files that changed since the last call, returning
+    /// their ids. Files that fail to load, e.g. while still being written, keep their previous
+    /// contents.
+    pub fn reload_texture_files(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Vec<imgui::TextureId> {
+        let changed = match &self.texture_files {
+            Some(texture_files) => texture_files.changed(),
+            None => return Vec::new(),
+        };
+        let mut reloaded = Vec::new();
+        for (path, texture_id) in changed {
+            let image = match load(&path) {
+                Ok(image) => image,
+                // Most likely a partial write; the next event for the file retries.
+                Err(_) => continue,
+            };
+            let (width, height) = image.dimensions();
+            // Keeps the previous contents, as for a file that fails to load.
+            if crate::validate_texture_size(width, height).is_err() {
+                continue;
+            }
+            let texture = match self.textures.get(texture_id) {
+                Some(texture) => texture,
+                // Removed by the application.
+                None => continue,
+            };
+            if texture.size().map(|size| (size.width, size.height)) == Some((width, height)) {
+                texture.write(queue, &image);
+            } else if let Some(texture) = self.reallocate_texture(device, texture_id, width, height)
+            {
+                texture.write(queue, &image);
+            }
+            reloaded.push(texture_id);
+        }
+        reloaded
+    }
+}
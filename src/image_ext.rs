@@ -0,0 +1,100 @@
+use crate::{Renderer, SamplerId, Texture};
+use std::sync::Mutex;
+
+// Transient images get ids from the top half of the id space, far from the ids
+// `imgui::Textures` counts up from zero. Each frame hands them out from the start again.
+const FIRST_TRANSIENT_ID: usize = usize::MAX / 2;
+
+/// The bind groups created for `ImageExt::wgpu_image` while a frame's UI is built, and the ids
+/// the previous frame's took.
+#[derive(Default)]
+pub(crate) struct TransientViews {
+    pending: Mutex<Vec<wgpu::BindGroup>>,
+    shown: usize,
+}
+
+/// Registers texture views for one frame while its UI is built, from
+/// `Renderer::transient_images`. Drop it before rendering the frame; the views are shown by
+/// the next render only, so each viewport needs its own.
+pub struct TransientImages<'r> {
+    device: &'r wgpu::Device,
+    renderer: &'r Renderer,
+    // The ids handed out, by view and sampler. Holding the views borrowed keeps their
+    // addresses from being reused by other views while ids are handed out.
+    ids: Mutex<Vec<(&'r wgpu::TextureView, SamplerId, imgui::TextureId)>>,
+}
+impl<'r> TransientImages<'r> {
+    /// Registers `view` for this frame and returns its id, e.g. for a draw list's `add_image`.
+    /// A view shown several times gets the same id. The view needs a filterable float
+    /// format; it is sampled with the renderer's default sampler. Like bind groups from
+    /// `Renderer::insert_bind_group`, it can't be drawn in bindless mode.
+    pub fn texture_id(&self, view: &'r wgpu::TextureView) -> imgui::TextureId {
+        let renderer = self.renderer;
+        let sampler = renderer.default_sampler;
+        let mut ids = self.ids.lock().unwrap();
+        if let Some(&(_, _, texture_id)) = ids.iter().find(|(shown, shown_sampler, _)| {
+            std::ptr::eq(*shown, view) && *shown_sampler == sampler
+        }) {
+            return texture_id;
+        }
+        let bind_group = crate::texture_bind_group(
+            self.device,
+            &renderer.texture_bind_layout,
+            view,
+            renderer.samplers.get(sampler),
+        );
+        let mut pending = renderer.transient_views.pending.lock().unwrap();
+        pending.push(bind_group);
+        let texture_id = imgui::TextureId::from(FIRST_TRANSIENT_ID + pending.len() - 1);
+        ids.push((view, sampler, texture_id));
+        texture_id
+    }
+}
+
+/// Shows `wgpu::TextureView`s, such as transient debug views of engine textures, without
+/// registering them with the renderer first.
+pub trait ImageExt {
+    /// Draws `view` as an `imgui::Image` of `size`.
+    fn wgpu_image<'r>(
+        &self,
+        images: &TransientImages<'r>,
+        view: &'r wgpu::TextureView,
+        size: [f32; 2],
+    );
+}
+impl ImageExt for imgui::Ui<'_> {
+    fn wgpu_image<'r>(
+        &self,
+        images: &TransientImages<'r>,
+        view: &'r wgpu::TextureView,
+        size: [f32; 2],
+    ) {
+        imgui::Image::new(images.texture_id(view), size).build(self);
+    }
+}
+
+impl Renderer {
+    /// Starts registering texture views for `ImageExt::wgpu_image` in the UI being built.
+    pub fn transient_images<'r>(&'r self, device: &'r wgpu::Device) -> TransientImages<'r> {
+        TransientImages {
+            device,
+            renderer: self,
+            ids: Mutex::new(Vec::new()),
+        }
+    }
+    // Unregisters the previous frame's transient images and registers the ones since.
+    pub(crate) fn register_transient_images(&mut self) {
+        for index in 0..self.transient_views.shown {
+            self.textures
+                .remove(imgui::TextureId::from(FIRST_TRANSIENT_ID + index));
+        }
+        let pending = std::mem::take(self.transient_views.pending.get_mut().unwrap());
+        self.transient_views.shown = pending.len();
+        for (index, bind_group) in pending.into_iter().enumerate() {
+            self.textures.replace(
+                imgui::TextureId::from(FIRST_TRANSIENT_ID + index),
+                Texture::from_bind_group(bind_group),
+            );
+        }
+    }
+}
@@ -1,14 +1,70 @@
 #version 450
 #extension GL_ARB_separate_shader_objects : enable
+#ifdef BINDLESS
+#extension GL_EXT_nonuniform_qualifier : require
+#endif
 
 layout(location = 0) in vec4 fragColor;
 layout(location = 1) in vec2 fragUv;
 layout(location = 0) out vec4 outColor;
 
-layout(set = 1, binding = 0) uniform texture2D tex;
+// Built with -DPUSH_CONSTANTS into imgui.push.frag.spv
+#ifdef PUSH_CONSTANTS
+layout(push_constant) uniform PushConstants {
+    mat4 projection;
+    float gamma;
+    float vertex_gamma;
+    float depth_base;
+    float depth_step;
+    vec4 color_transform[3];
+} ubo;
+#define TEXTURE_SET 0
+#else
+layout(set = 0, binding = 0) uniform UniformBufferObject {
+    mat4 projection;
+    float gamma;
+    float vertex_gamma;
+    float depth_base;
+    float depth_step;
+    vec4 color_transform[3];
+} ubo;
+#define TEXTURE_SET 1
+#endif
+
+// Built with -DBINDLESS into imgui.bindless.frag.spv; the array size must match
+// TEXTURE_ARRAY_SIZE in bindless.rs.
+#ifdef BINDLESS
+layout(location = 2) flat in uint fragTexture;
+layout(set = 1, binding = 0) uniform texture2D textures[256];
 layout(set = 1, binding = 1) uniform sampler texSampler;
+#define TEXTURE textures[nonuniformEXT(fragTexture)]
+#else
+layout(set = TEXTURE_SET, binding = 0) uniform texture2D tex;
+layout(set = TEXTURE_SET, binding = 1) uniform sampler texSampler;
+#define TEXTURE tex
+#endif
 
 void main() {
-    vec4 texColor = texture(sampler2D(tex,texSampler), fragUv);
-    outColor = fragColor * texColor;
+    vec4 texColor = texture(sampler2D(TEXTURE, texSampler), fragUv);
+#ifdef SDF
+    // Built with -DSDF into the *.sdf.frag.spv variants: the texture holds a signed distance
+    // field in alpha, 0.5 on the outline, which is antialiased over one screen pixel.
+    float distance = texColor.a;
+    float edge = max(fwidth(distance), 1e-5);
+    texColor = vec4(1.0, 1.0, 1.0, smoothstep(0.5 - edge, 0.5 + edge, distance));
+#endif
+#ifdef CHECKERBOARD
+    // Built with -DCHECKERBOARD into the *.checker.frag.spv variants: the texture is shown over
+    // light and dark gray squares of 8 pixels, so its transparency can be seen.
+    vec2 cell = floor(gl_FragCoord.xy / 8.0);
+    vec3 backdrop = mod(cell.x + cell.y, 2.0) < 1.0 ? vec3(0.8) : vec3(0.6);
+    texColor = vec4(mix(backdrop, texColor.rgb, texColor.a), 1.0);
+#endif
+    vec3 vertexColor = pow(fragColor.rgb, vec3(ubo.gamma / ubo.vertex_gamma));
+    vec4 linear = vec4(pow(texColor.rgb, vec3(ubo.gamma)) * vertexColor, 1.0);
+    vec3 graded = vec3(
+        dot(ubo.color_transform[0], linear),
+        dot(ubo.color_transform[1], linear),
+        dot(ubo.color_transform[2], linear));
+    outColor = vec4(graded, fragColor.a * texColor.a);
 }
\ No newline at end of file
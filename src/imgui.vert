@@ -7,15 +7,54 @@ layout(location = 2) in uint color;
 layout(location = 0) out vec4 fragColor;
 layout(location = 1) out vec2 fragUv;
 
+// Built with -DBINDLESS into imgui.bindless.vert.spv
+#ifdef BINDLESS
+#define TEXTURE_ARRAY_SIZE 256u
+layout(location = 2) flat out uint fragTexture;
+#endif
+
+// Built with -DPUSH_CONSTANTS into imgui.push.vert.spv
+#ifdef PUSH_CONSTANTS
+layout(push_constant) uniform PushConstants {
+    mat4 projection;
+    float gamma;
+    float vertex_gamma;
+    float depth_base;
+    float depth_step;
+    vec4 color_transform[3];
+} ubo;
+#else
 layout(set = 0, binding = 0) uniform UniformBufferObject {
     mat4 projection;
+    float gamma;
+    float vertex_gamma;
+    float depth_base;
+    float depth_step;
+    vec4 color_transform[3];
 } ubo;
+#endif
 
 out gl_PerVertex {
     vec4 gl_Position;
 };
 void main() {
     gl_Position = ubo.projection * vec4(position, 0.0, 1.0);
+    // The instance index is the draw list's position in the frame, for depth layering.
+#ifdef BINDLESS
+    // It also carries the texture's slot in the texture array.
+    uint drawList = uint(gl_InstanceIndex) / TEXTURE_ARRAY_SIZE;
+    fragTexture = uint(gl_InstanceIndex) % TEXTURE_ARRAY_SIZE;
+#else
+    uint drawList = uint(gl_InstanceIndex);
+#endif
+    gl_Position.z += (ubo.depth_base + ubo.depth_step * float(drawList)) * gl_Position.w;
     fragColor = unpackUnorm4x8(color);
+    // Built with -DBGRA into imgui.bgra.vert.spv, imgui.push.bgra.vert.spv and
+    // imgui.bindless.bgra.vert.spv, for imgui builds with IMGUI_USE_BGRA_PACKED_COLOR.
+#ifdef BGRA
+    fragColor = fragColor.bgra;
+#endif
+    // Decoding here makes the color interpolate in linear space.
+    fragColor.rgb = pow(fragColor.rgb, vec3(ubo.vertex_gamma));
     fragUv = uv;
 }
\ No newline at end of file
@@ -0,0 +1,45 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+layout(location = 0) in vec2 fragUv;
+layout(location = 0) out vec4 outColor;
+
+layout(set = 0, binding = 0) uniform IntegerMapping {
+    float minValue;
+    float maxValue;
+    // 0 spreads values between minValue and maxValue over grayscale, 1 hashes them to colors.
+    uint mode;
+} mapping;
+layout(set = 0, binding = 1) uniform sampler integerSampler;
+#ifdef SIGNED
+layout(set = 0, binding = 2) uniform itexture2D integers;
+#else
+layout(set = 0, binding = 2) uniform utexture2D integers;
+#endif
+
+vec3 hashColor(uint value) {
+    // Zero usually means "nothing here", so it stays black.
+    if (value == 0u) {
+        return vec3(0.0);
+    }
+    uint h = value * 2654435761u;
+    h ^= h >> 15;
+    h *= 2246822519u;
+    h ^= h >> 13;
+    return vec3(uvec3(h, h >> 8, h >> 16) & 255u) / 255.0;
+}
+
+void main() {
+    ivec2 texel = ivec2(gl_FragCoord.xy);
+#ifdef SIGNED
+    int value = texelFetch(isampler2D(integers, integerSampler), texel, 0).r;
+#else
+    uint value = texelFetch(usampler2D(integers, integerSampler), texel, 0).r;
+#endif
+    if (mapping.mode == 1u) {
+        outColor = vec4(hashColor(uint(value)), 1.0);
+    } else {
+        float t = (float(value) - mapping.minValue) / (mapping.maxValue - mapping.minValue);
+        outColor = vec4(vec3(clamp(t, 0.0, 1.0)), 1.0);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::fullscreen_pass::FullscreenPass;
+use crate::shaders::Shader;
+use crate::Renderer;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IntegerMapping {
+    min: f32,
+    max: f32,
+    mode: u32,
+    _padding: u32,
+}
+
+unsafe impl bytemuck::Zeroable for IntegerMapping {}
+
+unsafe impl bytemuck::Pod for IntegerMapping {}
+
+/// How an `IntegerView` turns values into colors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IntegerVisualization {
+    /// Grayscale, black at `min` and white at `max`.
+    Normalize { min: f32, max: f32 },
+    /// A distinct color per value, e.g. for entity ids or masks; zero stays black.
+    Hash,
+}
+
+/// The component type of an `IntegerView`'s source texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntegerComponent {
+    Uint,
+    Sint,
+}
+
+/// Shows a `Uint` or `Sint` texture, such as an entity id buffer, with `imgui::Image` by
+/// converting its first channel to colors in a texture registered with the renderer.
+pub struct IntegerView {
+    visualization: IntegerVisualization,
+    pass: FullscreenPass<IntegerMapping>,
+}
+impl IntegerView {
+    /// `source` is a view of a `width` x `height` texture with `SAMPLED` usage whose format
+    /// has the `Uint` or `Sint` component type given by `component`. Create a new `IntegerView` when the source
+    /// texture is recreated.
+    pub fn new(
+        device: &wgpu::Device,
+        renderer: &mut Renderer,
+        source: &wgpu::TextureView,
+        component: IntegerComponent,
+        width: u32,
+        height: u32,
+        visualization: IntegerVisualization,
+    ) -> Self {
+        let (shader, component_type) = match component {
+            IntegerComponent::Uint => (Shader::UintFragment, wgpu::TextureComponentType::Uint),
+            IntegerComponent::Sint => (Shader::SintFragment, wgpu::TextureComponentType::Sint),
+        };
+        Self {
+            visualization,
+            pass: FullscreenPass::new(
+                device,
+                renderer,
+                source,
+                component_type,
+                width,
+                height,
+                shader,
+            ),
+        }
+    }
+    pub fn texture_id(&self) -> imgui::TextureId {
+        self.pass.texture_id()
+    }
+    pub fn visualization(&self) -> IntegerVisualization {
+        self.visualization
+    }
+    /// Takes effect at the next `update`.
+    pub fn set_visualization(&mut self, visualization: IntegerVisualization) {
+        self.visualization = visualization;
+    }
+    /// Records the conversion of the source texture's current contents into `encoder`.
+    pub fn update(&self, queue: &wgpu::Queue, encoder: &mut wgpu::CommandEncoder) {
+        let mapping = match self.visualization {
+            IntegerVisualization::Normalize { min, max } => IntegerMapping {
+                min,
+                max,
+                mode: 0,
+                _padding: 0,
+            },
+            IntegerVisualization::Hash => IntegerMapping {
+                min: 0.0,
+                max: 1.0,
+                mode: 1,
+                _padding: 0,
+            },
+        };
+        self.pass.update(queue, encoder, &mapping);
+    }
+}
@@ -1,16 +1,266 @@
+use bindless::TextureArray;
 use imgui::internal::RawWrapper;
 use imgui::DrawIdx;
 use imgui::DrawVert;
+use mapped_buffers::MappedBufferPool;
+use samplers::Samplers;
+use shaders::Shader;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use texture_gc::{RemovedTextures, TextureGc};
+use texture_handle::DroppedTextures;
+use texture_pool::TexturePool;
+use wgpu::util::DeviceExt;
+
+mod animated;
+mod bindless;
+mod blur;
+mod callbacks;
+mod chunked_upload;
+mod debug_window;
+mod depth_view;
+mod frame_cache;
+mod fullscreen_pass;
+#[cfg(feature = "hot-reload")]
+mod hot_reload;
+mod image_ext;
+mod integer_view;
+mod mapped_buffers;
+mod multi_device;
+mod multisample;
+#[cfg(feature = "rayon")]
+mod parallel_pack;
+mod prewarm;
+#[cfg(feature = "imgui-sys")]
+mod raw_draw_data;
+mod renderer_trait;
+mod samplers;
+mod scaled;
+mod sdf;
+mod shader_globals;
+mod shaders;
+mod snapshot;
+mod stereo;
+#[cfg(feature = "stress")]
+mod stress;
+#[cfg(feature = "svg")]
+mod svg;
+mod swizzle_view;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+#[cfg(feature = "texture-containers")]
+mod texture_container;
+mod texture_gc;
+mod texture_handle;
+mod texture_pool;
+mod ui_target;
+mod uploader;
+mod video;
+mod window_capture;
+#[cfg(feature = "animated")]
+pub use animated::AnimationError;
+pub use animated::{AnimatedTexture, AnimationFrame};
+pub use bindless::BINDLESS_FEATURES;
+pub use blur::BackgroundBlur;
+pub use callbacks::{push_draw_callback, DrawCallback};
+pub use chunked_upload::ChunkedUpload;
+pub use depth_view::DepthView;
+#[cfg(feature = "hot-reload")]
+pub use hot_reload::TextureFileError;
+pub use image_ext::{ImageExt, TransientImages};
+pub use integer_view::{IntegerComponent, IntegerView, IntegerVisualization};
+pub use multi_device::{DeviceId, MultiDeviceRenderer};
+pub use prewarm::{PipelinePrewarm, PrewarmedPipelines};
+pub use renderer_trait::{ImguiRenderer, NullRenderer};
+pub use samplers::SamplerId;
+pub use scaled::ScaledComposite;
+pub use snapshot::{DrawCmdSnapshot, DrawDataSnapshot, DrawListSnapshot};
+pub use stereo::StereoUiTarget;
+#[cfg(feature = "stress")]
+pub use stress::StressConfig;
+#[cfg(feature = "svg")]
+pub use svg::SvgError;
+pub use swizzle_view::{Swizzle, SwizzleChannel, SwizzleView};
+#[cfg(feature = "texture-containers")]
+pub use texture_container::TextureContainerError;
+pub use texture_handle::TextureHandle;
+pub use ui_target::{TextureSubresource, UiRenderTarget};
+pub use uploader::{DrawDataUploader, DrawRange};
+pub use video::{VideoTexture, YuvColorSpace, YuvFormat};
+#[cfg(feature = "winit")]
+mod winit_support;
+#[cfg(feature = "winit")]
+pub use winit_support::ImguiWgpu;
+
+#[cfg(feature = "profiling")]
+use profiling::scope as profile_scope;
+#[cfg(not(feature = "profiling"))]
+macro_rules! profile_scope {
+    ($($arg:tt)*) => {};
+}
+
+#[cfg(feature = "log")]
+use log::{debug, trace};
+#[cfg(not(feature = "log"))]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
+#[cfg(not(feature = "log"))]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
 
 const MAX_INDEX_BUFFER_SIZE: u64 = 1024*1024;
 const MAX_VERTEX_BUFFER_SIZE: u64 = 1024*1024;
+// wgpu 0.6 doesn't report a texture size limit; this is the size WebGPU guarantees everywhere.
+const MAX_TEXTURE_DIMENSION: u32 = 8192;
+// Font atlases get ids from the top quarter of the id space, far from the ids
+// `imgui::Textures` counts up from zero for user textures, so rebuilding fonts never moves an
+// atlas onto an id the application holds. The quarters below are reserved for transient
+// images and `MultiDeviceRenderer`.
+const FIRST_INTERNAL_TEXTURE_ID: usize = usize::MAX / 4 * 3;
+
+/// Id of imgui's main viewport (`IMGUI_VIEWPORT_DEFAULT_ID`), used by `render` and
+/// `render_with_target`.
+pub const MAIN_VIEWPORT_ID: u32 = 0x1111_1111;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RendererError {
+    VertexBufferOverflow,
+    IndexBufferOverflow,
+    BadTexture(imgui::TextureId),
+    StaleTexture(imgui::TextureId),
+    /// The render target of a cached frame has no pipelines anymore, because
+    /// `set_render_target_format` dropped them.
+    UnknownRenderTarget(RenderTarget),
+    ReadbackFailed,
+    InvalidSnapshot,
+    TooManyTextures,
+    EmptyTexture,
+    TextureTooLarge {
+        width: u32,
+        height: u32,
+        max: u32,
+    },
+    UnsupportedTextureFormat(wgpu::TextureFormat),
+    /// The pixel data passed to `upload_texture` doesn't match its dimensions.
+    TextureDataSize {
+        expected: usize,
+        actual: usize,
+    },
+    /// The row stride passed to `upload_texture_with_stride` is shorter than a row of pixels.
+    BadRowStride {
+        bytes_per_row: u32,
+        min: u32,
+    },
+    /// The texture can't be copied back from the GPU, because it was created without
+    /// `COPY_SRC` usage or from a bind group.
+    TextureNotReadable(imgui::TextureId),
+    /// The device was never added to the `MultiDeviceRenderer` or has been removed.
+    UnknownDevice(DeviceId),
+    /// The sampler was created by another renderer.
+    UnknownSampler(SamplerId),
+    /// `VideoTexture::update` got another number of planes than its `YuvFormat` has.
+    PlaneCount {
+        expected: usize,
+        actual: usize,
+    },
+}
+impl std::fmt::Display for RendererError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RendererError::VertexBufferOverflow => write!(f, "vertex buffer is full"),
+            RendererError::IndexBufferOverflow => write!(f, "index buffer is full"),
+            RendererError::BadTexture(id) => write!(f, "no texture registered for {:?}", id),
+            RendererError::StaleTexture(id) => {
+                write!(f, "texture {:?} was used after it was removed", id)
+            }
+            RendererError::UnknownRenderTarget(target) => {
+                write!(f, "render target {:?} has no pipelines anymore", target)
+            }
+            RendererError::ReadbackFailed => write!(f, "failed to map the readback buffer"),
+            RendererError::InvalidSnapshot => write!(f, "serialized draw data is malformed"),
+            RendererError::TooManyTextures => {
+                write!(f, "frame draws more textures than the texture array holds")
+            }
+            RendererError::EmptyTexture => write!(f, "texture has no pixels"),
+            RendererError::TextureTooLarge { width, height, max } => write!(
+                f,
+                "texture of {}x{} pixels is too large; the maximum is {}x{}",
+                width, height, max, max
+            ),
+            RendererError::UnsupportedTextureFormat(format) => {
+                write!(f, "device does not support the texture format {:?}", format)
+            }
+            RendererError::TextureDataSize { expected, actual } => write!(
+                f,
+                "texture data is {} bytes, but its dimensions need {}",
+                actual, expected
+            ),
+            RendererError::BadRowStride { bytes_per_row, min } => write!(
+                f,
+                "rows are {} bytes apart, but a row of pixels takes {}",
+                bytes_per_row, min
+            ),
+            RendererError::TextureNotReadable(id) => {
+                write!(f, "texture {:?} can't be read back", id)
+            }
+            RendererError::UnknownDevice(device_id) => {
+                write!(f, "{:?} is not a device of the renderer", device_id)
+            }
+            RendererError::UnknownSampler(sampler_id) => {
+                write!(f, "{:?} is not a sampler of the renderer", sampler_id)
+            }
+            RendererError::PlaneCount { expected, actual } => {
+                write!(
+                    f,
+                    "video frame has {} planes, but its format has {}",
+                    actual, expected
+                )
+            }
+        }
+    }
+}
+impl std::error::Error for RendererError {}
+
+pub type RendererResult<T> = Result<T, RendererError>;
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct Uniforms {
+    projection: [f32; 16],
+    gamma: f32,
+    vertex_gamma: f32,
+    depth_base: f32,
+    depth_step: f32,
+    color_transform: [[f32; 4]; 3],
+}
+
+unsafe impl bytemuck::Zeroable for Uniforms {}
+
+unsafe impl bytemuck::Pod for Uniforms {}
 
 #[derive(Clone, Copy)]
-struct Vertex(DrawVert);
+#[repr(C)]
+struct DrawIndexedIndirect {
+    index_count: u32,
+    instance_count: u32,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+}
 
-unsafe impl bytemuck::Zeroable for Vertex {}
+unsafe impl bytemuck::Zeroable for DrawIndexedIndirect {}
 
-unsafe impl bytemuck::Pod for Vertex {}
+unsafe impl bytemuck::Pod for DrawIndexedIndirect {}
 
 macro_rules! size_of {
     ($T:ty) => {
@@ -24,221 +274,3020 @@ macro_rules! offset_of {
     }};
 }
 
-struct Texture {
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    #[default]
+    Alpha,
+    PremultipliedAlpha,
+    Additive,
+}
+impl BlendMode {
+    const ALL: [BlendMode; 3] = [
+        BlendMode::Alpha,
+        BlendMode::PremultipliedAlpha,
+        BlendMode::Additive,
+    ];
+    fn color_blend(self) -> wgpu::BlendDescriptor {
+        let (src_factor, dst_factor) = match self {
+            BlendMode::Alpha => (
+                wgpu::BlendFactor::SrcAlpha,
+                wgpu::BlendFactor::OneMinusSrcAlpha,
+            ),
+            BlendMode::PremultipliedAlpha => {
+                (wgpu::BlendFactor::One, wgpu::BlendFactor::OneMinusSrcAlpha)
+            }
+            BlendMode::Additive => (wgpu::BlendFactor::SrcAlpha, wgpu::BlendFactor::One),
+        };
+        wgpu::BlendDescriptor {
+            src_factor,
+            dst_factor,
+            operation: wgpu::BlendOperation::Add,
+        }
+    }
+}
+
+const INDEX_FORMAT: wgpu::IndexFormat = if size_of!(DrawIdx) == 4 {
+    wgpu::IndexFormat::Uint32
+} else {
+    wgpu::IndexFormat::Uint16
+};
+
+// `COPY_SRC` lets `Renderer::download_texture` read uploaded textures back.
+const UPLOAD_USAGE: wgpu::TextureUsage = wgpu::TextureUsage::from_bits_truncate(
+    wgpu::TextureUsage::SAMPLED.bits()
+        | wgpu::TextureUsage::COPY_DST.bits()
+        | wgpu::TextureUsage::COPY_SRC.bits(),
+);
+
+// Distance between the per-layer uniform slots, as required for dynamic offsets.
+const UNIFORM_STRIDE: u32 = wgpu::BIND_BUFFER_ALIGNMENT as u32;
+
+/// A texture the renderer can draw, registered with `Renderer::insert_texture`.
+pub struct Texture {
     bind_group: wgpu::BindGroup,
+    blend_mode: BlendMode,
+    // Whether the texture is a signed distance field, drawn with the SDF pipelines.
+    sdf: bool,
+    shader: Option<TextureShaderId>,
+    // Whether V is mirrored, for textures with a bottom-left origin.
+    flip_v: bool,
+    // The allocation behind `bind_group` when it is known, so it can be pooled.
+    storage: Option<TextureStorage>,
+    // The renderer's sampler in `bind_group`, unless the texture brought its own.
+    sampler: Option<SamplerId>,
+}
+
+struct TextureStorage {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    size: wgpu::Extent3d,
+    mip_level_count: u32,
+    sample_count: u32,
+    dimension: wgpu::TextureDimension,
+    format: wgpu::TextureFormat,
+    usage: wgpu::TextureUsage,
 }
 impl Texture {
     pub fn bind_group(&self) -> &wgpu::BindGroup {
         &self.bind_group
     }
-    pub fn new(
+    /// Creates an RGBA texture and uploads tightly packed `pixels`.
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+    ) -> Self {
+        let texture =
+            Self::from_descriptor(device, bind_group_layout, &upload_descriptor(width, height));
+        texture.write(queue, pixels);
+        texture
+    }
+    /// Creates an uninitialized texture from `descriptor`, with a sampler of its own that
+    /// samples linearly with clamped addressing. `bind_group_layout` should be
+    /// `Renderer::texture_layout()`, and the descriptor needs `SAMPLED` usage and a
+    /// filterable float format.
+    pub fn from_descriptor(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        descriptor: &wgpu::TextureDescriptor,
+    ) -> Self {
+        let sampler = device.create_sampler(&linear_sampler_descriptor());
+        Self::with_sampler(device, bind_group_layout, descriptor, &sampler)
+    }
+    /// Like `from_descriptor`, but shares `sampler`, e.g. one from `Renderer::sampler`.
+    pub fn with_sampler(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        descriptor: &wgpu::TextureDescriptor,
+        sampler: &wgpu::Sampler,
+    ) -> Self {
+        profile_scope!("imgui-wgpu create_texture");
+        let texture = device.create_texture(descriptor);
+        Self::from_texture(device, bind_group_layout, texture, descriptor, sampler)
+    }
+    // `with_sampler` for a texture already created from `descriptor`.
+    fn from_texture(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        texture: wgpu::Texture,
+        descriptor: &wgpu::TextureDescriptor,
+        sampler: &wgpu::Sampler,
+    ) -> Self {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            bind_group: texture_bind_group(device, bind_group_layout, &view, sampler),
+            blend_mode: BlendMode::default(),
+            sdf: false,
+            shader: None,
+            flip_v: false,
+            storage: Some(TextureStorage {
+                texture,
+                view,
+                size: descriptor.size,
+                mip_level_count: descriptor.mip_level_count,
+                sample_count: descriptor.sample_count,
+                dimension: descriptor.dimension,
+                format: descriptor.format,
+                usage: descriptor.usage,
+            }),
+            sampler: None,
+        }
+    }
+    /// Wraps a bind group created with `Renderer::texture_layout()`.
+    pub fn from_bind_group(bind_group: wgpu::BindGroup) -> Self {
+        Self {
+            bind_group,
+            blend_mode: BlendMode::default(),
+            sdf: false,
+            shader: None,
+            flip_v: false,
+            storage: None,
+            sampler: None,
+        }
+    }
+    /// The underlying texture, unless this was created with `from_bind_group`.
+    pub fn texture(&self) -> Option<&wgpu::Texture> {
+        self.storage.as_ref().map(|storage| &storage.texture)
+    }
+    pub fn view(&self) -> Option<&wgpu::TextureView> {
+        self.storage.as_ref().map(|storage| &storage.view)
+    }
+    pub fn size(&self) -> Option<wgpu::Extent3d> {
+        self.storage.as_ref().map(|storage| storage.size)
+    }
+    /// Rebuilds the bind group with another sampler; false if the view isn't known.
+    fn set_sampler(
+        &mut self,
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        sampler_id: SamplerId,
+        sampler: &wgpu::Sampler,
+    ) -> bool {
+        match &self.storage {
+            Some(storage) => {
+                self.bind_group =
+                    texture_bind_group(device, bind_group_layout, &storage.view, sampler);
+                self.sampler = Some(sampler_id);
+                true
+            }
+            None => false,
+        }
+    }
+    // Another texture drawing the same allocation through a bind group of its own, for a
+    // renderer on the same device. None if the view isn't known.
+    fn mirror(
+        &self,
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+    ) -> Option<Self> {
+        let storage = self.storage.as_ref()?;
+        Some(Self {
+            bind_group: texture_bind_group(device, bind_group_layout, &storage.view, sampler),
+            blend_mode: self.blend_mode,
+            sdf: self.sdf,
+            // Shader ids belong to the renderer that registered them.
+            shader: None,
+            flip_v: self.flip_v,
+            storage: None,
+            sampler: None,
+        })
+    }
+    fn write(&self, queue: &wgpu::Queue, pixels: &[u8]) {
+        if let Some(storage) = &self.storage {
+            self.write_rows(queue, pixels, storage.size.width * 4);
+        }
+    }
+    // Writes tightly packed `pixels` into the given rectangle.
+    fn write_region(
+        &self,
+        queue: &wgpu::Queue,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+    ) {
+        if let Some(storage) = &self.storage {
+            queue.write_texture(
+                wgpu::TextureCopyView {
+                    texture: &storage.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d { x, y, z: 0 },
+                },
+                pixels,
+                wgpu::TextureDataLayout {
+                    offset: 0,
+                    bytes_per_row: width * 4,
+                    rows_per_image: 0,
+                },
+                wgpu::Extent3d {
+                    width,
+                    height,
+                    depth: 1,
+                },
+            );
+        }
+    }
+    fn write_rows(&self, queue: &wgpu::Queue, pixels: &[u8], bytes_per_row: u32) {
+        if let Some(storage) = &self.storage {
+            queue.write_texture(
+                wgpu::TextureCopyView {
+                    texture: &storage.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                pixels,
+                wgpu::TextureDataLayout {
+                    offset: 0,
+                    bytes_per_row,
+                    rows_per_image: 0,
+                },
+                storage.size,
+            );
+        }
+    }
+}
+
+fn texture_bind_group(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    view: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+        label: None,
+    })
+}
+
+fn upload_descriptor(width: u32, height: u32) -> wgpu::TextureDescriptor<'static> {
+    wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: UPLOAD_USAGE,
+    }
+}
+
+// Writes `data` to the start of `buffer`, as a copy from a staging buffer recorded into
+// `encoder` when there is one.
+fn write_buffer(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    encoder: Option<&mut wgpu::CommandEncoder>,
+    buffer: &wgpu::Buffer,
+    data: &[u8],
+) {
+    match encoder {
+        Some(encoder) if !data.is_empty() => {
+            let staging = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: data,
+                usage: wgpu::BufferUsage::COPY_SRC,
+            });
+            encoder.copy_buffer_to_buffer(&staging, 0, buffer, 0, data.len() as u64);
+        }
+        _ => queue.write_buffer(buffer, 0, data),
+    }
+}
+
+fn linear_sampler_descriptor() -> wgpu::SamplerDescriptor<'static> {
+    wgpu::SamplerDescriptor {
+        address_mode_u: wgpu::AddressMode::ClampToEdge,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        address_mode_w: wgpu::AddressMode::ClampToEdge,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        mipmap_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    }
+}
+
+/// A replacement for one of the built-in shader stages.
+///
+/// Custom shaders must stay compatible with the renderer's pipeline layout:
+///
+/// * vertex inputs: `location = 0` position (`vec2`), `location = 1` uv (`vec2`),
+///   `location = 2` color (`uint`, packed RGBA8 as in `imgui::DrawVert`), followed by any
+///   `RendererConfig::extra_vertex_attributes`
+/// * `set = 0, binding = 0`: uniform block
+///   `{ mat4 projection; float gamma; float vertex_gamma; float depth_base;
+///   float depth_step; vec4 color_transform[3]; }`, visible to both stages;
+///   `color_transform` already includes the white level
+/// * `set = 1, binding = 0`: `texture2D` of the current draw command
+/// * `set = 1, binding = 1`: `sampler` for that texture
+///
+/// When push constants are in use the uniform block is instead a push
+/// constant block at offset 0, and the texture bindings move to `set = 0`.
+/// With `RendererConfig::bindless`, `set = 1, binding = 0` is a
+/// `texture2D textures[256]` shared by all draw commands instead.
+/// Any `user_bind_group_layouts` follow, starting at
+/// `Renderer::user_bind_group_index`, and then the `RendererConfig::shader_globals` block
+/// at `Renderer::shader_globals_bind_group_index`.
+///
+/// The built-in vertex stage passes the unpacked color, raised to
+/// `vertex_gamma`, to `location = 0` and the uv to `location = 1`. It also adds `depth_base + depth_step *
+/// gl_InstanceIndex` to the normalized depth; each draw list is drawn with its
+/// position in the frame as the instance index. In bindless mode the
+/// instance index is `256 * draw_list + slot`, where `slot` indexes `textures`.
+pub struct UserShader {
+    pub module: wgpu::ShaderModule,
+    pub entry_point: String,
+}
+
+/// A fragment shader registered with `Renderer::register_texture_shader`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureShaderId(usize);
+
+/// Per-vertex data for custom vertex shaders beyond what `imgui::DrawVert` carries, read from
+/// a second vertex buffer in slot 1. Attribute locations must start at 3.
+#[derive(Clone, Debug)]
+pub struct ExtraVertexAttributes {
+    pub stride: u64,
+    pub attributes: Vec<wgpu::VertexAttributeDescriptor>,
+}
+
+type VertexDataCallback = Box<dyn FnMut(usize, usize, &mut [u8]) + Send>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClipMode {
+    Scissor,
+    /// Clip by writing each clip rect into the stencil buffer and testing
+    /// against it, so clipping follows the projection. Needs a
+    /// `depth_stencil_state` with a stencil format; the stencil contents of
+    /// the pass are overwritten.
+    Stencil,
+}
+
+/// Normalized depths written by the first and last draw list of a frame when compositing
+/// the UI into a 3D scene. Lists in between are spaced evenly, so with `front < back` later
+/// (higher) windows win the `LessEqual` depth test.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthLayering {
+    pub back: f32,
+    pub front: f32,
+}
+
+/// The primaries of the display the render target is shown on. imgui's colors are authored
+/// in sRGB, so on a wide-gamut surface they are converted to keep saturated colors from
+/// being stretched over the wider gamut. The transfer function stays the one implied by the
+/// target format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    Srgb,
+    DisplayP3,
+    Rec2020,
+}
+impl ColorSpace {
+    // Converts linear sRGB to linear colors in these primaries, with a D65 white point.
+    fn srgb_conversion(self) -> [[f32; 3]; 3] {
+        match self {
+            ColorSpace::Srgb => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            ColorSpace::DisplayP3 => [
+                [0.822_462, 0.177_538, 0.0],
+                [0.033_194, 0.966_806, 0.0],
+                [0.017_083, 0.072_397, 0.910_520],
+            ],
+            ColorSpace::Rec2020 => [
+                [0.627_404, 0.329_283, 0.043_313],
+                [0.069_097, 0.919_540, 0.011_362],
+                [0.016_391, 0.088_013, 0.895_595],
+            ],
+        }
+    }
+}
+
+pub struct RendererConfig {
+    pub texture_format: wgpu::TextureFormat,
+    pub sample_count: u32,
+    pub depth_stencil_state: Option<wgpu::DepthStencilStateDescriptor>,
+    pub vertex_shader: Option<UserShader>,
+    pub fragment_shader: Option<UserShader>,
+    /// Pass the projection matrix as a push constant instead of a uniform
+    /// buffer. Ignored unless the device has `wgpu::Features::PUSH_CONSTANTS`.
+    pub use_push_constants: bool,
+    /// Exponent applied to the output color. `None` picks 2.2 for sRGB and
+    /// float targets, so imgui's sRGB-authored colors are linearized before
+    /// the target encodes them again, and 1.0 otherwise.
+    pub gamma: Option<f32>,
+    /// Scale applied to the linearized output color. On HDR targets this maps
+    /// UI white to the SDR reference white; see `with_sdr_white_nits`.
+    pub white_level: f32,
+    /// The color space of the surface; `set_color_transform` applies before the conversion.
+    pub output_color_space: ColorSpace,
+    /// Decode vertex colors from sRGB in the vertex stage, so they are
+    /// interpolated and blended in linear space. Gives correct antialiased
+    /// edges on linear targets.
+    pub decode_vertex_srgb: bool,
+    /// Set when Dear ImGui is built with `IMGUI_USE_BGRA_PACKED_COLOR`, so the vertex stage
+    /// unpacks vertex colors as BGRA. Ignored with a custom `vertex_shader`.
+    pub bgra_vertex_colors: bool,
+    pub clip_mode: ClipMode,
+    /// Extra bind group layouts appended after the renderer's own groups, for
+    /// custom shaders. Bind matching groups on the render pass before calling
+    /// `render`; the renderer leaves them untouched.
+    pub user_bind_group_layouts: Vec<wgpu::BindGroupLayout>,
+    /// Give each draw list its own depth and enable depth writes with a
+    /// `LessEqual` test. Needs a `depth_stencil_state`.
+    pub depth_layering: Option<DepthLayering>,
+    /// Bind the textures of each frame as one texture array and index it per
+    /// draw, instead of binding every command's texture. Ignored unless the
+    /// device has `BINDLESS_FEATURES`; implies the uniform buffer path. All
+    /// textures are sampled linearly with clamped addressing, and textures
+    /// registered with `insert_bind_group` can't be drawn.
+    pub bindless: bool,
+    /// Collect consecutive draw commands that share a texture, clip rect and
+    /// blend mode into one `multi_draw_indexed_indirect` call. Ignored unless
+    /// the device has `wgpu::Features::MULTI_DRAW_INDIRECT`. Each draw keeps its
+    /// instance index as `first_instance`, which Vulkan devices only honor with
+    /// the `drawIndirectFirstInstance` feature.
+    pub multi_draw_indirect: bool,
+    /// Extra vertex attributes for `vertex_shader`, filled in for every draw list by the
+    /// callback set with `Renderer::set_vertex_data_callback`, or zeroed without one.
+    pub extra_vertex_attributes: Option<ExtraVertexAttributes>,
+    /// Replaces the color blend of textures drawn with `BlendMode::Alpha`, the default, e.g.
+    /// so the UI conforms to a frame graph that blends premultiplied colors throughout.
+    pub color_blend: Option<wgpu::BlendDescriptor>,
+    /// Replaces the alpha blend of all blend modes, `One`, `OneMinusSrcAlpha` by default.
+    pub alpha_blend: Option<wgpu::BlendDescriptor>,
+    /// Write vertex and index data straight into mapped buffers, skipping the staging copy
+    /// of `Queue::write_buffer`; worthwhile on integrated GPUs that share memory with the
+    /// CPU. Ignored unless the device has `wgpu::Features::MAPPABLE_PRIMARY_BUFFERS`. Each
+    /// frame must be submitted before the same viewport is rendered again.
+    pub mapped_buffers: bool,
+    /// Whether `Renderer::new` builds imgui's font atlas and uploads it. Turn it off for apps
+    /// that rasterize their own glyph atlas and hand it over with `Renderer::set_font_texture`.
+    pub font_atlas: bool,
+    /// Gives custom shaders a uniform block of per-frame values, e.g. for animated effects:
+    /// `{ float time; vec2 resolution; vec2 framebuffer_scale; vec2 mouse; }` at
+    /// `Renderer::shader_globals_bind_group_index`, `binding = 0`. `time` is in seconds and
+    /// `mouse` in the coordinates of vertex positions, both from
+    /// `Renderer::update_shader_globals`; `resolution` is the drawn area in physical pixels.
+    pub shader_globals: bool,
+}
+impl RendererConfig {
+    pub fn new(texture_format: wgpu::TextureFormat) -> Self {
+        Self {
+            texture_format,
+            sample_count: 1,
+            depth_stencil_state: None,
+            vertex_shader: None,
+            fragment_shader: None,
+            use_push_constants: false,
+            gamma: None,
+            white_level: 1.0,
+            output_color_space: ColorSpace::Srgb,
+            decode_vertex_srgb: false,
+            bgra_vertex_colors: false,
+            clip_mode: ClipMode::Scissor,
+            user_bind_group_layouts: Vec::new(),
+            depth_layering: None,
+            bindless: false,
+            multi_draw_indirect: false,
+            extra_vertex_attributes: None,
+            color_blend: None,
+            alpha_blend: None,
+            mapped_buffers: false,
+            font_atlas: true,
+            shader_globals: false,
+        }
+    }
+    pub fn with_depth_format(self, format: wgpu::TextureFormat) -> Self {
+        Self {
+            depth_stencil_state: Some(wgpu::DepthStencilStateDescriptor {
+                format,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilStateDescriptor::default(),
+            }),
+            ..self
+        }
+    }
+    /// Sets `white_level` so UI white is shown at `nits` on an scRGB target such as an
+    /// `Rgba16Float` swapchain, where 1.0 is 80 nits.
+    pub fn with_sdr_white_nits(self, nits: f32) -> Self {
+        Self {
+            white_level: nits / SCRGB_REFERENCE_NITS,
+            ..self
+        }
+    }
+}
+
+const SCRGB_REFERENCE_NITS: f32 = 80.0;
+
+const IDENTITY_COLOR_TRANSFORM: [[f32; 4]; 3] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RenderTarget {
+    pub format: wgpu::TextureFormat,
+    pub sample_count: u32,
+}
+impl RenderTarget {
+    fn gamma(self) -> f32 {
+        match self.format {
+            wgpu::TextureFormat::Rgba8UnormSrgb
+            | wgpu::TextureFormat::Bgra8UnormSrgb
+            | wgpu::TextureFormat::Rgba16Float
+            | wgpu::TextureFormat::Rgba32Float => 2.2,
+            _ => 1.0,
+        }
+    }
+}
+
+struct PipelineFactory {
+    layout: wgpu::PipelineLayout,
+    vs_module: wgpu::ShaderModule,
+    vs_entry_point: String,
+    fs_module: wgpu::ShaderModule,
+    fs_entry_point: String,
+    sdf_fs_module: wgpu::ShaderModule,
+    overdraw_fs_module: wgpu::ShaderModule,
+    // Locked so that `PipelinePrewarm` can compile pipelines on another thread meanwhile.
+    texture_shaders: Mutex<Vec<UserShader>>,
+    extra_vertex_attributes: Option<ExtraVertexAttributes>,
+    color_blend: Option<wgpu::BlendDescriptor>,
+    alpha_blend: wgpu::BlendDescriptor,
+    depth_stencil_state: Option<wgpu::DepthStencilStateDescriptor>,
+    clip_mode: ClipMode,
+}
+impl PipelineFactory {
+    fn create(
+        &self,
+        device: &wgpu::Device,
+        target: RenderTarget,
+        blend_mode: BlendMode,
+        sdf: bool,
+    ) -> wgpu::RenderPipeline {
+        let fragment_stage = if sdf {
+            wgpu::ProgrammableStageDescriptor {
+                module: &self.sdf_fs_module,
+                entry_point: "main",
+            }
+        } else {
+            wgpu::ProgrammableStageDescriptor {
+                module: &self.fs_module,
+                entry_point: &self.fs_entry_point,
+            }
+        };
+        self.create_blended(device, target, blend_mode, fragment_stage)
+    }
+    // One pipeline per blend mode for a shader from `register_texture_shader`.
+    fn create_texture_shader(
+        &self,
+        device: &wgpu::Device,
+        target: RenderTarget,
+        shader: &UserShader,
+    ) -> Vec<wgpu::RenderPipeline> {
+        BlendMode::ALL
+            .iter()
+            .map(|&blend_mode| {
+                let fragment_stage = wgpu::ProgrammableStageDescriptor {
+                    module: &shader.module,
+                    entry_point: &shader.entry_point,
+                };
+                self.create_blended(device, target, blend_mode, fragment_stage)
+            })
+            .collect()
+    }
+    fn create_blended(
+        &self,
+        device: &wgpu::Device,
+        target: RenderTarget,
+        blend_mode: BlendMode,
+        fragment_stage: wgpu::ProgrammableStageDescriptor,
+    ) -> wgpu::RenderPipeline {
+        self.create_with(
+            device,
+            target,
+            Some(fragment_stage),
+            wgpu::ColorStateDescriptor {
+                format: target.format,
+                color_blend: match (blend_mode, &self.color_blend) {
+                    (BlendMode::Alpha, Some(color_blend)) => color_blend.clone(),
+                    _ => blend_mode.color_blend(),
+                },
+                alpha_blend: self.alpha_blend.clone(),
+                write_mask: wgpu::ColorWrite::ALL,
+            },
+            self.draw_depth_stencil_state(),
+        )
+    }
+    fn create_overdraw(&self, device: &wgpu::Device, target: RenderTarget) -> wgpu::RenderPipeline {
+        let additive = wgpu::BlendDescriptor {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        self.create_with(
+            device,
+            target,
+            Some(wgpu::ProgrammableStageDescriptor {
+                module: &self.overdraw_fs_module,
+                entry_point: "main",
+            }),
+            wgpu::ColorStateDescriptor {
+                format: target.format,
+                color_blend: additive.clone(),
+                alpha_blend: additive,
+                write_mask: wgpu::ColorWrite::ALL,
+            },
+            self.draw_depth_stencil_state(),
+        )
+    }
+    // Draws a full-target triangle in a solid color, which is limited to the clip rect outlines
+    // with scissors. It binds nothing, so it can be used whatever state is bound.
+    fn create_clip_outline(
+        &self,
+        device: &wgpu::Device,
+        target: RenderTarget,
+    ) -> wgpu::RenderPipeline {
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[],
+            push_constant_ranges: &[],
+        });
+        let vs_module = shaders::create_module(device, Shader::FullscreenVertex);
+        let fs_module = shaders::create_module(device, Shader::ClipOutlineFragment);
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&layout),
+            vertex_stage: wgpu::ProgrammableStageDescriptor {
+                module: &vs_module,
+                entry_point: "main",
+            },
+            fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
+                module: &fs_module,
+                entry_point: "main",
+            }),
+            rasterization_state: None,
+            primitive_topology: wgpu::PrimitiveTopology::TriangleList,
+            color_states: &[wgpu::ColorStateDescriptor {
+                format: target.format,
+                color_blend: wgpu::BlendDescriptor::REPLACE,
+                alpha_blend: wgpu::BlendDescriptor::REPLACE,
+                write_mask: wgpu::ColorWrite::ALL,
+            }],
+            depth_stencil_state: self.depth_stencil_state.clone().map(|state| {
+                wgpu::DepthStencilStateDescriptor {
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::Always,
+                    stencil: wgpu::StencilStateDescriptor::default(),
+                    ..state
+                }
+            }),
+            vertex_state: wgpu::VertexStateDescriptor {
+                index_format: INDEX_FORMAT,
+                vertex_buffers: &[],
+            },
+            sample_count: target.sample_count,
+            sample_mask: !0,
+            alpha_to_coverage_enabled: false,
+        })
+    }
+    // The depth-stencil state of the pipelines that draw UI geometry.
+    fn draw_depth_stencil_state(&self) -> Option<wgpu::DepthStencilStateDescriptor> {
+        match self.clip_mode {
+            ClipMode::Scissor => self.depth_stencil_state.clone(),
+            ClipMode::Stencil => {
+                self.depth_stencil_state
+                    .clone()
+                    .map(|state| wgpu::DepthStencilStateDescriptor {
+                        stencil: stencil_state(
+                            wgpu::CompareFunction::Equal,
+                            wgpu::StencilOperation::Keep,
+                        ),
+                        ..state
+                    })
+            }
+        }
+    }
+    fn create_clip(&self, device: &wgpu::Device, target: RenderTarget) -> wgpu::RenderPipeline {
+        let depth_stencil_state =
+            self.depth_stencil_state
+                .clone()
+                .map(|state| wgpu::DepthStencilStateDescriptor {
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::Always,
+                    stencil: stencil_state(
+                        wgpu::CompareFunction::Always,
+                        wgpu::StencilOperation::Replace,
+                    ),
+                    ..state
+                });
+        self.create_with(
+            device,
+            target,
+            None,
+            wgpu::ColorStateDescriptor {
+                format: target.format,
+                color_blend: wgpu::BlendDescriptor::REPLACE,
+                alpha_blend: wgpu::BlendDescriptor::REPLACE,
+                write_mask: wgpu::ColorWrite::empty(),
+            },
+            depth_stencil_state,
+        )
+    }
+    fn create_with(
+        &self,
+        device: &wgpu::Device,
+        target: RenderTarget,
+        fragment_stage: Option<wgpu::ProgrammableStageDescriptor>,
+        color_state: wgpu::ColorStateDescriptor,
+        depth_stencil_state: Option<wgpu::DepthStencilStateDescriptor>,
+    ) -> wgpu::RenderPipeline {
+        let attributes = DrawDataUploader::vertex_attributes();
+        let mut vertex_buffers = vec![wgpu::VertexBufferDescriptor {
+            stride: DrawDataUploader::vertex_stride(),
+            step_mode: wgpu::InputStepMode::Vertex,
+            attributes: &attributes,
+        }];
+        if let Some(extra) = &self.extra_vertex_attributes {
+            vertex_buffers.push(wgpu::VertexBufferDescriptor {
+                stride: extra.stride,
+                step_mode: wgpu::InputStepMode::Vertex,
+                attributes: &extra.attributes,
+            });
+        }
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&self.layout),
+            vertex_stage: wgpu::ProgrammableStageDescriptor {
+                module: &self.vs_module,
+                entry_point: &self.vs_entry_point,
+            },
+            fragment_stage,
+            rasterization_state: Some(wgpu::RasterizationStateDescriptor {
+                front_face: wgpu::FrontFace::Cw,
+                cull_mode: wgpu::CullMode::None,
+                ..Default::default()
+            }),
+            primitive_topology: wgpu::PrimitiveTopology::TriangleList,
+            color_states: &[color_state],
+            depth_stencil_state,
+            vertex_state: wgpu::VertexStateDescriptor {
+                index_format: INDEX_FORMAT,
+                vertex_buffers: &vertex_buffers,
+            },
+            sample_count: target.sample_count,
+            sample_mask: !0,
+            alpha_to_coverage_enabled: false,
+        })
+    }
+    fn create_all(&self, device: &wgpu::Device, target: RenderTarget) -> Pipelines {
+        Pipelines {
+            blend_modes: BlendMode::ALL
+                .iter()
+                .map(|&blend_mode| self.create(device, target, blend_mode, false))
+                .collect(),
+            sdf: BlendMode::ALL
+                .iter()
+                .map(|&blend_mode| self.create(device, target, blend_mode, true))
+                .collect(),
+            clip: match self.clip_mode {
+                ClipMode::Scissor => None,
+                ClipMode::Stencil => Some(self.create_clip(device, target)),
+            },
+            texture_shaders: self
+                .texture_shaders
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|shader| self.create_texture_shader(device, target, shader))
+                .collect(),
+            overdraw: None,
+            clip_outline: None,
+        }
+    }
+}
+
+struct Pipelines {
+    blend_modes: Vec<wgpu::RenderPipeline>,
+    sdf: Vec<wgpu::RenderPipeline>,
+    clip: Option<wgpu::RenderPipeline>,
+    // Indexed by `TextureShaderId`, then by blend mode.
+    texture_shaders: Vec<Vec<wgpu::RenderPipeline>>,
+    // Created when the overdraw visualization is first enabled.
+    overdraw: Option<wgpu::RenderPipeline>,
+    // Created when scissoring is first disabled.
+    clip_outline: Option<wgpu::RenderPipeline>,
+}
+
+fn stencil_state(
+    compare: wgpu::CompareFunction,
+    pass_op: wgpu::StencilOperation,
+) -> wgpu::StencilStateDescriptor {
+    let face = wgpu::StencilStateFaceDescriptor {
+        compare,
+        fail_op: wgpu::StencilOperation::Keep,
+        depth_fail_op: wgpu::StencilOperation::Keep,
+        pass_op,
+    };
+    wgpu::StencilStateDescriptor {
+        front: face.clone(),
+        back: face,
+        read_mask: 0xff,
+        write_mask: 0xff,
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Frame<'a> {
+    display_pos: [f32; 2],
+    display_size: [f32; 2],
+    framebuffer_scale: [f32; 2],
+    draw_lists: FrameDrawLists<'a>,
+}
+impl Frame<'_> {
+    fn is_degenerate(&self) -> bool {
+        self.display_size
+            .iter()
+            .zip(&self.framebuffer_scale)
+            .any(|(size, scale)| size * scale < 1.0)
+    }
+}
+impl<'a> From<&'a imgui::DrawData> for Frame<'a> {
+    fn from(draw_data: &'a imgui::DrawData) -> Self {
+        Self {
+            display_pos: draw_data.display_pos,
+            display_size: draw_data.display_size,
+            framebuffer_scale: draw_data.framebuffer_scale,
+            draw_lists: FrameDrawLists::DrawData(draw_data),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Layer<'a> {
+    frame: Frame<'a>,
+    projection: Option<[f32; 16]>,
+}
+impl<'a> From<&'a imgui::DrawData> for Layer<'a> {
+    fn from(draw_data: &'a imgui::DrawData) -> Self {
+        Self {
+            frame: Frame::from(draw_data),
+            projection: None,
+        }
+    }
+}
+
+/// One `DrawData` of a `Renderer::render_layers` call. `projection` overrides the renderer's
+/// projection for this layer only.
+#[derive(Clone, Copy)]
+pub struct DrawLayer<'d> {
+    pub draw_data: &'d imgui::DrawData,
+    pub projection: Option<[f32; 16]>,
+}
+impl<'d> From<&'d imgui::DrawData> for DrawLayer<'d> {
+    fn from(draw_data: &'d imgui::DrawData) -> Self {
+        Self {
+            draw_data,
+            projection: None,
+        }
+    }
+}
+
+// The draw lists of a frame, borrowed from wherever they are kept so that building a frame
+// doesn't allocate.
+#[derive(Clone, Copy)]
+enum FrameDrawLists<'a> {
+    DrawData(&'a imgui::DrawData),
+    Imgui(&'a [&'a imgui::DrawList]),
+    Snapshot(&'a [DrawListSnapshot]),
+}
+impl<'a> FrameDrawLists<'a> {
+    fn len(&self) -> usize {
+        match self {
+            FrameDrawLists::DrawData(draw_data) => draw_data.draw_lists_count(),
+            FrameDrawLists::Imgui(draw_lists) => draw_lists.len(),
+            FrameDrawLists::Snapshot(draw_lists) => draw_lists.len(),
+        }
+    }
+    fn iter(&self) -> FrameDrawListIter<'a> {
+        match *self {
+            FrameDrawLists::DrawData(draw_data) => {
+                FrameDrawListIter::DrawData(draw_data.draw_lists())
+            }
+            FrameDrawLists::Imgui(draw_lists) => FrameDrawListIter::Imgui(draw_lists.iter()),
+            FrameDrawLists::Snapshot(draw_lists) => FrameDrawListIter::Snapshot(draw_lists.iter()),
+        }
+    }
+}
+
+enum FrameDrawListIter<'a> {
+    DrawData(imgui::DrawListIterator<'a>),
+    Imgui(std::slice::Iter<'a, &'a imgui::DrawList>),
+    Snapshot(std::slice::Iter<'a, DrawListSnapshot>),
+}
+impl<'a> Iterator for FrameDrawListIter<'a> {
+    type Item = FrameDrawList<'a>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            FrameDrawListIter::DrawData(iter) => iter.next().map(FrameDrawList::Imgui),
+            FrameDrawListIter::Imgui(iter) => iter
+                .next()
+                .map(|&draw_list| FrameDrawList::Imgui(draw_list)),
+            FrameDrawListIter::Snapshot(iter) => iter.next().map(FrameDrawList::Snapshot),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum FrameDrawList<'a> {
+    Imgui(&'a imgui::DrawList),
+    Snapshot(&'a DrawListSnapshot),
+}
+impl<'a> FrameDrawList<'a> {
+    fn idx_buffer(&self) -> &'a [DrawIdx] {
+        match self {
+            FrameDrawList::Imgui(draw_list) => draw_list.idx_buffer(),
+            FrameDrawList::Snapshot(draw_list) => &draw_list.idx_buffer,
+        }
+    }
+    fn vtx_buffer(&self) -> &'a [DrawVert] {
+        match self {
+            FrameDrawList::Imgui(draw_list) => draw_list.vtx_buffer(),
+            FrameDrawList::Snapshot(draw_list) => &draw_list.vtx_buffer,
+        }
+    }
+    // The bytes its indices and vertices take in the buffers.
+    fn buffer_bytes(&self) -> (usize, usize) {
+        (
+            std::mem::size_of_val(self.idx_buffer()),
+            std::mem::size_of_val(self.vtx_buffer()),
+        )
+    }
+    fn commands(&self) -> FrameDrawCmdIter<'a> {
+        match self {
+            FrameDrawList::Imgui(draw_list) => FrameDrawCmdIter::Imgui(draw_list.commands()),
+            FrameDrawList::Snapshot(draw_list) => {
+                FrameDrawCmdIter::Snapshot(draw_list.commands.iter())
+            }
+        }
+    }
+}
+
+enum FrameDrawCmdIter<'a> {
+    Imgui(imgui::DrawCmdIterator<'a>),
+    Snapshot(std::slice::Iter<'a, DrawCmdSnapshot>),
+}
+impl Iterator for FrameDrawCmdIter<'_> {
+    type Item = imgui::DrawCmd;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            FrameDrawCmdIter::Imgui(iter) => iter.next(),
+            FrameDrawCmdIter::Snapshot(iter) => iter.next().map(|cmd| cmd.to_draw_cmd()),
+        }
+    }
+}
+
+// The bytes of imgui's vertices as they are uploaded. `DrawVert` is `repr(C)` without
+// padding, so every byte is initialized.
+fn vertex_bytes(vertices: &[DrawVert]) -> &[u8] {
+    unsafe {
+        std::slice::from_raw_parts(
+            vertices.as_ptr() as *const u8,
+            std::mem::size_of_val(vertices),
+        )
+    }
+}
+
+// Appends the stencil quads of a frame: its whole display, then each clip rect in draw order.
+fn clip_quads(frame: &Frame, quads: &mut Vec<DrawVert>) {
+    let mut push_rect = |rect: [f32; 4]| {
+        let corner = |x: f32, y: f32| DrawVert {
+            pos: [x, y],
+            uv: [0.0, 0.0],
+            col: [0; 4],
+        };
+        quads.extend_from_slice(&[
+            corner(rect[0], rect[1]),
+            corner(rect[2], rect[1]),
+            corner(rect[2], rect[3]),
+            corner(rect[0], rect[1]),
+            corner(rect[2], rect[3]),
+            corner(rect[0], rect[3]),
+        ]);
+    };
+    push_rect([
+        frame.display_pos[0],
+        frame.display_pos[1],
+        frame.display_pos[0] + frame.display_size[0],
+        frame.display_pos[1] + frame.display_size[1],
+    ]);
+    let mut last_clip_rect = None;
+    for draw_list in frame.draw_lists.iter() {
+        for draw_cmd in draw_list.commands() {
+            if let imgui::DrawCmd::Elements { cmd_params, .. } = draw_cmd {
+                if last_clip_rect != Some(cmd_params.clip_rect) {
+                    last_clip_rect = Some(cmd_params.clip_rect);
+                    push_rect(cmd_params.clip_rect);
+                }
+            }
+        }
+    }
+}
+
+// Working memory of `Renderer::render`, kept between frames so that rendering doesn't
+// allocate once the vectors have grown to the UI's size.
+#[derive(Default)]
+struct FrameScratch {
+    // The buffer chunk and index and vertex byte offsets of each draw list, in draw order.
+    offsets: Vec<(usize, u64, u64)>,
+    uniforms: Vec<Uniforms>,
+    uniform_bytes: Vec<u8>,
+    clip_quad_bases: Vec<u32>,
+    clip_quads: Vec<DrawVert>,
+    evicted: Vec<(imgui::TextureId, Texture)>,
+    flipped_vertices: Vec<usize>,
+    // Always empty between frames, like `DrawScratch::layers`.
+    #[cfg(feature = "rayon")]
+    copies: Vec<parallel_pack::DrawListCopy<'static>>,
+    // Locked while drawing, since the render pass keeps the renderer borrowed.
+    draw: Mutex<DrawScratch>,
+}
+
+#[derive(Default)]
+struct DrawScratch {
+    // Always empty between frames; `recycle` reuses it for the layers of each frame.
+    layers: Vec<Layer<'static>>,
+    indirect_draws: IndirectDraws,
+    clip_outlines: Vec<(u32, u32, u32, u32)>,
+}
+
+// Lays out draw lists whose indices and vertices take the given bytes in buffer chunks,
+// pushing the chunk and the index and vertex offsets of each onto `offsets`. A chunk takes
+// draw lists until the next doesn't fit, so one too large for any chunk gets a chunk of its
+// own. Returns the index of the last chunk.
+fn plan_chunks(
+    buffer_bytes: impl Iterator<Item = (usize, usize)>,
+    max_index_bytes: u64,
+    max_vertex_bytes: u64,
+    offsets: &mut Vec<(usize, u64, u64)>,
+) -> usize {
+    let (mut chunk, mut index_bytes, mut vertex_bytes) = (0, 0, 0);
+    for (draw_list_index_bytes, draw_list_vertex_bytes) in buffer_bytes {
+        let fits = index_bytes + draw_list_index_bytes < max_index_bytes as usize
+            && vertex_bytes + draw_list_vertex_bytes < max_vertex_bytes as usize;
+        if !fits && vertex_bytes > 0 {
+            chunk += 1;
+            index_bytes = 0;
+            vertex_bytes = 0;
+        }
+        offsets.push((
+            chunk,
+            (index_bytes / size_of!(DrawIdx)) as u64,
+            (vertex_bytes / size_of!(DrawVert)) as u64,
+        ));
+        index_bytes += draw_list_index_bytes;
+        vertex_bytes += draw_list_vertex_bytes;
+    }
+    chunk
+}
+
+// Reuses the allocation of `vec` for elements of another type with the same layout, e.g. the
+// layers of a frame with a shorter lifetime than the ones kept in `DrawScratch`.
+fn recycle<T, U>(mut vec: Vec<T>) -> Vec<U> {
+    vec.clear();
+    vec.into_iter().map(|_| unreachable!()).collect()
+}
+
+enum Transform {
+    Uniform {
+        buffer: wgpu::Buffer,
+        bind_group: wgpu::BindGroup,
+        slots: usize,
+    },
+    PushConstants,
+}
+impl Transform {
+    fn new(
+        device: &wgpu::Device,
+        uniform_bind_layout: Option<&wgpu::BindGroupLayout>,
+        slots: usize,
+    ) -> Self {
+        match uniform_bind_layout {
+            Some(layout) => {
+                let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: None,
+                    size: slots as u64 * UNIFORM_STRIDE as u64,
+                    usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::Buffer(
+                            buffer.slice(..size_of!(Uniforms) as u64),
+                        ),
+                    }],
+                    label: None,
+                });
+                Transform::Uniform {
+                    buffer,
+                    bind_group,
+                    slots,
+                }
+            }
+            None => Transform::PushConstants,
+        }
+    }
+}
+
+/// Issues draws directly, or, with an indirect buffer, queues them up until the next state
+/// change and then draws them with one `multi_draw_indexed_indirect` call.
+struct DrawBatch<'a, 's> {
+    indirect_buffer: Option<&'a wgpu::Buffer>,
+    draws: &'s mut IndirectDraws,
+}
+impl<'a> DrawBatch<'a, '_> {
+    fn draw_indexed(
+        &mut self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        indices: std::ops::Range<u32>,
+        base_vertex: i32,
+        instance: u32,
+    ) {
+        match self.indirect_buffer {
+            Some(_) => self.draws.push(indices, base_vertex, instance),
+            None => render_pass.draw_indexed(indices, base_vertex, instance..instance + 1),
+        }
+    }
+    /// Draws the queued draws; call before changing any render pass state.
+    fn flush(&mut self, render_pass: &mut wgpu::RenderPass<'a>) {
+        if let Some(indirect_buffer) = self.indirect_buffer {
+            if let Some((offset, count)) = self.draws.take_pending() {
+                render_pass.multi_draw_indexed_indirect(indirect_buffer, offset, count);
+            }
+        }
+    }
+}
+
+// The indirect draw arguments of a frame, in the order they're written to the indirect
+// buffer, and how many of them have been drawn.
+#[derive(Default)]
+struct IndirectDraws {
+    draws: Vec<DrawIndexedIndirect>,
+    flushed: usize,
+}
+impl IndirectDraws {
+    fn clear(&mut self) {
+        self.draws.clear();
+        self.flushed = 0;
+    }
+    fn push(&mut self, indices: std::ops::Range<u32>, base_vertex: i32, instance: u32) {
+        self.draws.push(DrawIndexedIndirect {
+            index_count: indices.end - indices.start,
+            instance_count: 1,
+            first_index: indices.start,
+            base_vertex,
+            first_instance: instance,
+        });
+    }
+    // The byte offset into the indirect buffer and count of the draws queued since the last
+    // call, if any.
+    fn take_pending(&mut self) -> Option<(u64, u32)> {
+        let pending = self.draws.len() - self.flushed;
+        if pending == 0 {
+            return None;
+        }
+        let offset = (self.flushed * size_of!(DrawIndexedIndirect)) as u64;
+        self.flushed = self.draws.len();
+        Some((offset, pending as u32))
+    }
+}
+
+fn scissor_rect(
+    clip_rect: [f32; 4],
+    clip_offset: [f32; 2],
+    clip_scale: [f32; 2],
+    bounds: Viewport,
+) -> Option<(u32, u32, u32, u32)> {
+    let min_x = (bounds.x + (clip_rect[0] - clip_offset[0]) * clip_scale[0])
+        .max(bounds.x)
+        .floor() as u32;
+    let min_y = (bounds.y + (clip_rect[1] - clip_offset[1]) * clip_scale[1])
+        .max(bounds.y)
+        .floor() as u32;
+    let max_x = (bounds.x + (clip_rect[2] - clip_offset[0]) * clip_scale[0])
+        .min(bounds.x + bounds.width)
+        .ceil() as u32;
+    let max_y = (bounds.y + (clip_rect[3] - clip_offset[1]) * clip_scale[1])
+        .min(bounds.y + bounds.height)
+        .ceil() as u32;
+    if max_x <= min_x || max_y <= min_y {
+        return None;
+    }
+    Some((min_x, min_y, max_x - min_x, max_y - min_y))
+}
+
+/// A rectangle of the render target, in physical pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// How much of the vertex and index buffers the UI uses, from `Renderer::buffer_stats`. Sizes
+/// are in bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BufferStats {
+    /// The size of a vertex buffer chunk, from `RendererConfig` or `set_buffer_limits`.
+    pub vertex_capacity: u64,
+    pub index_capacity: u64,
+    /// The most vertex data one chunk held in the last rendered frame. It only exceeds the
+    /// capacity when a single draw list needed a chunk of its own.
+    pub vertex_high_water: u64,
+    pub index_high_water: u64,
+    /// The chunks the last rendered frame was split into; more than one means it overflowed.
+    pub chunks: usize,
+    /// How often buffers were allocated for an extra chunk or a draw list larger than a chunk,
+    /// since the renderer was created.
+    pub grow_events: u64,
+}
+
+/// Checks that the device can create a texture like `descriptor`, e.g. before creating one
+/// for `Texture::from_descriptor` from a user-picked image.
+pub fn validate_texture_descriptor(
+    device: &wgpu::Device,
+    descriptor: &wgpu::TextureDescriptor,
+) -> RendererResult<()> {
+    validate_texture_size(descriptor.size.width, descriptor.size.height)?;
+    let compressed = matches!(
+        descriptor.format,
+        wgpu::TextureFormat::Bc1RgbaUnorm
+            | wgpu::TextureFormat::Bc1RgbaUnormSrgb
+            | wgpu::TextureFormat::Bc2RgbaUnorm
+            | wgpu::TextureFormat::Bc2RgbaUnormSrgb
+            | wgpu::TextureFormat::Bc3RgbaUnorm
+            | wgpu::TextureFormat::Bc3RgbaUnormSrgb
+            | wgpu::TextureFormat::Bc4RUnorm
+            | wgpu::TextureFormat::Bc4RSnorm
+            | wgpu::TextureFormat::Bc5RgUnorm
+            | wgpu::TextureFormat::Bc5RgSnorm
+            | wgpu::TextureFormat::Bc6hRgbSfloat
+            | wgpu::TextureFormat::Bc6hRgbUfloat
+            | wgpu::TextureFormat::Bc7RgbaUnorm
+            | wgpu::TextureFormat::Bc7RgbaUnormSrgb
+    );
+    if compressed
+        && !device
+            .features()
+            .contains(wgpu::Features::TEXTURE_COMPRESSION_BC)
+    {
+        return Err(RendererError::UnsupportedTextureFormat(descriptor.format));
+    }
+    Ok(())
+}
+
+fn validate_texture_size(width: u32, height: u32) -> RendererResult<()> {
+    if width == 0 || height == 0 {
+        Err(RendererError::EmptyTexture)
+    } else if width > MAX_TEXTURE_DIMENSION || height > MAX_TEXTURE_DIMENSION {
+        Err(RendererError::TextureTooLarge {
+            width,
+            height,
+            max: MAX_TEXTURE_DIMENSION,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+// Checks that `data_len` bytes hold `height` rows of `width` RGBA pixels that start
+// `bytes_per_row` bytes apart, for a size that passed `validate_texture_size`.
+fn validate_stride(
+    width: u32,
+    height: u32,
+    bytes_per_row: u32,
+    data_len: usize,
+) -> RendererResult<()> {
+    if bytes_per_row < width * 4 {
+        return Err(RendererError::BadRowStride {
+            bytes_per_row,
+            min: width * 4,
+        });
+    }
+    // The last row needs no padding after its pixels.
+    let expected = bytes_per_row as usize * (height as usize - 1) + width as usize * 4;
+    if data_len < expected {
+        return Err(RendererError::TextureDataSize {
+            expected,
+            actual: data_len,
+        });
+    }
+    Ok(())
+}
+
+// Tells imgui which renderer draws the context and what it supports, as the reference backends
+// do; the metrics window shows the name.
+fn register_backend(imgui: &mut imgui::Context) {
+    imgui.set_renderer_name(Some(imgui::ImString::new("imgui-wgpu-rs")));
+    imgui
+        .io_mut()
+        .backend_flags
+        .insert(imgui::BackendFlags::RENDERER_HAS_VTX_OFFSET);
+}
+
+/// The column-major matrix the renderer uses unless a projection is set with
+/// `Renderer::set_projection`; multiply onto it to transform the whole UI.
+pub fn orthographic_projection(draw_data: &imgui::DrawData) -> [f32; 16] {
+    projection(draw_data.display_pos, draw_data.display_size)
+}
+fn projection(display_pos: [f32; 2], display_size: [f32; 2]) -> [f32; 16] {
+    let left = display_pos[0];
+    let right = display_pos[0] + display_size[0];
+    let top = display_pos[1];
+    let bottom = display_pos[1] + display_size[1];
+    [
+        (2.0 / (right - left)),
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        (2.0 / (top - bottom)),
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        -1.0,
+        0.0,
+        (right + left) / (left - right),
+        (top + bottom) / (bottom - top),
+        0.0,
+        1.0,
+    ]
+}
+
+struct FrameBuffers {
+    index_buffer: wgpu::Buffer,
+    vertex_buffer: wgpu::Buffer,
+    // The `ExtraVertexAttributes` data, laid out like the vertices.
+    extra_buffer: Option<wgpu::Buffer>,
+    index_size: u64,
+    vertex_size: u64,
+    extra_size: u64,
+}
+impl FrameBuffers {
+    fn new(device: &wgpu::Device, index_size: u64, vertex_size: u64, extra_size: u64) -> Self {
+        Self::with_usage(
+            device,
+            (index_size, vertex_size, extra_size),
+            wgpu::BufferUsage::COPY_DST,
+            false,
+        )
+    }
+    // Created mapped and with `MAP_WRITE`, which needs `MAPPABLE_PRIMARY_BUFFERS`.
+    fn mapped(device: &wgpu::Device, index_size: u64, vertex_size: u64, extra_size: u64) -> Self {
+        Self::with_usage(
+            device,
+            (index_size, vertex_size, extra_size),
+            wgpu::BufferUsage::MAP_WRITE,
+            true,
+        )
+    }
+    fn with_usage(
+        device: &wgpu::Device,
+        (index_size, vertex_size, extra_size): (u64, u64, u64),
+        usage: wgpu::BufferUsage,
+        mapped_at_creation: bool,
+    ) -> Self {
+        let index_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: index_size,
+            usage: wgpu::BufferUsage::INDEX | usage,
+            mapped_at_creation,
+        });
+        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: vertex_size,
+            usage: wgpu::BufferUsage::VERTEX | usage,
+            mapped_at_creation,
+        });
+        let extra_buffer = if extra_size > 0 {
+            Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: extra_size,
+                usage: wgpu::BufferUsage::VERTEX | usage,
+                mapped_at_creation,
+            }))
+        } else {
+            None
+        };
+        Self {
+            index_buffer,
+            vertex_buffer,
+            extra_buffer,
+            index_size,
+            vertex_size,
+            extra_size,
+        }
+    }
+    fn buffers(&self) -> impl Iterator<Item = &wgpu::Buffer> {
+        std::iter::once(&self.index_buffer)
+            .chain(std::iter::once(&self.vertex_buffer))
+            .chain(self.extra_buffer.as_ref())
+    }
+}
+
+struct ViewportResources {
+    frame_buffers: Vec<FrameBuffers>,
+    clip_buffer: Option<wgpu::Buffer>,
+    transform: Transform,
+    // The indirect buffer and how many draws fit, while multi-draw-indirect is used.
+    indirect_buffer: Option<(wgpu::Buffer, usize)>,
+    cached_frame: frame_cache::CachedFrame,
+    globals: Option<shader_globals::GlobalsBuffer>,
+}
+impl ViewportResources {
+    fn new(
+        device: &wgpu::Device,
+        uniform_bind_layout: Option<&wgpu::BindGroupLayout>,
+        clip_mode: ClipMode,
+        vertex_size: u64,
+    ) -> Self {
+        let clip_buffer = match clip_mode {
+            ClipMode::Scissor => None,
+            ClipMode::Stencil => Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: vertex_size,
+                usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+                mapped_at_creation: false,
+            })),
+        };
+        Self {
+            // Created by the first upload.
+            frame_buffers: Vec::new(),
+            clip_buffer,
+            transform: Transform::new(device, uniform_bind_layout, 1),
+            indirect_buffer: None,
+            cached_frame: frame_cache::CachedFrame::default(),
+            globals: None,
+        }
+    }
+}
+
+pub struct Renderer {
+    texture_bind_layout: wgpu::BindGroupLayout,
+    pipeline_factory: Arc<PipelineFactory>,
+    pipelines: HashMap<RenderTarget, Pipelines>,
+    // The targets passed to `add_render_target`, whose pipelines are kept when the default
+    // render target changes.
+    added_render_targets: HashSet<RenderTarget>,
+    render_target: RenderTarget,
+    uniform_bind_layout: Option<wgpu::BindGroupLayout>,
+    user_bind_group_layouts: Vec<wgpu::BindGroupLayout>,
+    shader_globals: Option<shader_globals::ShaderGlobals>,
+    viewports: HashMap<u32, ViewportResources>,
+    gamma: Option<f32>,
+    white_level: f32,
+    output_color_space: ColorSpace,
+    decode_vertex_srgb: bool,
+    depth_layering: Option<DepthLayering>,
+    color_transform: [[f32; 4]; 3],
+    projection: Option<[f32; 16]>,
+    viewport: Option<Viewport>,
+    max_index_bytes: u64,
+    max_vertex_bytes: u64,
+    indices_byte_buffer: Vec<u8>,
+    vertices_byte_buffer: Vec<u8>,
+    extra_vertex_stride: u64,
+    extra_vertices_byte_buffer: Vec<u8>,
+    vertex_data_callback: Option<VertexDataCallback>,
+    textures: imgui::Textures<Texture>,
+    retired_textures: Vec<Texture>,
+    // imgui::Textures never hands out an id twice, so remembering the removed ones is enough
+    // to tell a stale id from one that never existed. Only the most recent are kept.
+    removed_textures: RemovedTextures,
+    font_textures: Vec<imgui::TextureId>,
+    // The next id from `FIRST_INTERNAL_TEXTURE_ID` up; internal ids are never reused.
+    next_internal_texture_id: usize,
+    // Ids mirrored from another renderer by `share_textures`.
+    shared_textures: Vec<imgui::TextureId>,
+    draw_callbacks: HashMap<usize, Box<dyn DrawCallback + Send>>,
+    draw_hook: Option<Box<dyn DrawCallback + Send>>,
+    texture_gc: TextureGc,
+    texture_pool: TexturePool,
+    texture_array: Option<TextureArray>,
+    multi_draw_indirect: bool,
+    debug_overdraw: bool,
+    scissor_disabled: bool,
+    upload_encoder: Option<wgpu::CommandEncoder>,
+    mapped_buffers: Option<MappedBufferPool>,
+    samplers: Samplers,
+    default_sampler: SamplerId,
+    // The sampler of the font atlases, once `set_font_filter` replaced the default one.
+    font_sampler: Option<SamplerId>,
+    // Registered by the first `set_texture_checkerboard`.
+    checkerboard_shader: Option<TextureShaderId>,
+    #[cfg(feature = "hot-reload")]
+    texture_files: Option<hot_reload::TextureFiles>,
+    #[cfg(feature = "svg")]
+    svg_icons: svg::SvgIcons,
+    scratch: FrameScratch,
+    buffer_stats: BufferStats,
+    dropped_textures: DroppedTextures,
+    transient_views: image_ext::TransientViews,
+    multisampled: multisample::MultisampledTextures,
+}
+impl Renderer {
+    /// Uploads tightly packed RGBA pixels, `width * height * 4` bytes of them.
+    pub fn upload_texture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        data: &[u8],
+    ) -> RendererResult<imgui::TextureId> {
+        validate_texture_size(width, height)?;
+        let expected = width as usize * height as usize * 4;
+        if data.len() != expected {
+            return Err(RendererError::TextureDataSize {
+                expected,
+                actual: data.len(),
+            });
+        }
+        self.upload_texture_with_stride(device, queue, width, height, width * 4, data)
+    }
+    /// Like `upload_texture`, but the rows of `data` start `bytes_per_row` bytes apart, so
+    /// padded images can be uploaded without repacking them.
+    pub fn upload_texture_with_stride(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        bytes_per_row: u32,
+        data: &[u8],
+    ) -> RendererResult<imgui::TextureId> {
+        profile_scope!("imgui-wgpu upload_texture");
+        validate_texture_size(width, height)?;
+        validate_stride(width, height, bytes_per_row, data.len())?;
+        let texture = match self.texture_pool.take(&upload_descriptor(width, height)) {
+            Some(mut texture) => {
+                texture.blend_mode = BlendMode::default();
+                texture.sdf = false;
+                texture.shader = None;
+                texture.flip_v = false;
+                if texture.sampler != Some(self.default_sampler) {
+                    texture.set_sampler(
+                        device,
+                        &self.texture_bind_layout,
+                        self.default_sampler,
+                        self.samplers.get(self.default_sampler),
+                    );
+                }
+                texture
+            }
+            None => self.allocate_texture(device, width, height),
+        };
+        texture.write_rows(queue, data, bytes_per_row);
+        let texture_id = self.textures.insert(texture);
+        self.texture_gc.track(texture_id);
+        debug!("uploaded {}x{} texture as {:?}", width, height, texture_id);
+        Ok(texture_id)
+    }
+    /// The layout of every texture's bind group: `binding = 0` is a filterable float
+    /// `texture2D` and `binding = 1` a non-comparison `sampler`, both visible to the fragment
+    /// stage.
+    pub fn texture_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.texture_bind_layout
+    }
+    /// Registers a bind group created with `texture_layout()`, e.g. for a view of an existing
+    /// render target, and returns its id for `imgui::Image`.
+    pub fn insert_bind_group(&mut self, bind_group: wgpu::BindGroup) -> imgui::TextureId {
+        self.insert_texture(Texture::from_bind_group(bind_group))
+    }
+    pub fn insert_texture(&mut self, texture: Texture) -> imgui::TextureId {
+        let texture_id = self.textures.insert(texture);
+        self.texture_gc.track(texture_id);
+        texture_id
+    }
+    /// Registers `layers` of a `D2Array` texture as textures of their own, in layer order, so
+    /// single layers such as shadow cascades can be shown with `imgui::Image`. Each gets a
+    /// view of its layer, which keeps the array alive, and the default sampler. Like bind
+    /// groups from `insert_bind_group`, they can't be drawn in bindless mode.
+    pub fn insert_texture_layers(
+        &mut self,
+        device: &wgpu::Device,
+        texture: &wgpu::Texture,
+        layers: std::ops::Range<u32>,
+    ) -> Vec<imgui::TextureId> {
+        layers
+            .map(|layer| {
+                let view = texture.create_view(&wgpu::TextureViewDescriptor {
+                    dimension: Some(wgpu::TextureViewDimension::D2),
+                    base_array_layer: layer,
+                    array_layer_count: std::num::NonZeroU32::new(1),
+                    ..Default::default()
+                });
+                let mut layer_texture = Texture::from_bind_group(texture_bind_group(
+                    device,
+                    &self.texture_bind_layout,
+                    &view,
+                    self.samplers.get(self.default_sampler),
+                ));
+                layer_texture.sampler = Some(self.default_sampler);
+                self.insert_texture(layer_texture)
+            })
+            .collect()
+    }
+    pub fn set_texture_blend_mode(
+        &mut self,
+        texture_id: imgui::TextureId,
+        blend_mode: BlendMode,
+    ) -> RendererResult<()> {
+        let texture = self
+            .textures
+            .get_mut(texture_id)
+            .ok_or(RendererError::BadTexture(texture_id))?;
+        texture.blend_mode = blend_mode;
+        Ok(())
+    }
+    /// Draws the texture as a signed distance field, with the distance in alpha and 0.5 on
+    /// the outline, so it stays sharp when the UI is magnified or projected.
+    pub fn set_texture_sdf(
+        &mut self,
+        texture_id: imgui::TextureId,
+        sdf: bool,
+    ) -> RendererResult<()> {
+        let texture = self
+            .textures
+            .get_mut(texture_id)
+            .ok_or(RendererError::BadTexture(texture_id))?;
+        texture.sdf = sdf;
+        Ok(())
+    }
+    /// Mirrors the V coordinate of everything drawn with the texture, for render targets or
+    /// images with a bottom-left origin that would otherwise appear upside down. The UVs of
+    /// its draw commands' vertices are flipped as the draw data is uploaded.
+    pub fn set_texture_flip_v(
+        &mut self,
+        texture_id: imgui::TextureId,
+        flip_v: bool,
+    ) -> RendererResult<()> {
+        let texture = self
+            .textures
+            .get_mut(texture_id)
+            .ok_or(RendererError::BadTexture(texture_id))?;
+        texture.flip_v = flip_v;
+        Ok(())
+    }
+    /// Registers a fragment shader to draw individual textures with, e.g. to show a depth
+    /// buffer or an id map in false color. It has the same inputs and bindings as a
+    /// `RendererConfig::fragment_shader`.
+    pub fn register_texture_shader(
+        &mut self,
+        device: &wgpu::Device,
+        shader: UserShader,
+    ) -> TextureShaderId {
+        for (&target, pipelines) in &mut self.pipelines {
+            pipelines.texture_shaders.push(
+                self.pipeline_factory
+                    .create_texture_shader(device, target, &shader),
+            );
+        }
+        let mut texture_shaders = self.pipeline_factory.texture_shaders.lock().unwrap();
+        texture_shaders.push(shader);
+        TextureShaderId(texture_shaders.len() - 1)
+    }
+    /// Draws the texture with a shader from `register_texture_shader`, or with the built-in
+    /// one for `None`. The texture's blend mode still applies; `set_texture_sdf` is ignored
+    /// while a shader is set.
+    pub fn set_texture_shader(
+        &mut self,
+        texture_id: imgui::TextureId,
+        shader: Option<TextureShaderId>,
+    ) -> RendererResult<()> {
+        let texture = self
+            .textures
+            .get_mut(texture_id)
+            .ok_or(RendererError::BadTexture(texture_id))?;
+        texture.shader = shader;
+        Ok(())
+    }
+    /// Shows the texture over a gray checkerboard, so its transparent parts can be seen the
+    /// way image editors show them, e.g. in an asset browser. This draws the texture with a
+    /// built-in texture shader, in place of one set with `set_texture_shader`.
+    pub fn set_texture_checkerboard(
+        &mut self,
+        device: &wgpu::Device,
+        texture_id: imgui::TextureId,
+        checkerboard: bool,
+    ) -> RendererResult<()> {
+        if self.textures.get(texture_id).is_none() {
+            return Err(RendererError::BadTexture(texture_id));
+        }
+        let shader = match (checkerboard, self.checkerboard_shader) {
+            (false, _) => None,
+            (true, Some(shader)) => Some(shader),
+            (true, None) => {
+                let shader = if self.texture_array.is_some() {
+                    Shader::BindlessCheckerFragment
+                } else if self.uniform_bind_layout.is_none() {
+                    Shader::PushCheckerFragment
+                } else {
+                    Shader::CheckerFragment
+                };
+                let shader = self.register_texture_shader(
+                    device,
+                    UserShader {
+                        module: shaders::create_module(device, shader),
+                        entry_point: String::from("main"),
+                    },
+                );
+                self.checkerboard_shader = Some(shader);
+                Some(shader)
+            }
+        };
+        let texture = self.textures.get_mut(texture_id).unwrap();
+        if checkerboard || texture.shader == self.checkerboard_shader {
+            texture.shader = shader;
+        }
+        Ok(())
+    }
+    /// Frees a texture. Its allocation is pooled and, from the next render on, reused by an
+    /// upload of the same size.
+    pub fn remove_texture(&mut self, texture_id: imgui::TextureId) -> RendererResult<()> {
+        self.texture_gc.untrack(texture_id);
+        let texture = self
+            .textures
+            .remove(texture_id)
+            .ok_or(RendererError::BadTexture(texture_id))?;
+        self.texture_pool.recycle(texture);
+        self.removed_textures.insert(texture_id);
+        Ok(())
+    }
+    /// Hands ownership of a registered texture to a `TextureHandle`, which removes it like
+    /// `remove_texture` once dropped, e.g. `renderer.texture_handle(renderer.upload_texture(..)?)`.
+    pub fn texture_handle(&self, texture_id: imgui::TextureId) -> TextureHandle {
+        self.dropped_textures.handle(texture_id)
+    }
+    /// Every registered texture, including the font atlas, whose id lies in a range of its
+    /// own. Insert and remove textures through the renderer so it can keep track of them.
+    pub fn textures(&self) -> &imgui::Textures<Texture> {
+        &self.textures
+    }
+    /// The ids of the textures uploaded or inserted through the renderer and not removed yet,
+    /// leaving out font atlases and textures mirrored by `share_textures`.
+    pub fn user_texture_ids(&self) -> Vec<imgui::TextureId> {
+        self.texture_gc
+            .tracked()
+            .filter(|&texture_id| self.textures.get(texture_id).is_some())
+            .collect()
+    }
+    /// Removes every texture listed by `user_texture_ids`, e.g. when a scene is unloaded.
+    pub fn clear_user_textures(&mut self) {
+        for texture_id in self.user_texture_ids() {
+            let _ = self.remove_texture(texture_id);
+        }
+    }
+    /// Looks up a texture, reporting `StaleTexture` rather than `BadTexture` for ids that were
+    /// removed, e.g. ones cached by the application or evicted by the texture GC. Only the
+    /// last 1024 removed ids are remembered; older ones report `BadTexture` again.
+    pub fn checked_texture(&self, texture_id: imgui::TextureId) -> RendererResult<&Texture> {
+        match self.textures.get(texture_id) {
+            Some(texture) => Ok(texture),
+            None if self.removed_textures.contains(&texture_id) => {
+                Err(RendererError::StaleTexture(texture_id))
+            }
+            None => Err(RendererError::BadTexture(texture_id)),
+        }
+    }
+    /// Returns a sampler created from `descriptor`, reusing an earlier one with the same
+    /// settings.
+    pub fn cached_sampler(
+        &mut self,
+        device: &wgpu::Device,
+        descriptor: &wgpu::SamplerDescriptor,
+    ) -> SamplerId {
+        self.samplers.cached(device, descriptor)
+    }
+    /// Takes ownership of `sampler` so textures can share it through `set_texture_sampler`.
+    pub fn register_sampler(&mut self, sampler: wgpu::Sampler) -> SamplerId {
+        self.samplers.register(sampler)
+    }
+    /// For `Texture::with_sampler`. Fails for an id from another renderer.
+    pub fn sampler(&self, sampler_id: SamplerId) -> RendererResult<&wgpu::Sampler> {
+        self.samplers.checked(sampler_id)
+    }
+    /// Samples a texture with another of the renderer's samplers, e.g. a nearest-neighbor one
+    /// for pixel art. Fails for textures registered with `insert_bind_group`, and has no effect
+    /// in bindless mode.
+    pub fn set_texture_sampler(
+        &mut self,
+        device: &wgpu::Device,
+        texture_id: imgui::TextureId,
+        sampler_id: SamplerId,
+    ) -> RendererResult<()> {
+        let sampler = self.samplers.checked(sampler_id)?;
+        let texture = self
+            .textures
+            .get_mut(texture_id)
+            .ok_or(RendererError::BadTexture(texture_id))?;
+        if texture.set_sampler(device, &self.texture_bind_layout, sampler_id, sampler) {
+            Ok(())
+        } else {
+            Err(RendererError::BadTexture(texture_id))
+        }
+    }
+    /// Makes a texture repeat or mirror-repeat beyond the `0..1` UV range along each axis, e.g.
+    /// to tile a small pattern over a widget's background with oversized UVs. The texture's
+    /// filtering is kept. Like `set_texture_sampler`, this fails for textures registered with
+    /// `insert_bind_group` and has no effect in bindless mode.
+    pub fn set_texture_address_modes(
+        &mut self,
+        device: &wgpu::Device,
+        texture_id: imgui::TextureId,
+        address_mode_u: wgpu::AddressMode,
+        address_mode_v: wgpu::AddressMode,
+    ) -> RendererResult<()> {
+        let texture = self
+            .textures
+            .get(texture_id)
+            .ok_or(RendererError::BadTexture(texture_id))?;
+        let current = texture.sampler.unwrap_or(self.default_sampler);
+        let descriptor = wgpu::SamplerDescriptor {
+            address_mode_u,
+            address_mode_v,
+            ..self
+                .samplers
+                .descriptor(current)
+                .unwrap_or_else(linear_sampler_descriptor)
+        };
+        let sampler_id = self.samplers.cached(device, &descriptor);
+        self.set_texture_sampler(device, texture_id, sampler_id)
+    }
+    /// Samples the font atlases with `filter`, e.g. `Nearest` for crisp bitmap fonts or to
+    /// check whether blurry text at a fractional DPI scale comes from filtering. Only the
+    /// atlases' bind groups are rebuilt, and atlases uploaded later keep the filter. Atlases
+    /// set with `set_font_texture` from a bind group keep their sampler. Like
+    /// `set_texture_sampler`, this has no effect in bindless mode.
+    pub fn set_font_filter(&mut self, device: &wgpu::Device, filter: wgpu::FilterMode) {
+        let descriptor = wgpu::SamplerDescriptor {
+            mag_filter: filter,
+            min_filter: filter,
+            ..self
+                .samplers
+                .descriptor(self.default_sampler)
+                .unwrap_or_else(linear_sampler_descriptor)
+        };
+        let sampler_id = self.samplers.cached(device, &descriptor);
+        self.font_sampler = Some(sampler_id);
+        for texture_id in self.font_textures.clone() {
+            let _ = self.set_texture_sampler(device, texture_id, sampler_id);
+        }
+    }
+    /// Limits how many removed textures are kept for reuse; 0 disables pooling.
+    pub fn set_texture_pool_capacity(&mut self, capacity: usize) {
+        self.texture_pool.set_capacity(capacity);
+    }
+    /// Uploads the font atlas again after fonts changed. The atlas keeps its `TextureId`; when
+    /// its size is unchanged the existing texture is rewritten instead of reallocated. Font
+    /// atlases have ids of their own, apart from user textures, so ids the application
+    /// holds keep pointing at its textures.
+    pub fn reload_font_texture(
+        &mut self,
+        imgui: &mut imgui::Context,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> RendererResult<()> {
+        register_backend(imgui);
+        self.rebuild_fonts(imgui, device, queue)
+    }
+    /// Uploads the font atlas of another imgui context so this renderer can draw its UI too.
+    /// Unlike `reload_font_texture` this never replaces a texture, since a fresh context's
+    /// font `TextureId` may collide with one that is already registered. When several
+    /// contexts are rendered before a submit, give each its own id in `render_viewport`.
+    pub fn attach_context(
+        &mut self,
+        imgui: &mut imgui::Context,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> RendererResult<()> {
+        register_backend(imgui);
+        let mut fonts = imgui.fonts();
+        let texture_data = fonts.build_rgba32_texture();
+        validate_texture_size(texture_data.width, texture_data.height)?;
+        let texture = self.allocate_font_texture(device, texture_data.width, texture_data.height);
+        texture.write(queue, texture_data.data);
+        debug!(
+            "attached {}x{} font atlas",
+            texture_data.width, texture_data.height
+        );
+        fonts.tex_id = self.insert_font_texture(texture);
+        fonts.clear_tex_data();
+        Ok(())
+    }
+    /// Makes this renderer draw the font atlases of `primary`, and with `user_textures` its
+    /// other textures too, under the same ids and without uploading them again, e.g. for a
+    /// second renderer with another swapchain format. Both renderers must use the same device.
+    ///
+    /// Call it again after the primary's textures change. Textures should then only be added
+    /// to the primary, since ids this renderer hands out may collide with the shared ones.
+    /// Shared textures can't be drawn in bindless mode.
+    pub fn share_textures(
+        &mut self,
+        device: &wgpu::Device,
+        primary: &Renderer,
+        user_textures: bool,
+    ) {
+        let mut texture_ids = primary.font_textures.clone();
+        if user_textures {
+            texture_ids.extend(primary.texture_gc.tracked());
+        }
+        for texture_id in std::mem::take(&mut self.shared_textures) {
+            if !texture_ids.contains(&texture_id) {
+                self.retired_textures
+                    .extend(self.textures.remove(texture_id));
+                self.removed_textures.insert(texture_id);
+            }
+        }
+        for texture_id in texture_ids {
+            let texture = match primary.textures.get(texture_id) {
+                Some(texture) => texture,
+                None => continue,
+            };
+            // Textures with a sampler of their own sample like the default one.
+            let sampler = match texture.sampler {
+                Some(sampler_id) => primary.samplers.get(sampler_id),
+                None => self.samplers.get(self.default_sampler),
+            };
+            if let Some(mirror) = texture.mirror(device, &self.texture_bind_layout, sampler) {
+                self.replace_texture(texture_id, mirror);
+                self.shared_textures.push(texture_id);
+            }
+        }
+    }
+    /// Frees the font atlas uploaded for a context that is no longer rendered.
+    pub fn detach_context(&mut self, imgui: &mut imgui::Context) {
+        let tex_id = imgui.fonts().tex_id;
+        if self.font_textures.contains(&tex_id) {
+            self.textures.remove(tex_id);
+            self.removed_textures.insert(tex_id);
+            self.font_textures.retain(|id| *id != tex_id);
+        }
+    }
+    /// Rebuilds the font atlas after fonts were added or changed at runtime. The atlas keeps
+    /// its `TextureId`. A same-size atlas is rewritten in place; otherwise the previous
+    /// texture is kept alive until the next frame is rendered so already recorded frames can
+    /// still use it. Fails with `TextureTooLarge` if the atlas outgrows the maximum texture
+    /// size, leaving the previous atlas in place.
+    pub fn rebuild_fonts(
+        &mut self,
+        imgui: &mut imgui::Context,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> RendererResult<()> {
+        let mut fonts = imgui.fonts();
+        let texture_id = fonts.tex_id;
+        let texture_data = fonts.build_rgba32_texture();
+        debug!(
+            "rebuilding {}x{} font atlas",
+            texture_data.width, texture_data.height
+        );
+        fonts.tex_id = self.upload_font_atlas(device, queue, texture_id, texture_data, false)?;
+        fonts.clear_tex_data();
+        Ok(())
+    }
+    /// Like `rebuild_fonts`, but turns the atlas into a signed distance field drawn with
+    /// `set_texture_sdf`, so text stays sharp when scaled up. `spread` is how many atlas pixels
+    /// the field extends beyond each outline; rasterize fonts at a large size for best results.
+    /// Fails like `rebuild_fonts` for an atlas larger than the maximum texture size.
+    pub fn rebuild_fonts_sdf(
+        &mut self,
+        imgui: &mut imgui::Context,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        spread: f32,
+    ) -> RendererResult<()> {
+        let mut fonts = imgui.fonts();
+        let texture_id = fonts.tex_id;
+        let texture_data = fonts.build_alpha8_texture();
+        debug!(
+            "rebuilding {}x{} font atlas as a distance field",
+            texture_data.width, texture_data.height
+        );
+        // Checked before the distance field is computed for an atlas that can't be uploaded.
+        validate_texture_size(texture_data.width, texture_data.height)?;
+        let pixels = sdf::alpha_to_sdf(
+            texture_data.data,
+            texture_data.width,
+            texture_data.height,
+            spread,
+        );
+        let texture_data = imgui::FontAtlasTexture {
+            width: texture_data.width,
+            height: texture_data.height,
+            data: &pixels,
+        };
+        fonts.tex_id = self.upload_font_atlas(device, queue, texture_id, texture_data, true)?;
+        fonts.clear_tex_data();
+        Ok(())
+    }
+    /// Makes an externally managed glyph atlas the font texture of `imgui`, e.g. one built with
+    /// freetype and harfbuzz, and returns its id. A font atlas of this renderer already set on
+    /// the context is replaced under the same id. Like the built-in atlas, it is never evicted
+    /// by the texture GC, and `reload_font_texture` overwrites it with imgui's own atlas.
+    pub fn set_font_texture(
+        &mut self,
+        imgui: &mut imgui::Context,
+        texture: Texture,
+    ) -> imgui::TextureId {
+        let mut fonts = imgui.fonts();
+        if self.font_textures.contains(&fonts.tex_id) && self.textures.get(fonts.tex_id).is_some() {
+            self.replace_texture(fonts.tex_id, texture);
+        } else {
+            fonts.tex_id = self.insert_font_texture(texture);
+        }
+        fonts.tex_id
+    }
+    // Stores a font atlas under `texture_id` if it is one of ours, rewriting it in place when
+    // the size allows, and registers it otherwise. Returns the atlas' id.
+    fn upload_font_atlas(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture_id: imgui::TextureId,
+        texture_data: imgui::FontAtlasTexture,
+        sdf: bool,
+    ) -> RendererResult<imgui::TextureId> {
+        validate_texture_size(texture_data.width, texture_data.height)?;
+        if self.font_textures.contains(&texture_id) {
+            if let Some(texture) = self.textures.get_mut(texture_id) {
+                let same_size = texture.size().map(|size| (size.width, size.height))
+                    == Some((texture_data.width, texture_data.height));
+                if same_size {
+                    texture.write(queue, texture_data.data);
+                    texture.sdf = sdf;
+                    return Ok(texture_id);
+                }
+            }
+        }
+        let mut texture =
+            self.allocate_font_texture(device, texture_data.width, texture_data.height);
+        texture.write(queue, texture_data.data);
+        texture.sdf = sdf;
+        if self.font_textures.contains(&texture_id) {
+            self.replace_texture(texture_id, texture);
+            Ok(texture_id)
+        } else {
+            Ok(self.insert_font_texture(texture))
+        }
+    }
+    // Registers a font atlas under the next id of the internal range.
+    fn insert_font_texture(&mut self, texture: Texture) -> imgui::TextureId {
+        let texture_id = imgui::TextureId::from(self.next_internal_texture_id);
+        self.next_internal_texture_id += 1;
+        self.textures.replace(texture_id, texture);
+        self.font_textures.push(texture_id);
+        texture_id
+    }
+    // Swaps in a new `width`×`height` allocation under `texture_id`, keeping its blend mode,
+    // SDF flag, shader, flip and sampler. The contents are left for the caller to write.
+    // None if the id isn't registered.
+    #[cfg(any(feature = "hot-reload", feature = "svg"))]
+    fn reallocate_texture(
+        &mut self,
+        device: &wgpu::Device,
+        texture_id: imgui::TextureId,
+        width: u32,
+        height: u32,
+    ) -> Option<&Texture> {
+        let old = self.textures.get(texture_id)?;
+        let mut texture = self.allocate_texture(device, width, height);
+        texture.blend_mode = old.blend_mode;
+        texture.sdf = old.sdf;
+        texture.shader = old.shader;
+        texture.flip_v = old.flip_v;
+        if let Some(sampler_id) = old.sampler.filter(|&id| id != self.default_sampler) {
+            texture.set_sampler(
+                device,
+                &self.texture_bind_layout,
+                sampler_id,
+                self.samplers.get(sampler_id),
+            );
+        }
+        self.replace_texture(texture_id, texture);
+        self.textures.get(texture_id)
+    }
+    // Swaps in a new texture under an existing id, keeping the old one alive until the next
+    // render in case a recorded pass still uses it.
+    fn replace_texture(&mut self, texture_id: imgui::TextureId, texture: Texture) {
+        let old = self.textures.replace(texture_id, texture);
+        self.retired_textures.extend(old);
+        if let Some(texture_array) = &mut self.texture_array {
+            texture_array.invalidate();
+        }
+    }
+    pub fn user_bind_group_layouts(&self) -> &[wgpu::BindGroupLayout] {
+        &self.user_bind_group_layouts
+    }
+    /// The group index of the first of `RendererConfig::user_bind_group_layouts`.
+    pub fn user_bind_group_index(&self) -> u32 {
+        if self.uniform_bind_layout.is_some() {
+            2
+        } else {
+            1
+        }
+    }
+    /// Frees textures that no draw command has used for more than `max_unused_frames`
+    /// frames, calling `on_evict` with each freed id so it can be uploaded again when
+    /// needed. Frames are counted by `end_frame`, which also does the freeing. Font atlases
+    /// are never evicted.
+    pub fn enable_texture_gc<F: FnMut(imgui::TextureId) + Send + 'static>(
+        &mut self,
+        max_unused_frames: u64,
+        on_evict: F,
+    ) {
+        self.texture_gc
+            .set_policy(Some((max_unused_frames, Box::new(on_evict))));
+    }
+    pub fn disable_texture_gc(&mut self) {
+        self.texture_gc.set_policy(None);
+    }
+    /// Ends an application frame, once all of its viewports are rendered. Only needed with
+    /// `enable_texture_gc`, which counts frames by these calls.
+    pub fn end_frame(&mut self) {
+        self.texture_gc
+            .end_frame(&mut self.textures, &mut self.scratch.evicted);
+        for (texture_id, texture) in self.scratch.evicted.drain(..) {
+            self.texture_pool.recycle(texture);
+            self.removed_textures.insert(texture_id);
+        }
+    }
+    /// Registers `callback` to run wherever `push_draw_callback` was called with `id`.
+    pub fn add_draw_callback<C: DrawCallback + Send + 'static>(&mut self, id: usize, callback: C) {
+        self.draw_callbacks.insert(id, Box::new(callback));
+    }
+    pub fn remove_draw_callback(&mut self, id: usize) {
+        self.draw_callbacks.remove(&id);
+    }
+    /// Runs `hook` right before each `DrawCmd::Elements` is drawn, with the renderer's state
+    /// already bound, e.g. to push debug markers or bind another texture for some ids. State
+    /// it changes applies to that command only.
+    pub fn set_draw_hook<C: DrawCallback + Send + 'static>(&mut self, hook: C) {
+        self.draw_hook = Some(Box::new(hook));
+    }
+    pub fn remove_draw_hook(&mut self) {
+        self.draw_hook = None;
+    }
+    pub fn add_render_target(&mut self, device: &wgpu::Device, target: RenderTarget) {
+        self.added_render_targets.insert(target);
+        self.create_pipelines(device, target);
+    }
+    fn create_pipelines(&mut self, device: &wgpu::Device, target: RenderTarget) {
+        let pipeline_factory = &self.pipeline_factory;
+        let pipelines = self
+            .pipelines
+            .entry(target)
+            .or_insert_with(|| pipeline_factory.create_all(device, target));
+        if self.debug_overdraw && pipelines.overdraw.is_none() {
+            pipelines.overdraw = Some(pipeline_factory.create_overdraw(device, target));
+        }
+        if self.scissor_disabled && pipelines.clip_outline.is_none() {
+            pipelines.clip_outline = Some(pipeline_factory.create_clip_outline(device, target));
+        }
+        // Prewarmed pipelines miss the texture shaders registered while they compiled.
+        let texture_shaders = pipeline_factory.texture_shaders.lock().unwrap();
+        for shader in &texture_shaders[pipelines.texture_shaders.len()..] {
+            pipelines
+                .texture_shaders
+                .push(pipeline_factory.create_texture_shader(device, target, shader));
+        }
+    }
+    /// Draws every triangle with a constant color and additive blending instead of its
+    /// texture, so the brightness of each pixel shows how many triangles cover it. Best
+    /// viewed over a black clear color.
+    pub fn set_debug_overdraw(&mut self, device: &wgpu::Device, enabled: bool) {
+        self.debug_overdraw = enabled;
+        if enabled {
+            for (&target, pipelines) in &mut self.pipelines {
+                if pipelines.overdraw.is_none() {
+                    pipelines.overdraw =
+                        Some(self.pipeline_factory.create_overdraw(device, target));
+                }
+            }
+        }
+    }
+    /// Draws every command with the whole viewport as its scissor, and outlines the clip rects
+    /// that would have been used in magenta, to tell clipping bugs from geometry bugs. Has no
+    /// effect with `ClipMode::Stencil`.
+    pub fn set_scissor_disabled(&mut self, device: &wgpu::Device, disabled: bool) {
+        self.scissor_disabled = disabled;
+        if disabled {
+            for (&target, pipelines) in &mut self.pipelines {
+                if pipelines.clip_outline.is_none() {
+                    pipelines.clip_outline =
+                        Some(self.pipeline_factory.create_clip_outline(device, target));
+                }
+            }
+        }
+    }
+    /// Changes the format of the default render target. The pipelines of the previous one
+    /// are dropped, unless it was added with `add_render_target`.
+    pub fn set_render_target_format(&mut self, device: &wgpu::Device, format: wgpu::TextureFormat) {
+        let previous = self.render_target;
+        self.render_target.format = format;
+        self.create_pipelines(device, self.render_target);
+        if previous != self.render_target && !self.added_render_targets.contains(&previous) {
+            self.pipelines.remove(&previous);
+        }
+    }
+    /// Changes `RendererConfig::white_level`, e.g. when the display's SDR white changes.
+    pub fn set_white_level(&mut self, white_level: f32) {
+        self.white_level = white_level;
+    }
+    /// Changes `RendererConfig::output_color_space`, e.g. when the window moves to another
+    /// display.
+    pub fn set_output_color_space(&mut self, color_space: ColorSpace) {
+        self.output_color_space = color_space;
+    }
+    /// Applies an affine color transform to the linearized output, e.g. to match an engine's
+    /// grading or tint the UI for a night mode. Each row yields one output channel as the dot
+    /// product with `(r, g, b, 1)`; `None` restores the identity.
+    pub fn set_color_transform(&mut self, color_transform: Option<[[f32; 4]; 3]>) {
+        self.color_transform = color_transform.unwrap_or(IDENTITY_COLOR_TRANSFORM);
+    }
+    /// Replaces the orthographic projection; `None` restores it. Scissor
+    /// clipping stays in untransformed framebuffer space, so rotated or
+    /// perspective projections should use `ClipMode::Stencil`.
+    pub fn set_projection(&mut self, projection: Option<[f32; 16]>) {
+        self.projection = projection;
+    }
+    /// Draws the UI scaled into a sub-rectangle of the render target instead
+    /// of covering it; `None` restores the default.
+    pub fn set_viewport(&mut self, viewport: Option<Viewport>) {
+        self.viewport = viewport;
+    }
+    /// Changes the size of each vertex and index buffer chunk, e.g. to shrink the buffers while
+    /// only a small HUD is shown. The buffers of all viewports are freed and allocated again at
+    /// the new size when they are next rendered; textures are kept.
+    pub fn set_buffer_limits(&mut self, max_vertex_bytes: u64, max_index_bytes: u64) {
+        self.max_vertex_bytes = max_vertex_bytes;
+        self.max_index_bytes = max_index_bytes;
+        self.viewports.clear();
+        if let Some(mapped_buffers) = &mut self.mapped_buffers {
+            *mapped_buffers = MappedBufferPool::default();
+        }
+        self.vertices_byte_buffer = Vec::with_capacity(max_vertex_bytes as usize);
+        self.indices_byte_buffer = Vec::with_capacity(max_index_bytes as usize);
+    }
+    /// The buffer capacities and how much of them the last rendered frame used, to tune
+    /// `set_buffer_limits` or warn when the UI approaches them.
+    pub fn buffer_stats(&self) -> BufferStats {
+        BufferStats {
+            vertex_capacity: self.max_vertex_bytes,
+            index_capacity: self.max_index_bytes,
+            ..self.buffer_stats
+        }
+    }
+    /// Sets the callback that fills in `RendererConfig::extra_vertex_attributes`. It is called
+    /// with the index of each draw list in the rendered frame, its vertex count and the bytes
+    /// to write, `stride` per vertex.
+    pub fn set_vertex_data_callback<F: FnMut(usize, usize, &mut [u8]) + Send + 'static>(
+        &mut self,
+        callback: F,
+    ) {
+        self.vertex_data_callback = Some(Box::new(callback));
+    }
+    /// Records the vertex, index and uniform uploads of the following render calls into
+    /// `encoder` as copies from staging buffers, instead of writing them through the queue.
+    /// Take it back with `take_upload_encoder` and submit it before the render passes that
+    /// draw those frames. The indirect draw arguments of `multi_draw_indirect` are still
+    /// written through the queue.
+    pub fn set_upload_encoder(&mut self, encoder: wgpu::CommandEncoder) {
+        self.upload_encoder = Some(encoder);
+    }
+    /// Returns the encoder given to `set_upload_encoder`, going back to queue writes.
+    pub fn take_upload_encoder(&mut self) -> Option<wgpu::CommandEncoder> {
+        self.upload_encoder.take()
+    }
+    /// Frees the buffers of a platform viewport once its window has been destroyed.
+    pub fn remove_viewport(&mut self, viewport_id: u32) {
+        self.viewports.remove(&viewport_id);
+    }
+    pub fn render<'a>(
+        &'a mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        draw_data: &imgui::DrawData,
+    ) -> RendererResult<()> {
+        let target = self.render_target;
+        self.render_with_target(device, queue, render_pass, draw_data, target)
+    }
+    /// Begins a render pass on `target_view`, draws the UI and ends the pass. The view must
+    /// match the configured render target; a configured depth format is not supported here.
+    pub fn render_with_encoder(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        target_view: &wgpu::TextureView,
+        load_op: wgpu::LoadOp<wgpu::Color>,
+        draw_data: &imgui::DrawData,
+    ) -> RendererResult<()> {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: target_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: load_op,
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+        self.render(device, queue, &mut render_pass, draw_data)
+    }
+    /// `render` for a pass drawing to `target` rather than the default render target. The
+    /// pipelines for a target not added with `add_render_target` are created by the first
+    /// frame drawn to it.
+    pub fn render_with_target<'a>(
+        &'a mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        draw_data: &imgui::DrawData,
+        target: RenderTarget,
+    ) -> RendererResult<()> {
+        self.render_viewport(
+            device,
+            queue,
+            render_pass,
+            draw_data,
+            MAIN_VIEWPORT_ID,
+            target,
+        )
+    }
+    /// Renders the draw data of one imgui platform viewport. Each viewport id gets its own
+    /// vertex, index and uniform buffers, so several viewports can be recorded into different
+    /// surfaces before the queue is submitted.
+    pub fn render_viewport<'a>(
+        &'a mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        draw_data: &imgui::DrawData,
+        viewport_id: u32,
+        target: RenderTarget,
+    ) -> RendererResult<()> {
+        self.render_frame(
+            device,
+            queue,
+            render_pass,
+            std::iter::once(Layer::from(draw_data)),
+            viewport_id,
+            target,
+        )
+    }
+    /// Renders several `DrawData`s into one render pass, in order. Each layer gets its own
+    /// uniforms, so e.g. a magnified overlay can use a different projection than the main UI.
+    pub fn render_layers<'a>(
+        &'a mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        layers: &[DrawLayer],
+    ) -> RendererResult<()> {
+        let target = self.render_target;
+        self.render_draw_layers(device, queue, render_pass, layers, MAIN_VIEWPORT_ID, target)
+    }
+    pub(crate) fn render_draw_layers<'a>(
+        &'a mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-        bind_group_layout: &wgpu::BindGroupLayout,
-        width: u32,
-        height: u32,
-        pixels: &[u8],
-    ) -> Self {
-        let texture_extent = wgpu::Extent3d {
-            width,
-            height,
-            depth: 1,
-        };
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: None,
-            size: texture_extent,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8Unorm,
-            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
-        });
-        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        queue.write_texture(
-            wgpu::TextureCopyView {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            pixels,
-            wgpu::TextureDataLayout {
-                offset: 0,
-                bytes_per_row: (width * 4) as u32,
-                rows_per_image: 0,
-            },
-            texture_extent,
-        );
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::FilterMode::Linear,
-            ..Default::default()
-        });
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-            ],
-            label: None,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        layers: &[DrawLayer],
+        viewport_id: u32,
+        target: RenderTarget,
+    ) -> RendererResult<()> {
+        let layers = layers.iter().map(|layer| Layer {
+            frame: Frame::from(layer.draw_data),
+            projection: layer.projection,
         });
-        Self { bind_group }
+        self.render_frame(device, queue, render_pass, layers, viewport_id, target)
     }
-}
-
-pub struct Renderer {
-    texture_bind_layout: wgpu::BindGroupLayout,
-    pipeline: wgpu::RenderPipeline,
-    index_buffer: wgpu::Buffer,
-    vertex_buffer: wgpu::Buffer,
-    uniform_buffer: wgpu::Buffer,
-    uniform_buffer_bind_group: wgpu::BindGroup,
-    indices_byte_buffer: Vec<u8>,
-    vertices_byte_buffer: Vec<u8>,
-    textures: imgui::Textures<Texture>,
-}
-impl Renderer {
-    pub fn upload_texture(
-        &mut self,
+    /// Replays a recorded frame into the main viewport of the current render target.
+    pub fn render_snapshot<'a>(
+        &'a mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-        width: u32,
-        height: u32,
-        data: &[u8],
-    ) -> imgui::TextureId {
-        let texture = Texture::new(
+        render_pass: &mut wgpu::RenderPass<'a>,
+        snapshot: &DrawDataSnapshot,
+    ) -> RendererResult<()> {
+        let target = self.render_target;
+        self.render_frame(
             device,
             queue,
-            &self.texture_bind_layout,
-            width,
-            height,
-            data,
+            render_pass,
+            std::iter::once(Layer {
+                frame: Frame::from(snapshot),
+                projection: None,
+            }),
+            MAIN_VIEWPORT_ID,
+            target,
+        )
+    }
+    /// Decodes draw data serialized with `DrawDataSnapshot::to_bytes`, e.g. on another
+    /// machine, and renders it. Texture ids are used as-is, so the sender's textures must
+    /// have been uploaded here under the same ids.
+    pub fn render_serialized<'a>(
+        &'a mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        bytes: &[u8],
+    ) -> RendererResult<()> {
+        let snapshot =
+            DrawDataSnapshot::read_from(bytes).map_err(|_| RendererError::InvalidSnapshot)?;
+        self.render_snapshot(device, queue, render_pass, &snapshot)
+    }
+    fn render_frame<'a, 'l>(
+        &'a mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        layers: impl IntoIterator<Item = Layer<'l>>,
+        viewport_id: u32,
+        target: RenderTarget,
+    ) -> RendererResult<()> {
+        profile_scope!("imgui-wgpu render");
+        self.retired_textures.clear();
+        self.texture_pool.release();
+        for texture_id in self.dropped_textures.take() {
+            // The texture may have been removed by id or evicted already.
+            let _ = self.remove_texture(texture_id);
+        }
+        self.register_transient_images();
+        let scratch = self.scratch.draw.get_mut().unwrap();
+        let mut frame_layers: Vec<Layer> = recycle(std::mem::take(&mut scratch.layers));
+        // Minimized windows report a zero display size; drawing them would need a degenerate
+        // projection and viewport.
+        frame_layers.extend(
+            layers
+                .into_iter()
+                .filter(|layer| !layer.frame.is_degenerate()),
         );
-        self.textures.insert(texture)
+        if frame_layers.is_empty() {
+            scratch.layers = recycle(frame_layers);
+            return Ok(());
+        }
+        self.upload_frame(device, queue, &frame_layers, viewport_id, target)?;
+        self.draw_frame(render_pass, &frame_layers, viewport_id)?;
+        let mut scratch = self.scratch.draw.lock().unwrap();
+        if let Some((indirect_buffer, _)) = &self.viewports[&viewport_id].indirect_buffer {
+            // The render pass keeps `self` borrowed, so this one can't go through the upload
+            // encoder; queue writes land before any command buffer of the next submission.
+            queue.write_buffer(
+                indirect_buffer,
+                0,
+                bytemuck::cast_slice(&scratch.indirect_draws.draws),
+            );
+        }
+        scratch.layers = recycle(frame_layers);
+        Ok(())
     }
-    pub fn reload_font_texture(
+    // Uploads the vertices, indices and uniforms of `layers` into the viewport's buffers and
+    // caches what `draw_frame` needs to draw them.
+    fn upload_frame(
         &mut self,
-        imgui: &mut imgui::Context,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-    ) {
-        let mut fonts = imgui.fonts();
-
-        self.textures.remove(fonts.tex_id);
-
-        let texture_data = fonts.build_rgba32_texture();
-        let texture = Texture::new(
-            device,
-            queue,
-            &self.texture_bind_layout,
-            texture_data.width,
-            texture_data.height,
-            texture_data.data,
+        layers: &[Layer],
+        viewport_id: u32,
+        target: RenderTarget,
+    ) -> RendererResult<()> {
+        self.texture_gc
+            .mark_used(layers.iter().map(|layer| &layer.frame));
+        if let Some(texture_array) = &mut self.texture_array {
+            texture_array.update(
+                device,
+                layers.iter().map(|layer| &layer.frame),
+                &self.textures,
+            )?;
+        }
+        // Targets that weren't added with `add_render_target` get their pipelines on first use.
+        if !self.pipelines.contains_key(&target) {
+            self.create_pipelines(device, target);
+        }
+        if !self.viewports.contains_key(&viewport_id) {
+            let resources = ViewportResources::new(
+                device,
+                self.uniform_bind_layout.as_ref(),
+                self.pipeline_factory.clip_mode,
+                self.max_vertex_bytes,
+            );
+            self.viewports.insert(viewport_id, resources);
+        }
+        // Until this frame is uploaded, the buffers hold neither it nor the cached frame.
+        let resources = self.viewports.get_mut(&viewport_id).unwrap();
+        resources.cached_frame.target = None;
+        if let Some(mapped_buffers) = &mut self.mapped_buffers {
+            // The previous frame of this viewport has been submitted, so its buffers can be
+            // mapped again.
+            for buffers in resources.frame_buffers.drain(..) {
+                mapped_buffers.recycle(buffers);
+            }
+            mapped_buffers.poll(device);
+        }
+        self.scratch.offsets.clear();
+        self.buffer_stats.vertex_high_water = 0;
+        self.buffer_stats.index_high_water = 0;
+        let chunk = self.pack_draw_lists(device, queue, layers, viewport_id)?;
+        trace!(
+            "rendering {} layers ({} vertices, {} indices) from {} buffer chunks",
+            layers.len(),
+            layers
+                .iter()
+                .flat_map(|layer| layer.frame.draw_lists.iter())
+                .map(|draw_list| draw_list.vtx_buffer().len())
+                .sum::<usize>(),
+            layers
+                .iter()
+                .flat_map(|layer| layer.frame.draw_lists.iter())
+                .map(|draw_list| draw_list.idx_buffer().len())
+                .sum::<usize>(),
+            chunk + 1
         );
-        fonts.tex_id = self.textures.insert(texture);
-        fonts.clear_tex_data();
+        let gamma = self.gamma.unwrap_or_else(|| target.gamma());
+        let vertex_gamma = if self.decode_vertex_srgb { 2.2 } else { 1.0 };
+        let gamut = self.output_color_space.srgb_conversion();
+        let mut color_transform = [[0.0; 4]; 3];
+        for (row, gamut_row) in color_transform.iter_mut().zip(gamut.iter()) {
+            for (column, value) in row.iter_mut().enumerate() {
+                let converted: f32 = gamut_row
+                    .iter()
+                    .zip(self.color_transform.iter())
+                    .map(|(weight, transform_row)| weight * transform_row[column])
+                    .sum();
+                *value = converted * self.white_level;
+            }
+        }
+        let (depth_layering, default_projection) = (self.depth_layering, self.projection);
+        self.scratch.uniforms.clear();
+        self.scratch.uniforms.extend(layers.iter().map(|layer| {
+            let (depth_base, depth_step) = match depth_layering {
+                Some(DepthLayering { back, front }) => {
+                    let step = (front - back) / layer.frame.draw_lists.len().max(1) as f32;
+                    (back + step, step)
+                }
+                None => (0.0, 0.0),
+            };
+            Uniforms {
+                projection: layer.projection.or(default_projection).unwrap_or_else(|| {
+                    projection(layer.frame.display_pos, layer.frame.display_size)
+                }),
+                gamma,
+                vertex_gamma,
+                depth_base,
+                depth_step,
+                color_transform,
+            }
+        }));
+        let resources = self.viewports.get_mut(&viewport_id).unwrap();
+        if let Transform::Uniform { slots, .. } = resources.transform {
+            if slots < layers.len() {
+                resources.transform =
+                    Transform::new(device, self.uniform_bind_layout.as_ref(), layers.len());
+            }
+        }
+        if self.multi_draw_indirect {
+            let draw_count = layers
+                .iter()
+                .flat_map(|layer| layer.frame.draw_lists.iter())
+                .flat_map(|draw_list| draw_list.commands())
+                .filter(|draw_cmd| matches!(draw_cmd, imgui::DrawCmd::Elements { .. }))
+                .count();
+            let capacity = resources
+                .indirect_buffer
+                .as_ref()
+                .map_or(0, |(_, capacity)| *capacity);
+            if capacity < draw_count {
+                let capacity = draw_count.next_power_of_two();
+                let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: None,
+                    size: (capacity * size_of!(DrawIndexedIndirect)) as u64,
+                    usage: wgpu::BufferUsage::INDIRECT | wgpu::BufferUsage::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                resources.indirect_buffer = Some((buffer, capacity));
+            }
+        }
+        let resources = &self.viewports[&viewport_id];
+        self.scratch.clip_quad_bases.clear();
+        if let Some(clip_buffer) = &resources.clip_buffer {
+            let quads = &mut self.scratch.clip_quads;
+            quads.clear();
+            for layer in layers {
+                self.scratch.clip_quad_bases.push(quads.len() as u32);
+                clip_quads(&layer.frame, quads);
+            }
+            let bytes = vertex_bytes(quads);
+            if bytes.len() as u64 > self.max_vertex_bytes {
+                return Err(RendererError::VertexBufferOverflow);
+            }
+            write_buffer(
+                device,
+                queue,
+                self.upload_encoder.as_mut(),
+                clip_buffer,
+                bytes,
+            );
+        }
+        if let Transform::Uniform { buffer, .. } = &resources.transform {
+            let bytes = &mut self.scratch.uniform_bytes;
+            bytes.clear();
+            bytes.resize(self.scratch.uniforms.len() * UNIFORM_STRIDE as usize, 0);
+            for (slot, layer_uniforms) in bytes
+                .chunks_mut(UNIFORM_STRIDE as usize)
+                .zip(self.scratch.uniforms.iter())
+            {
+                slot[..size_of!(Uniforms)].copy_from_slice(bytemuck::bytes_of(layer_uniforms));
+            }
+            write_buffer(device, queue, self.upload_encoder.as_mut(), buffer, bytes);
+        }
+        self.write_shader_globals(device, queue, layers, viewport_id);
+        let resources = self.viewports.get_mut(&viewport_id).unwrap();
+        resources.cached_frame.record(layers, target, &self.scratch);
+        Ok(())
     }
-    pub fn render<'a>(
-        &'a mut self,
-        queue: &wgpu::Queue,
+    // Records the draws of an uploaded frame into `render_pass`, using the offsets and
+    // uniforms the viewport cached when it was uploaded. Leaves the draws to write into the
+    // viewport's indirect buffer, if it has one, in `DrawScratch::indirect_draws`.
+    fn draw_frame<'a>(
+        &'a self,
         render_pass: &mut wgpu::RenderPass<'a>,
-        draw_data: &imgui::DrawData,
-    ) {
-        let left = draw_data.display_pos[0];
-        let right = draw_data.display_pos[0] + draw_data.display_size[0];
-        let top = draw_data.display_pos[1];
-        let bottom = draw_data.display_pos[1] + draw_data.display_size[1];
-        let matrix = [
-            (2.0 / (right - left)),
-            0.0,
-            0.0,
-            0.0,
-            0.0,
-            (2.0 / (top - bottom)),
-            0.0,
-            0.0,
-            0.0,
-            0.0,
-            -1.0,
-            0.0,
-            (right + left) / (left - right),
-            (top + bottom) / (bottom - top),
-            0.0,
-            1.0,
-        ];
-        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&matrix));
-        let mut offsets = Vec::<(u64, u64)>::new();
-        for draw_list in draw_data.draw_lists() {
-            offsets.push((
-                self.append_indices(draw_list.idx_buffer()).unwrap(),
-                self.append_vertices(draw_list.vtx_buffer()).unwrap(),
-            ))
-        }
-        self.upload_buffers(queue);
-        render_pass.set_pipeline(&self.pipeline);
-        render_pass.set_index_buffer(self.index_buffer.slice(..));
-        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        render_pass.set_bind_group(0, &self.uniform_buffer_bind_group, &[]);
-        for draw_list in draw_data.draw_lists() {
-            let (idx_offset, vtx_offset) = *offsets.first().unwrap();
-            offsets.remove(0);
-            let mut idx_begin = idx_offset as u32;
-            for draw_cmd in draw_list.commands() {
-                match draw_cmd {
-                    imgui::DrawCmd::Elements { count, cmd_params } => {
-                        let scissor = (
-                            cmd_params.clip_rect[0].max(0.0).floor() as u32,
-                            cmd_params.clip_rect[1].max(0.0).floor() as u32,
-                            (cmd_params.clip_rect[2] - cmd_params.clip_rect[0])
-                                .abs()
-                                .ceil() as u32,
-                            (cmd_params.clip_rect[3] - cmd_params.clip_rect[1])
-                                .abs()
-                                .ceil() as u32,
-                        );
-                        render_pass.set_scissor_rect(scissor.0, scissor.1, scissor.2, scissor.3);
-                        let texture = self.textures.get(cmd_params.texture_id).unwrap();
-                        render_pass.set_bind_group(1, texture.bind_group(), &[]);
-                        let idx_end = idx_begin + count as u32;
-                        render_pass.draw_indexed(idx_begin..idx_end, vtx_offset as i32, 0..1);
-                        idx_begin = idx_end;
-                    }
-                    imgui::DrawCmd::RawCallback { callback, raw_cmd } => unsafe {
-                        callback(draw_list.raw(), raw_cmd);
+        layers: &[Layer],
+        viewport_id: u32,
+    ) -> RendererResult<()> {
+        let resources = &self.viewports[&viewport_id];
+        let cached_frame = &resources.cached_frame;
+        let target = cached_frame.target.expect("frame uploaded before drawing");
+        let pipelines = self
+            .pipelines
+            .get(&target)
+            .ok_or(RendererError::UnknownRenderTarget(target))?;
+        let overdraw = if self.debug_overdraw {
+            pipelines.overdraw.as_ref()
+        } else {
+            None
+        };
+        let texture_group = match &resources.transform {
+            Transform::Uniform { .. } => 1,
+            Transform::PushConstants => 0,
+        };
+        let uniforms = &cached_frame.uniforms;
+        let mut offsets = cached_frame.offsets.iter().copied();
+        let mut scratch = self.scratch.draw.lock().unwrap();
+        let DrawScratch {
+            indirect_draws,
+            clip_outlines,
+            ..
+        } = &mut *scratch;
+        indirect_draws.clear();
+        let mut batch = DrawBatch {
+            indirect_buffer: resources.indirect_buffer.as_ref().map(|(buffer, _)| buffer),
+            draws: indirect_draws,
+        };
+        if let (Some(index), Some(globals)) =
+            (self.shader_globals_bind_group_index(), &resources.globals)
+        {
+            render_pass.set_bind_group(index, &globals.bind_group, &[]);
+        }
+        let mut frame_buffers = None;
+        let mut stencil_reference = 0;
+        for (layer_index, layer) in layers.iter().enumerate() {
+            let frame = &layer.frame;
+            let (clip_bounds, clip_scale) = match self.viewport {
+                Some(viewport) => (
+                    viewport,
+                    [
+                        viewport.width / frame.display_size[0],
+                        viewport.height / frame.display_size[1],
+                    ],
+                ),
+                None => (
+                    Viewport {
+                        x: 0.0,
+                        y: 0.0,
+                        width: (frame.display_size[0] * frame.framebuffer_scale[0]).round(),
+                        height: (frame.display_size[1] * frame.framebuffer_scale[1]).round(),
                     },
-                    _ => {}
+                    frame.framebuffer_scale,
+                ),
+            };
+            let clip_quad_base = cached_frame
+                .clip_quad_bases
+                .get(layer_index)
+                .copied()
+                .unwrap_or(0);
+            let mut clip_quad = clip_quad_base;
+            let mut clip_rect = None;
+            let mut pipeline = None;
+            let mut bound_texture = None;
+            let mut scissor = None;
+            let mut state_bound = false;
+            let full_scissor = scissor_rect(
+                [
+                    frame.display_pos[0],
+                    frame.display_pos[1],
+                    frame.display_pos[0] + frame.display_size[0],
+                    frame.display_pos[1] + frame.display_size[1],
+                ],
+                frame.display_pos,
+                clip_scale,
+                clip_bounds,
+            );
+            clip_outlines.clear();
+            for (list_index, (draw_list, (chunk, idx_offset, vtx_offset))) in
+                frame.draw_lists.iter().zip(&mut offsets).enumerate()
+            {
+                let list_index = list_index as u32;
+                let buffers = &resources.frame_buffers[chunk];
+                for draw_cmd in draw_list.commands() {
+                    match draw_cmd {
+                        imgui::DrawCmd::Elements { count, cmd_params } => {
+                            if !state_bound {
+                                batch.flush(render_pass);
+                                state_bound = true;
+                                if let Some(viewport) = self.viewport {
+                                    render_pass.set_viewport(
+                                        viewport.x,
+                                        viewport.y,
+                                        viewport.width,
+                                        viewport.height,
+                                        0.0,
+                                        1.0,
+                                    );
+                                }
+                                match &resources.transform {
+                                    Transform::Uniform { bind_group, .. } => {
+                                        render_pass.set_bind_group(
+                                            0,
+                                            bind_group,
+                                            &[layer_index as u32 * UNIFORM_STRIDE],
+                                        );
+                                    }
+                                    Transform::PushConstants => {
+                                        render_pass.set_push_constants(
+                                            wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
+                                            0,
+                                            bytemuck::cast_slice(&[uniforms[layer_index]]),
+                                        );
+                                    }
+                                }
+                                if let Some(texture_array) = &self.texture_array {
+                                    render_pass.set_bind_group(1, texture_array.bind_group(), &[]);
+                                }
+                                if pipelines.clip.is_some() {
+                                    render_pass.set_stencil_reference(stencil_reference);
+                                }
+                                frame_buffers = None;
+                                pipeline = None;
+                                bound_texture = None;
+                                scissor = None;
+                            }
+                            if frame_buffers != Some(chunk) {
+                                batch.flush(render_pass);
+                                frame_buffers = Some(chunk);
+                                render_pass.set_index_buffer(buffers.index_buffer.slice(..));
+                                render_pass.set_vertex_buffer(0, buffers.vertex_buffer.slice(..));
+                                if let Some(extra_buffer) = &buffers.extra_buffer {
+                                    render_pass.set_vertex_buffer(1, extra_buffer.slice(..));
+                                }
+                            }
+                            let idx_begin = idx_offset as u32 + cmd_params.idx_offset as u32;
+                            let idx_end = idx_begin + count as u32;
+                            let texture = self.checked_texture(cmd_params.texture_id)?;
+                            let instance = match &self.texture_array {
+                                Some(texture_array) => {
+                                    list_index * bindless::TEXTURE_ARRAY_SIZE
+                                        + texture_array.slot(cmd_params.texture_id)?
+                                }
+                                None => {
+                                    if bound_texture != Some(cmd_params.texture_id) {
+                                        batch.flush(render_pass);
+                                        bound_texture = Some(cmd_params.texture_id);
+                                        render_pass.set_bind_group(
+                                            texture_group,
+                                            texture.bind_group(),
+                                            &[],
+                                        );
+                                    }
+                                    list_index
+                                }
+                            };
+                            match &pipelines.clip {
+                                None => {
+                                    let mut rect = scissor_rect(
+                                        cmd_params.clip_rect,
+                                        frame.display_pos,
+                                        clip_scale,
+                                        clip_bounds,
+                                    );
+                                    if self.scissor_disabled {
+                                        if let Some(rect) = rect {
+                                            if !clip_outlines.contains(&rect) {
+                                                clip_outlines.push(rect);
+                                            }
+                                        }
+                                        rect = full_scissor;
+                                    }
+                                    let rect = match rect {
+                                        Some(rect) => rect,
+                                        None => continue,
+                                    };
+                                    if scissor != Some(rect) {
+                                        batch.flush(render_pass);
+                                        scissor = Some(rect);
+                                        render_pass
+                                            .set_scissor_rect(rect.0, rect.1, rect.2, rect.3);
+                                    }
+                                }
+                                Some(clip_pipeline) if clip_rect != Some(cmd_params.clip_rect) => {
+                                    batch.flush(render_pass);
+                                    clip_rect = Some(cmd_params.clip_rect);
+                                    render_pass.set_pipeline(clip_pipeline);
+                                    if let Some(clip_buffer) = &resources.clip_buffer {
+                                        render_pass.set_vertex_buffer(0, clip_buffer.slice(..));
+                                    }
+                                    if stencil_reference == 0xff {
+                                        stencil_reference = 0;
+                                        render_pass.set_stencil_reference(stencil_reference);
+                                        render_pass.draw(clip_quad_base..clip_quad_base + 6, 0..1);
+                                    }
+                                    stencil_reference += 1;
+                                    clip_quad += 6;
+                                    render_pass.set_stencil_reference(stencil_reference);
+                                    render_pass.draw(clip_quad..clip_quad + 6, 0..1);
+                                    render_pass
+                                        .set_vertex_buffer(0, buffers.vertex_buffer.slice(..));
+                                    pipeline = None;
+                                }
+                                Some(_) => {}
+                            }
+                            let key = (texture.blend_mode, texture.sdf, texture.shader);
+                            if pipeline != Some(key) {
+                                batch.flush(render_pass);
+                                pipeline = Some(key);
+                                let variants = match texture.shader {
+                                    Some(shader) => &pipelines.texture_shaders[shader.0],
+                                    None if texture.sdf => &pipelines.sdf,
+                                    None => &pipelines.blend_modes,
+                                };
+                                render_pass.set_pipeline(match overdraw {
+                                    Some(overdraw) => overdraw,
+                                    None => &variants[texture.blend_mode as usize],
+                                });
+                            }
+                            if let Some(draw_hook) = &self.draw_hook {
+                                batch.flush(render_pass);
+                                draw_hook.draw(render_pass, &cmd_params);
+                                state_bound = false;
+                            }
+                            batch.draw_indexed(
+                                render_pass,
+                                idx_begin..idx_end,
+                                (vtx_offset + cmd_params.vtx_offset as u64) as i32,
+                                instance,
+                            );
+                        }
+                        imgui::DrawCmd::ResetRenderState => state_bound = false,
+                        imgui::DrawCmd::RawCallback { callback, raw_cmd } => {
+                            match unsafe { callbacks::draw_callback_id(callback, raw_cmd) } {
+                                Some((id, cmd_params)) => {
+                                    if let Some(draw_callback) = self.draw_callbacks.get(&id) {
+                                        batch.flush(render_pass);
+                                        draw_callback.draw(render_pass, &cmd_params);
+                                        state_bound = false;
+                                    }
+                                }
+                                None => {
+                                    if let FrameDrawList::Imgui(draw_list) = draw_list {
+                                        unsafe { callback(draw_list.raw(), raw_cmd) };
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            // Drawn last so no command covers them.
+            if let (Some(clip_outline), false) = (&pipelines.clip_outline, clip_outlines.is_empty())
+            {
+                batch.flush(render_pass);
+                render_pass.set_pipeline(clip_outline);
+                for &(x, y, width, height) in clip_outlines.iter() {
+                    for &(x, y, width, height) in &[
+                        (x, y, width, 1),
+                        (x, y + height - 1, width, 1),
+                        (x, y, 1, height),
+                        (x + width - 1, y, 1, height),
+                    ] {
+                        render_pass.set_scissor_rect(x, y, width, height);
+                        render_pass.draw(0..3, 0..1);
+                    }
                 }
             }
         }
+        batch.flush(render_pass);
+        Ok(())
     }
     pub fn new(
         imgui: &mut imgui::Context,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-        swap_chain_texture_format: wgpu::TextureFormat,
-    ) -> Self {
-        let uniform_buffer_bind_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: None,
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStage::VERTEX,
-                    ty: wgpu::BindingType::UniformBuffer {
-                        dynamic: false,
-                        min_binding_size: wgpu::BufferSize::new(4 * 16),
-                    },
-                    count: None,
-                }],
-            });
+        config: RendererConfig,
+    ) -> RendererResult<Self> {
+        let font_atlas = config.font_atlas;
+        let mut renderer = Self::without_fonts(device, config);
+        if font_atlas {
+            renderer.attach_context(imgui, device, queue)?;
+        } else {
+            register_backend(imgui);
+        }
+        Ok(renderer)
+    }
+    /// Creates the GPU resources without touching an imgui context. Call `reload_font_texture`
+    /// or `attach_context` once the context exists, before rendering any frame.
+    pub fn without_fonts(device: &wgpu::Device, config: RendererConfig) -> Self {
+        let texture_array = if config.bindless && device.features().contains(BINDLESS_FEATURES) {
+            Some(TextureArray::new(device))
+        } else {
+            None
+        };
+        let use_push_constants = config.use_push_constants
+            && texture_array.is_none()
+            && device.features().contains(wgpu::Features::PUSH_CONSTANTS)
+            && device.limits().max_push_constant_size >= size_of!(Uniforms) as u32;
+        let uniform_buffer_bind_layout = if use_push_constants {
+            None
+        } else {
+            Some(
+                device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: None,
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::UniformBuffer {
+                            dynamic: true,
+                            min_binding_size: wgpu::BufferSize::new(size_of!(Uniforms) as u64),
+                        },
+                        count: None,
+                    }],
+                }),
+            )
+        };
         let texture_bind_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: None,
@@ -262,149 +3311,542 @@ impl Renderer {
                 ],
             });
 
+        let shader_globals = if config.shader_globals {
+            Some(shader_globals::ShaderGlobals::new(device))
+        } else {
+            None
+        };
+        let bind_group_layouts: Vec<&wgpu::BindGroupLayout> = uniform_buffer_bind_layout
+            .iter()
+            .chain(std::iter::once(match &texture_array {
+                Some(texture_array) => texture_array.layout(),
+                None => &texture_bind_layout,
+            }))
+            .chain(&config.user_bind_group_layouts)
+            .chain(shader_globals.as_ref().map(|globals| &globals.layout))
+            .collect();
+        let push_constant_ranges: &[wgpu::PushConstantRange] = if use_push_constants {
+            &[wgpu::PushConstantRange {
+                stages: wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
+                range: 0..size_of!(Uniforms) as u32,
+            }]
+        } else {
+            &[]
+        };
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
-            bind_group_layouts: &[&uniform_buffer_bind_layout, &texture_bind_layout],
-            push_constant_ranges: &[],
+            bind_group_layouts: &bind_group_layouts,
+            push_constant_ranges,
         });
 
-        let vs_module = device.create_shader_module(wgpu::include_spirv!("imgui.vert.spv"));
-        let fs_module = device.create_shader_module(wgpu::include_spirv!("imgui.frag.spv"));
-
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
-            vertex_stage: wgpu::ProgrammableStageDescriptor {
-                module: &vs_module,
-                entry_point: "main",
+        let (vs_module, vs_entry_point) = match config.vertex_shader {
+            Some(shader) => (shader.module, shader.entry_point),
+            None => {
+                let bindless = texture_array.is_some();
+                let shader = match (bindless, use_push_constants, config.bgra_vertex_colors) {
+                    (true, _, false) => Shader::BindlessVertex,
+                    (true, _, true) => Shader::BindlessBgraVertex,
+                    (false, true, false) => Shader::PushVertex,
+                    (false, true, true) => Shader::PushBgraVertex,
+                    (false, false, false) => Shader::Vertex,
+                    (false, false, true) => Shader::BgraVertex,
+                };
+                (shaders::create_module(device, shader), String::from("main"))
+            }
+        };
+        let (fs_module, fs_entry_point) = match config.fragment_shader {
+            Some(shader) => (shader.module, shader.entry_point),
+            None if texture_array.is_some() => (
+                shaders::create_module(device, Shader::BindlessFragment),
+                String::from("main"),
+            ),
+            None if use_push_constants => (
+                shaders::create_module(device, Shader::PushFragment),
+                String::from("main"),
+            ),
+            None => (
+                shaders::create_module(device, Shader::Fragment),
+                String::from("main"),
+            ),
+        };
+        let sdf_fs_module = shaders::create_module(
+            device,
+            if texture_array.is_some() {
+                Shader::BindlessSdfFragment
+            } else if use_push_constants {
+                Shader::PushSdfFragment
+            } else {
+                Shader::SdfFragment
             },
-            fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
-                module: &fs_module,
-                entry_point: "main",
-            }),
-            rasterization_state: Some(wgpu::RasterizationStateDescriptor {
-                front_face: wgpu::FrontFace::Cw,
-                cull_mode: wgpu::CullMode::None,
-                ..Default::default()
+        );
+
+        let pipeline_factory = PipelineFactory {
+            layout: pipeline_layout,
+            vs_module,
+            vs_entry_point,
+            fs_module,
+            fs_entry_point,
+            sdf_fs_module,
+            overdraw_fs_module: shaders::create_module(device, Shader::OverdrawFragment),
+            texture_shaders: Mutex::new(Vec::new()),
+            extra_vertex_attributes: config.extra_vertex_attributes,
+            color_blend: config.color_blend,
+            alpha_blend: config.alpha_blend.unwrap_or(wgpu::BlendDescriptor {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                operation: wgpu::BlendOperation::Add,
             }),
-            primitive_topology: wgpu::PrimitiveTopology::TriangleList,
-            color_states: &[wgpu::ColorStateDescriptor {
-                format: swap_chain_texture_format,
-                color_blend: wgpu::BlendDescriptor {
-                    src_factor: wgpu::BlendFactor::SrcAlpha,
-                    dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                    operation: wgpu::BlendOperation::Add,
-                },
-                alpha_blend: wgpu::BlendDescriptor {
-                    src_factor: wgpu::BlendFactor::One,
-                    dst_factor: wgpu::BlendFactor::Zero,
-                    operation: wgpu::BlendOperation::Add,
-                },
-                write_mask: wgpu::ColorWrite::ALL,
-            }],
-            depth_stencil_state: None,
-            vertex_state: wgpu::VertexStateDescriptor {
-                index_format: wgpu::IndexFormat::Uint16,
-                vertex_buffers: &[wgpu::VertexBufferDescriptor {
-                    stride: size_of!(DrawVert) as wgpu::BufferAddress,
-                    step_mode: wgpu::InputStepMode::Vertex,
-                    attributes: &[
-                        wgpu::VertexAttributeDescriptor {
-                            format: wgpu::VertexFormat::Float2,
-                            offset: unsafe { offset_of!(DrawVert, pos) } as u64,
-                            shader_location: 0,
-                        },
-                        wgpu::VertexAttributeDescriptor {
-                            format: wgpu::VertexFormat::Float2,
-                            offset: unsafe { offset_of!(DrawVert, uv) } as u64,
-                            shader_location: 1,
-                        },
-                        wgpu::VertexAttributeDescriptor {
-                            format: wgpu::VertexFormat::Uint,
-                            offset: unsafe { offset_of!(DrawVert, col) } as u64,
-                            shader_location: 2,
-                        },
-                    ],
-                }],
+            depth_stencil_state: match config.depth_layering {
+                Some(_) => {
+                    config
+                        .depth_stencil_state
+                        .map(|state| wgpu::DepthStencilStateDescriptor {
+                            depth_write_enabled: true,
+                            depth_compare: wgpu::CompareFunction::LessEqual,
+                            ..state
+                        })
+                }
+                None => config.depth_stencil_state,
             },
-            sample_count: 1,
-            sample_mask: !0,
-            alpha_to_coverage_enabled: false,
-        });
-        let index_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: None,
-            size: MAX_INDEX_BUFFER_SIZE,
-            usage: wgpu::BufferUsage::INDEX | wgpu::BufferUsage::COPY_DST,
-            mapped_at_creation: false,
-        });
-        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: None,
-            size: MAX_VERTEX_BUFFER_SIZE,
-            usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
-            mapped_at_creation: false,
-        });
-        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: None,
-            size: size_of!(f32) as u64 * 16,
-            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
-            mapped_at_creation: false,
-        });
-        let uniform_buffer_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &uniform_buffer_bind_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::Buffer(uniform_buffer.slice(..)),
-            }],
-            label: None,
-        });
-        let mut renderer = Self {
+            clip_mode: config.clip_mode,
+        };
+        let render_target = RenderTarget {
+            format: config.texture_format,
+            sample_count: config.sample_count,
+        };
+        let extra_vertex_stride = pipeline_factory
+            .extra_vertex_attributes
+            .as_ref()
+            .map_or(0, |extra| extra.stride);
+        let mut samplers = Samplers::default();
+        let default_sampler = samplers.cached(device, &linear_sampler_descriptor());
+        let mut pipelines = HashMap::new();
+        pipelines.insert(
+            render_target,
+            pipeline_factory.create_all(device, render_target),
+        );
+        Self {
             texture_bind_layout,
-            pipeline,
-            index_buffer,
-            vertex_buffer,
-            uniform_buffer,
-            uniform_buffer_bind_group,
+            pipeline_factory: Arc::new(pipeline_factory),
+            pipelines,
+            added_render_targets: HashSet::new(),
+            render_target,
+            uniform_bind_layout: uniform_buffer_bind_layout,
+            user_bind_group_layouts: config.user_bind_group_layouts,
+            shader_globals,
+            viewports: HashMap::new(),
+            gamma: config.gamma,
+            white_level: config.white_level,
+            output_color_space: config.output_color_space,
+            decode_vertex_srgb: config.decode_vertex_srgb,
+            depth_layering: config.depth_layering,
+            color_transform: IDENTITY_COLOR_TRANSFORM,
+            projection: None,
+            viewport: None,
+            max_index_bytes: MAX_INDEX_BUFFER_SIZE,
+            max_vertex_bytes: MAX_VERTEX_BUFFER_SIZE,
             indices_byte_buffer: Vec::with_capacity(MAX_INDEX_BUFFER_SIZE as usize),
             vertices_byte_buffer: Vec::with_capacity(MAX_VERTEX_BUFFER_SIZE as usize),
+            extra_vertex_stride,
+            extra_vertices_byte_buffer: Vec::new(),
+            vertex_data_callback: None,
             textures: imgui::Textures::<Texture>::new(),
+            retired_textures: Vec::new(),
+            removed_textures: RemovedTextures::default(),
+            font_textures: Vec::new(),
+            next_internal_texture_id: FIRST_INTERNAL_TEXTURE_ID,
+            shared_textures: Vec::new(),
+            draw_callbacks: HashMap::new(),
+            draw_hook: None,
+            texture_gc: TextureGc::default(),
+            texture_pool: TexturePool::default(),
+            texture_array,
+            multi_draw_indirect: config.multi_draw_indirect
+                && device
+                    .features()
+                    .contains(wgpu::Features::MULTI_DRAW_INDIRECT),
+            debug_overdraw: false,
+            scissor_disabled: false,
+            upload_encoder: None,
+            mapped_buffers: if config.mapped_buffers
+                && device
+                    .features()
+                    .contains(wgpu::Features::MAPPABLE_PRIMARY_BUFFERS)
+            {
+                Some(MappedBufferPool::default())
+            } else {
+                None
+            },
+            samplers,
+            default_sampler,
+            font_sampler: None,
+            checkerboard_shader: None,
+            #[cfg(feature = "hot-reload")]
+            texture_files: None,
+            #[cfg(feature = "svg")]
+            svg_icons: svg::SvgIcons::default(),
+            scratch: FrameScratch::default(),
+            buffer_stats: BufferStats::default(),
+            dropped_textures: DroppedTextures::default(),
+            transient_views: image_ext::TransientViews::default(),
+            multisampled: multisample::MultisampledTextures::default(),
+        }
+    }
+    fn allocate_texture(&self, device: &wgpu::Device, width: u32, height: u32) -> Texture {
+        let mut texture = Texture::with_sampler(
+            device,
+            &self.texture_bind_layout,
+            &upload_descriptor(width, height),
+            self.samplers.get(self.default_sampler),
+        );
+        texture.sampler = Some(self.default_sampler);
+        texture
+    }
+    // `allocate_texture` for a font atlas, sampled with the filter from `set_font_filter`.
+    fn allocate_font_texture(&self, device: &wgpu::Device, width: u32, height: u32) -> Texture {
+        let mut texture = self.allocate_texture(device, width, height);
+        if let Some(sampler_id) = self.font_sampler {
+            texture.set_sampler(
+                device,
+                &self.texture_bind_layout,
+                sampler_id,
+                self.samplers.get(sampler_id),
+            );
+        }
+        texture
+    }
+    // Copies the vertices and indices of every draw list into the byte buffers at the
+    // offsets from `plan_chunks`, and uploads them a chunk at a time. Returns the index of
+    // the last chunk.
+    #[cfg(not(feature = "rayon"))]
+    fn pack_draw_lists(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layers: &[Layer],
+        viewport_id: u32,
+    ) -> RendererResult<usize> {
+        profile_scope!("imgui-wgpu pack_draw_lists");
+        let draw_lists = || {
+            layers
+                .iter()
+                .flat_map(|layer| layer.frame.draw_lists.iter())
         };
-        renderer.reload_font_texture(imgui, device, queue);
-        renderer
+        let last_chunk = plan_chunks(
+            draw_lists().map(|draw_list| draw_list.buffer_bytes()),
+            self.max_index_bytes,
+            self.max_vertex_bytes,
+            &mut self.scratch.offsets,
+        );
+        for (index, draw_list) in draw_lists().enumerate() {
+            let (chunk, _, first_vertex) = self.scratch.offsets[index];
+            if index > 0 && chunk != self.scratch.offsets[index - 1].0 {
+                self.upload_buffers(device, queue, viewport_id, chunk - 1);
+            }
+            self.indices_byte_buffer
+                .extend_from_slice(bytemuck::cast_slice(draw_list.idx_buffer()));
+            self.vertices_byte_buffer
+                .extend_from_slice(vertex_bytes(draw_list.vtx_buffer()));
+            self.flip_texture_uvs(&draw_list, first_vertex);
+            if self.extra_vertex_stride > 0 {
+                self.append_extra_vertex_data(index, draw_list.vtx_buffer().len());
+            }
+        }
+        self.upload_buffers(device, queue, viewport_id, last_chunk);
+        Ok(last_chunk)
     }
-    fn upload_buffers(&mut self, queue: &wgpu::Queue) {
+    fn upload_buffers(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        viewport_id: u32,
+        chunk: usize,
+    ) {
+        profile_scope!("imgui-wgpu upload_buffers");
+        let stats = &mut self.buffer_stats;
+        stats.index_high_water = stats
+            .index_high_water
+            .max(self.indices_byte_buffer.len() as u64);
+        stats.vertex_high_water = stats
+            .vertex_high_water
+            .max(self.vertices_byte_buffer.len() as u64);
+        stats.chunks = chunk + 1;
+        // Growing is anything past the single chunk of the configured size.
+        let grows = chunk > 0
+            || self.indices_byte_buffer.len() as u64 > self.max_index_bytes
+            || self.vertices_byte_buffer.len() as u64 > self.max_vertex_bytes;
         let indices_byte_length = self.indices_byte_buffer.len();
         self.indices_byte_buffer
             .resize(indices_byte_length + (4 - indices_byte_length % 4), 0);
-        queue.write_buffer(&self.index_buffer, 0, self.indices_byte_buffer.as_slice());
-
         let vertices_byte_length = self.vertices_byte_buffer.len();
         self.vertices_byte_buffer
             .resize(vertices_byte_length + (4 - vertices_byte_length % 4), 0);
 
-        queue.write_buffer(&self.vertex_buffer, 0, self.vertices_byte_buffer.as_slice());
-        self.indices_byte_buffer.resize(0, 0);
-        self.vertices_byte_buffer.resize(0, 0);
-    }
-    fn append_indices(&mut self, indices: &[DrawIdx]) -> Option<u64> {
-        let offset = self.indices_byte_buffer.len();
-        let bytes: &[u8] = bytemuck::cast_slice(indices);
-        if offset + bytes.len() < MAX_INDEX_BUFFER_SIZE as usize {
-            self.indices_byte_buffer.extend_from_slice(bytes);
-            Some((offset / size_of!(DrawIdx)) as u64)
+        // A single draw list larger than the default size gets a chunk of its own.
+        let index_size = self
+            .max_index_bytes
+            .max(self.indices_byte_buffer.len() as u64);
+        let vertex_size = self
+            .max_vertex_bytes
+            .max(self.vertices_byte_buffer.len() as u64);
+        let extra_vertices_byte_length = self.extra_vertices_byte_buffer.len();
+        if self.extra_vertex_stride > 0 {
+            self.extra_vertices_byte_buffer.resize(
+                extra_vertices_byte_length + (4 - extra_vertices_byte_length % 4),
+                0,
+            );
+        }
+        let extra_size = self
+            .max_extra_vertex_bytes()
+            .max(self.extra_vertices_byte_buffer.len() as u64);
+        let frame_buffers = &mut self.viewports.get_mut(&viewport_id).unwrap().frame_buffers;
+        if let Some(mapped_buffers) = &mut self.mapped_buffers {
+            let (buffers, created) =
+                mapped_buffers.take(device, index_size, vertex_size, extra_size);
+            if created && grows {
+                self.buffer_stats.grow_events += 1;
+            }
+            mapped_buffers::write(
+                &buffers,
+                &self.indices_byte_buffer,
+                &self.vertices_byte_buffer,
+                &self.extra_vertices_byte_buffer,
+            );
+            frame_buffers.push(buffers);
         } else {
-            None
+            if chunk == frame_buffers.len() {
+                debug!("growing frame buffers to {} chunks", chunk + 1);
+                if grows {
+                    self.buffer_stats.grow_events += 1;
+                }
+                frame_buffers.push(FrameBuffers::new(
+                    device,
+                    index_size,
+                    vertex_size,
+                    extra_size,
+                ));
+            } else if frame_buffers[chunk].index_size < index_size
+                || frame_buffers[chunk].vertex_size < vertex_size
+                || frame_buffers[chunk].extra_size < extra_size
+            {
+                debug!(
+                    "growing frame buffer chunk {} to {} index and {} vertex bytes",
+                    chunk, index_size, vertex_size
+                );
+                self.buffer_stats.grow_events += 1;
+                frame_buffers[chunk] =
+                    FrameBuffers::new(device, index_size, vertex_size, extra_size);
+            }
+            let buffers = &frame_buffers[chunk];
+            let mut encoder = self.upload_encoder.as_mut();
+            write_buffer(
+                device,
+                queue,
+                encoder.as_deref_mut(),
+                &buffers.index_buffer,
+                &self.indices_byte_buffer,
+            );
+            write_buffer(
+                device,
+                queue,
+                encoder.as_deref_mut(),
+                &buffers.vertex_buffer,
+                &self.vertices_byte_buffer,
+            );
+            if let Some(extra_buffer) = &buffers.extra_buffer {
+                write_buffer(
+                    device,
+                    queue,
+                    encoder,
+                    extra_buffer,
+                    &self.extra_vertices_byte_buffer,
+                );
+            }
         }
+        self.indices_byte_buffer.clear();
+        self.vertices_byte_buffer.clear();
+        self.extra_vertices_byte_buffer.clear();
     }
-    fn append_vertices(&mut self, vertices: &[DrawVert]) -> Option<u64> {
-        let offset = self.vertices_byte_buffer.len();
-        let vertices =
-            unsafe { std::slice::from_raw_parts(vertices.as_ptr() as *mut Vertex, vertices.len()) };
-        let bytes: &[u8] = bytemuck::cast_slice(vertices);
-        if offset + bytes.len() < MAX_VERTEX_BUFFER_SIZE as usize {
-            self.vertices_byte_buffer.extend_from_slice(bytes);
-            Some((offset / size_of!(DrawVert)) as u64)
-        } else {
-            None
+    // Room for the extra vertex data of a full vertex buffer.
+    fn max_extra_vertex_bytes(&self) -> u64 {
+        self.max_vertex_bytes / size_of!(DrawVert) as u64 * self.extra_vertex_stride
+    }
+    fn append_extra_vertex_data(&mut self, draw_list: usize, vertex_count: usize) {
+        let offset = self.extra_vertices_byte_buffer.len();
+        self.extra_vertices_byte_buffer
+            .resize(offset + vertex_count * self.extra_vertex_stride as usize, 0);
+        if let Some(callback) = &mut self.vertex_data_callback {
+            callback(
+                draw_list,
+                vertex_count,
+                &mut self.extra_vertices_byte_buffer[offset..],
+            );
+        }
+    }
+    // Mirrors V in the just appended vertices of `draw_list`, starting at `first_vertex`, that
+    // are drawn with a texture set to `set_texture_flip_v`.
+    fn flip_texture_uvs(&mut self, draw_list: &FrameDrawList, first_vertex: u64) {
+        let indices = draw_list.idx_buffer();
+        let v_offset = unsafe { offset_of!(DrawVert, uv) } + size_of!(f32);
+        for draw_cmd in draw_list.commands() {
+            if let imgui::DrawCmd::Elements { count, cmd_params } = draw_cmd {
+                let texture = self.textures.get(cmd_params.texture_id);
+                if !matches!(texture, Some(texture) if texture.flip_v) {
+                    continue;
+                }
+                // Vertices used by several triangles must be flipped once.
+                let vertices = &mut self.scratch.flipped_vertices;
+                vertices.clear();
+                vertices.extend(
+                    indices[cmd_params.idx_offset..cmd_params.idx_offset + count]
+                        .iter()
+                        .map(|&index| {
+                            first_vertex as usize + cmd_params.vtx_offset + index as usize
+                        }),
+                );
+                vertices.sort_unstable();
+                vertices.dedup();
+                for &vertex in vertices.iter() {
+                    let start = vertex * size_of!(DrawVert) + v_offset;
+                    let v = &mut self.vertices_byte_buffer[start..start + size_of!(f32)];
+                    let flipped = 1.0 - f32::from_ne_bytes([v[0], v[1], v[2], v[3]]);
+                    v.copy_from_slice(&flipped.to_ne_bytes());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indirect_draws_cover_every_draw_list() {
+        let mut draws = IndirectDraws::default();
+        // Three draw lists of two commands each, with a texture change between lists.
+        for list_index in 0..3 {
+            draws.push(0..6, 0, list_index);
+            draws.push(6..12, 4, list_index);
+            let stride = size_of!(DrawIndexedIndirect) as u64;
+            assert_eq!(
+                draws.take_pending(),
+                Some((2 * list_index as u64 * stride, 2))
+            );
         }
+        assert_eq!(draws.take_pending(), None);
+        assert_eq!(draws.draws.len(), 6);
+        let instances: Vec<u32> = draws.draws.iter().map(|draw| draw.first_instance).collect();
+        assert_eq!(instances, [0, 0, 1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn chunks_hold_the_draw_lists_that_fit() {
+        let mut offsets = Vec::new();
+        // The index and vertex bytes of each draw list.
+        let buffer_bytes = [(4, 40), (8, 40), (40, 20), (200, 400), (2, 20)];
+        let last_chunk = plan_chunks(buffer_bytes.iter().copied(), 32, 128, &mut offsets);
+        assert_eq!(last_chunk, 3);
+        assert_eq!(
+            offsets,
+            [(0, 0, 0), (0, 2, 2), (1, 0, 0), (2, 0, 0), (3, 0, 0)]
+        );
+
+        offsets.clear();
+        assert_eq!(plan_chunks(std::iter::empty(), 32, 128, &mut offsets), 0);
+        assert!(offsets.is_empty());
+    }
+
+    #[test]
+    fn scissor_rects_cover_the_clip_rect_in_physical_pixels() {
+        let bounds = Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: 800.0,
+            height: 600.0,
+        };
+        let clip = [10.0, 20.0, 110.0, 70.0];
+        assert_eq!(
+            scissor_rect(clip, [0.0, 0.0], [1.0, 1.0], bounds),
+            Some((10, 20, 100, 50))
+        );
+        assert_eq!(
+            scissor_rect(clip, [5.0, 10.0], [2.0, 2.0], bounds),
+            Some((10, 20, 200, 100))
+        );
+        // Partly covered pixels are included.
+        assert_eq!(
+            scissor_rect([0.5, 0.5, 10.25, 10.75], [0.0, 0.0], [1.0, 1.0], bounds),
+            Some((0, 0, 11, 11))
+        );
+        assert_eq!(
+            scissor_rect(
+                [-50.0, -50.0, 1000.0, 1000.0],
+                [0.0, 0.0],
+                [1.0, 1.0],
+                bounds
+            ),
+            Some((0, 0, 800, 600))
+        );
+    }
+
+    #[test]
+    fn scissor_rects_stay_in_the_viewport() {
+        let bounds = Viewport {
+            x: 100.0,
+            y: 50.0,
+            width: 400.0,
+            height: 300.0,
+        };
+        assert_eq!(
+            scissor_rect([10.0, 10.0, 20.0, 20.0], [0.0, 0.0], [1.0, 1.0], bounds),
+            Some((110, 60, 10, 10))
+        );
+        assert_eq!(
+            scissor_rect([0.0, 0.0, 1000.0, 1000.0], [0.0, 0.0], [1.0, 1.0], bounds),
+            Some((100, 50, 400, 300))
+        );
+        assert_eq!(
+            scissor_rect([10.0, 10.0, 10.0, 50.0], [0.0, 0.0], [1.0, 1.0], bounds),
+            None
+        );
+        assert_eq!(
+            scissor_rect([500.0, 0.0, 600.0, 10.0], [0.0, 0.0], [1.0, 1.0], bounds),
+            None
+        );
+    }
+
+    #[test]
+    fn strided_data_needs_every_row() {
+        assert_eq!(validate_stride(4, 3, 16, 48), Ok(()));
+        // Padding after the last row is optional.
+        assert_eq!(validate_stride(4, 3, 20, 56), Ok(()));
+        assert_eq!(validate_stride(4, 3, 20, 60), Ok(()));
+        assert_eq!(validate_stride(4, 1, 256, 16), Ok(()));
+        assert_eq!(
+            validate_stride(4, 3, 20, 55),
+            Err(RendererError::TextureDataSize {
+                expected: 56,
+                actual: 55
+            })
+        );
+        // Rows can't overlap.
+        assert_eq!(
+            validate_stride(4, 3, 12, 48),
+            Err(RendererError::BadRowStride {
+                bytes_per_row: 12,
+                min: 16
+            })
+        );
+    }
+
+    #[test]
+    fn recycle_keeps_the_allocation() {
+        let mut values = vec![1u64, 2, 3];
+        values.reserve(61);
+        let capacity = values.capacity();
+        let recycled: Vec<&u64> = recycle(values);
+        assert!(recycled.is_empty());
+        assert_eq!(recycled.capacity(), capacity);
     }
 }
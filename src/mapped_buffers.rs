@@ -0,0 +1,107 @@
+use crate::ui_target::noop_waker;
+use crate::FrameBuffers;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+type MapFuture = Pin<Box<dyn Future<Output = Result<(), wgpu::BufferAsyncError>> + Send>>;
+
+// Buffers of a submitted frame, waiting for the GPU to finish with them.
+struct Remapping {
+    buffers: FrameBuffers,
+    maps: Vec<MapFuture>,
+    failed: bool,
+}
+
+/// Frame buffers created with `MAP_WRITE`, for `RendererConfig::mapped_buffers`. A frame writes
+/// into buffers that are already mapped and unmaps them; once that frame has been submitted
+/// they are mapped again, and reused when the mapping completes.
+#[derive(Default)]
+pub(crate) struct MappedBufferPool {
+    remapping: Vec<Remapping>,
+    mapped: Vec<FrameBuffers>,
+}
+impl MappedBufferPool {
+    /// Starts mapping the buffers of a frame that has been submitted.
+    pub(crate) fn recycle(&mut self, buffers: FrameBuffers) {
+        let maps = buffers
+            .buffers()
+            .map(|buffer| Box::pin(buffer.slice(..).map_async(wgpu::MapMode::Write)) as MapFuture)
+            .collect();
+        self.remapping.push(Remapping {
+            buffers,
+            maps,
+            failed: false,
+        });
+    }
+    /// Moves the buffers whose mapping completed to the mapped list.
+    pub(crate) fn poll(&mut self, device: &wgpu::Device) {
+        device.poll(wgpu::Maintain::Poll);
+        let waker = noop_waker();
+        let mut context = Context::from_waker(&waker);
+        for remapping in &mut self.remapping {
+            let mut pending = Vec::new();
+            for mut map in remapping.maps.drain(..) {
+                match map.as_mut().poll(&mut context) {
+                    Poll::Pending => pending.push(map),
+                    Poll::Ready(result) => remapping.failed |= result.is_err(),
+                }
+            }
+            remapping.maps = pending;
+        }
+        let mut index = 0;
+        while index < self.remapping.len() {
+            if self.remapping[index].maps.is_empty() {
+                let remapping = self.remapping.swap_remove(index);
+                // Buffers that failed to map are dropped; new ones replace them.
+                if !remapping.failed {
+                    self.mapped.push(remapping.buffers);
+                }
+            } else {
+                index += 1;
+            }
+        }
+    }
+    /// Returns mapped buffers of at least the given sizes, creating them if none are ready, and
+    /// whether they were created.
+    pub(crate) fn take(
+        &mut self,
+        device: &wgpu::Device,
+        index_size: u64,
+        vertex_size: u64,
+        extra_size: u64,
+    ) -> (FrameBuffers, bool) {
+        // Buffers too small for this frame were made before `set_buffer_limits` grew the
+        // limits, or for a draw list that needed a chunk of its own; drop them.
+        self.mapped.retain(|buffers| {
+            buffers.index_size >= index_size
+                && buffers.vertex_size >= vertex_size
+                && buffers.extra_size >= extra_size
+        });
+        if let Some(buffers) = self.mapped.pop() {
+            return (buffers, false);
+        }
+        let align =
+            |size: u64| size.div_ceil(wgpu::COPY_BUFFER_ALIGNMENT) * wgpu::COPY_BUFFER_ALIGNMENT;
+        let buffers = FrameBuffers::mapped(
+            device,
+            align(index_size),
+            align(vertex_size),
+            align(extra_size),
+        );
+        (buffers, true)
+    }
+}
+
+/// Copies the draw data into mapped `buffers` and unmaps them for drawing.
+pub(crate) fn write(buffers: &FrameBuffers, indices: &[u8], vertices: &[u8], extra: &[u8]) {
+    for (buffer, data) in buffers.buffers().zip([indices, vertices, extra].iter()) {
+        if !data.is_empty() {
+            buffer
+                .slice(..data.len() as u64)
+                .get_mapped_range_mut()
+                .copy_from_slice(data);
+        }
+        buffer.unmap();
+    }
+}
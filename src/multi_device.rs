@@ -0,0 +1,218 @@
+use crate::{Renderer, RendererConfig, RendererError, RendererResult};
+use std::sync::Arc;
+
+// Textures uploaded through the front-end get ids from here up, so they are the same on every
+// device and clear of the ids each device's renderer counts up from zero for its own textures.
+const FIRST_SHARED_ID: usize = usize::MAX / 4;
+
+/// Identifies a device added to a `MultiDeviceRenderer`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DeviceId(usize);
+
+struct DeviceRenderer {
+    device: Arc<wgpu::Device>,
+    queue: Arc<wgpu::Queue>,
+    renderer: Renderer,
+}
+
+// The pixels of a texture uploaded through the front-end, kept so devices added later get it
+// too.
+struct SharedTexture {
+    texture_id: imgui::TextureId,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+impl SharedTexture {
+    // Uploads the texture to one device under its shared id.
+    fn upload(&self, device: &mut DeviceRenderer, font: bool) {
+        let renderer = &mut device.renderer;
+        let texture = if font {
+            renderer.allocate_font_texture(&device.device, self.width, self.height)
+        } else {
+            renderer.allocate_texture(&device.device, self.width, self.height)
+        };
+        texture.write(&device.queue, &self.pixels);
+        renderer.replace_texture(self.texture_id, texture);
+        if font && !renderer.font_textures.contains(&self.texture_id) {
+            renderer.font_textures.push(self.texture_id);
+        }
+    }
+}
+
+/// Draws one imgui context on several `wgpu::Device`s, e.g. one per GPU driving a monitor.
+/// Each device gets a `Renderer` of its own with its own pipelines, buffers and textures.
+/// The font atlas and textures uploaded through `upload_texture` are uploaded to every device
+/// under the same `TextureId`, so the same draw data renders on all of them.
+pub struct MultiDeviceRenderer {
+    devices: Vec<Option<DeviceRenderer>>,
+    font_atlas: SharedTexture,
+    textures: Vec<SharedTexture>,
+    next_texture_id: usize,
+}
+impl MultiDeviceRenderer {
+    /// Builds imgui's font atlas for `imgui`. Add devices with `add_device` before rendering.
+    pub fn new(imgui: &mut imgui::Context) -> Self {
+        crate::register_backend(imgui);
+        let mut renderer = Self {
+            devices: Vec::new(),
+            font_atlas: SharedTexture {
+                texture_id: imgui::TextureId::from(FIRST_SHARED_ID),
+                width: 0,
+                height: 0,
+                pixels: Vec::new(),
+            },
+            textures: Vec::new(),
+            next_texture_id: FIRST_SHARED_ID + 1,
+        };
+        renderer.build_font_atlas(imgui);
+        renderer
+    }
+    fn build_font_atlas(&mut self, imgui: &mut imgui::Context) {
+        let mut fonts = imgui.fonts();
+        let texture_data = fonts.build_rgba32_texture();
+        self.font_atlas.width = texture_data.width;
+        self.font_atlas.height = texture_data.height;
+        self.font_atlas.pixels.clear();
+        self.font_atlas.pixels.extend_from_slice(texture_data.data);
+        fonts.tex_id = self.font_atlas.texture_id;
+        fonts.clear_tex_data();
+    }
+    /// Creates a `Renderer` for `device` and uploads the font atlas and every texture
+    /// uploaded so far to it. `config.font_atlas` is ignored; the atlas is built once, by
+    /// `new`.
+    pub fn add_device(
+        &mut self,
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+        config: RendererConfig,
+    ) -> DeviceId {
+        let mut device = DeviceRenderer {
+            renderer: Renderer::without_fonts(&device, config),
+            device,
+            queue,
+        };
+        self.font_atlas.upload(&mut device, true);
+        for texture in &self.textures {
+            texture.upload(&mut device, false);
+        }
+        let id = match self.devices.iter().position(Option::is_none) {
+            Some(index) => index,
+            None => {
+                self.devices.push(None);
+                self.devices.len() - 1
+            }
+        };
+        self.devices[id] = Some(device);
+        DeviceId(id)
+    }
+    /// Drops the renderer of a device, with all of its GPU resources.
+    pub fn remove_device(&mut self, device_id: DeviceId) -> RendererResult<()> {
+        self.devices
+            .get_mut(device_id.0)
+            .and_then(Option::take)
+            .map(|_| ())
+            .ok_or(RendererError::UnknownDevice(device_id))
+    }
+    /// The devices added and not removed yet.
+    pub fn device_ids(&self) -> impl Iterator<Item = DeviceId> + '_ {
+        self.devices
+            .iter()
+            .enumerate()
+            .filter(|(_, device)| device.is_some())
+            .map(|(index, _)| DeviceId(index))
+    }
+    /// The renderer of one device, e.g. to upload a texture only that device shows.
+    pub fn renderer(&self, device_id: DeviceId) -> RendererResult<&Renderer> {
+        self.device(device_id).map(|device| &device.renderer)
+    }
+    pub fn renderer_mut(&mut self, device_id: DeviceId) -> RendererResult<&mut Renderer> {
+        self.device_mut(device_id)
+            .map(|device| &mut device.renderer)
+    }
+    fn device(&self, device_id: DeviceId) -> RendererResult<&DeviceRenderer> {
+        self.devices
+            .get(device_id.0)
+            .and_then(Option::as_ref)
+            .ok_or(RendererError::UnknownDevice(device_id))
+    }
+    fn device_mut(&mut self, device_id: DeviceId) -> RendererResult<&mut DeviceRenderer> {
+        self.devices
+            .get_mut(device_id.0)
+            .and_then(Option::as_mut)
+            .ok_or(RendererError::UnknownDevice(device_id))
+    }
+    /// Builds the font atlas again after fonts changed and uploads it to every device. The
+    /// atlas keeps its `TextureId`.
+    pub fn reload_font_texture(&mut self, imgui: &mut imgui::Context) {
+        crate::register_backend(imgui);
+        self.build_font_atlas(imgui);
+        let font_atlas = &self.font_atlas;
+        for device in self.devices.iter_mut().flatten() {
+            font_atlas.upload(device, true);
+        }
+    }
+    /// Uploads tightly packed RGBA pixels, `width * height * 4` bytes of them, to every
+    /// device, including devices added later. The pixels are kept until the texture is
+    /// removed. Like textures mirrored by `Renderer::share_textures`, these are not evicted
+    /// by the texture GC.
+    pub fn upload_texture(
+        &mut self,
+        width: u32,
+        height: u32,
+        data: &[u8],
+    ) -> RendererResult<imgui::TextureId> {
+        crate::validate_texture_size(width, height)?;
+        let expected = width as usize * height as usize * 4;
+        if data.len() != expected {
+            return Err(RendererError::TextureDataSize {
+                expected,
+                actual: data.len(),
+            });
+        }
+        let texture = SharedTexture {
+            texture_id: imgui::TextureId::from(self.next_texture_id),
+            width,
+            height,
+            pixels: data.to_vec(),
+        };
+        self.next_texture_id += 1;
+        for device in self.devices.iter_mut().flatten() {
+            texture.upload(device, false);
+        }
+        let texture_id = texture.texture_id;
+        self.textures.push(texture);
+        Ok(texture_id)
+    }
+    /// Frees a texture from `upload_texture` on every device.
+    pub fn remove_texture(&mut self, texture_id: imgui::TextureId) -> RendererResult<()> {
+        let index = self
+            .textures
+            .iter()
+            .position(|texture| texture.texture_id == texture_id)
+            .ok_or(RendererError::BadTexture(texture_id))?;
+        self.textures.swap_remove(index);
+        // Every device gets to free its copy before an error is reported.
+        let mut result = Ok(());
+        for device in self.devices.iter_mut().flatten() {
+            let removed = device.renderer.remove_texture(texture_id);
+            if result.is_ok() {
+                result = removed;
+            }
+        }
+        result
+    }
+    /// Renders `draw_data` with the renderer of `device_id`, into a render pass on that
+    /// device.
+    pub fn render<'a>(
+        &'a mut self,
+        device_id: DeviceId,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        draw_data: &imgui::DrawData,
+    ) -> RendererResult<()> {
+        let device = self.device_mut(device_id)?;
+        device
+            .renderer
+            .render(&device.device, &device.queue, render_pass, draw_data)
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{Renderer, RendererError, RendererResult, Texture};
+use std::collections::HashMap;
+
+/// The multisampled views registered with `Renderer::insert_multisampled`, by the id of the
+/// texture they resolve into.
+#[derive(Default)]
+pub(crate) struct MultisampledTextures(HashMap<imgui::TextureId, wgpu::TextureView>);
+
+// Resolves `source` into `target` with an empty render pass; resolving needs no draws.
+fn resolve(
+    encoder: &mut wgpu::CommandEncoder,
+    source: &wgpu::TextureView,
+    target: &wgpu::TextureView,
+) {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+            attachment: source,
+            resolve_target: Some(target),
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: true,
+            },
+        }],
+        depth_stencil_attachment: None,
+    });
+}
+
+impl Renderer {
+    /// Registers a multisampled texture for display, e.g. a view of an MSAA GBuffer, and
+    /// returns the id of a single-sample texture it is resolved into by
+    /// `resolve_multisampled`. `source` must be a `width` x `height` view with
+    /// `OUTPUT_ATTACHMENT` usage and a filterable color `format`.
+    pub fn insert_multisampled(
+        &mut self,
+        device: &wgpu::Device,
+        source: wgpu::TextureView,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> RendererResult<imgui::TextureId> {
+        crate::validate_texture_size(width, height)?;
+        let mut descriptor = crate::upload_descriptor(width, height);
+        descriptor.format = format;
+        descriptor.usage = wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::OUTPUT_ATTACHMENT;
+        let texture = Texture::with_sampler(
+            device,
+            &self.texture_bind_layout,
+            &descriptor,
+            self.samplers.get(self.default_sampler),
+        );
+        let texture_id = self.insert_texture(texture);
+        self.multisampled.0.insert(texture_id, source);
+        Ok(texture_id)
+    }
+    /// Records the resolve of every texture from `insert_multisampled` into `encoder`; call
+    /// it each frame after the sources were drawn and before the UI is rendered.
+    pub fn resolve_multisampled(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let textures = &self.textures;
+        // Forget sources whose texture was removed.
+        self.multisampled
+            .0
+            .retain(|texture_id, _| textures.get(*texture_id).is_some());
+        for (&texture_id, source) in &self.multisampled.0 {
+            if let Some(target) = textures.get(texture_id).and_then(Texture::view) {
+                resolve(encoder, source, target);
+            }
+        }
+    }
+    /// Like `resolve_multisampled` for one texture, to resolve it on demand only.
+    pub fn resolve_multisampled_texture(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        texture_id: imgui::TextureId,
+    ) -> RendererResult<()> {
+        let source = self
+            .multisampled
+            .0
+            .get(&texture_id)
+            .ok_or(RendererError::BadTexture(texture_id))?;
+        let target = self
+            .checked_texture(texture_id)?
+            .view()
+            .ok_or(RendererError::BadTexture(texture_id))?;
+        resolve(encoder, source, target);
+        Ok(())
+    }
+}
@@ -0,0 +1,10 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+layout(location = 0) out vec4 outColor;
+
+// Drawn with additive blending, so every covering triangle adds to the pixel: light overdraw
+// shows as dark red and heavy overdraw builds up through orange and yellow to white.
+void main() {
+    outColor = vec4(0.1, 0.04, 0.02, 1.0);
+}
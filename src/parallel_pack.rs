@@ -0,0 +1,79 @@
+use crate::{Layer, Renderer, RendererResult};
+use rayon::prelude::*;
+
+// A draw list's index and vertex bytes and the parts of the byte buffers they are copied to.
+pub(crate) type DrawListCopy<'a> = (&'a [u8], &'a mut [u8], &'a [u8], &'a mut [u8]);
+
+impl Renderer {
+    // `pack_draw_lists` with the copying spread over rayon's thread pool. Each draw list
+    // copies into its own part of the byte buffers, at the offsets from `plan_chunks`; the
+    // chunks are still uploaded one at a time.
+    pub(crate) fn pack_draw_lists(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layers: &[Layer],
+        viewport_id: u32,
+    ) -> RendererResult<usize> {
+        let draw_lists = || {
+            layers
+                .iter()
+                .flat_map(|layer| layer.frame.draw_lists.iter())
+        };
+        let last_chunk = crate::plan_chunks(
+            draw_lists().map(|draw_list| draw_list.buffer_bytes()),
+            self.max_index_bytes,
+            self.max_vertex_bytes,
+            &mut self.scratch.offsets,
+        );
+        // Each chunk's draw lists are copied first and then fixed up.
+        let (mut copied, mut fixed) = (draw_lists(), draw_lists().enumerate());
+        let mut start = 0;
+        for chunk in 0..=last_chunk {
+            let count = self.scratch.offsets[start..]
+                .iter()
+                .take_while(|offsets| offsets.0 == chunk)
+                .count();
+            let mut copies: Vec<DrawListCopy> =
+                crate::recycle(std::mem::take(&mut self.scratch.copies));
+            let (mut index_bytes, mut vertex_bytes) = (0, 0);
+            for draw_list in copied.by_ref().take(count) {
+                let indices: &[u8] = bytemuck::cast_slice(draw_list.idx_buffer());
+                let vertices = crate::vertex_bytes(draw_list.vtx_buffer());
+                index_bytes += indices.len();
+                vertex_bytes += vertices.len();
+                copies.push((indices, &mut [], vertices, &mut []));
+            }
+            self.indices_byte_buffer.resize(index_bytes, 0);
+            self.vertices_byte_buffer.resize(vertex_bytes, 0);
+            let mut index_rest = &mut self.indices_byte_buffer[..];
+            let mut vertex_rest = &mut self.vertices_byte_buffer[..];
+            for (indices, index_target, vertices, vertex_target) in &mut copies {
+                let (target, rest) = std::mem::take(&mut index_rest).split_at_mut(indices.len());
+                *index_target = target;
+                index_rest = rest;
+                let (target, rest) = std::mem::take(&mut vertex_rest).split_at_mut(vertices.len());
+                *vertex_target = target;
+                vertex_rest = rest;
+            }
+            copies
+                .par_iter_mut()
+                .for_each(|(indices, index_target, vertices, vertex_target)| {
+                    index_target.copy_from_slice(indices);
+                    vertex_target.copy_from_slice(vertices);
+                });
+            self.scratch.copies = crate::recycle(copies);
+
+            // These read the textures and call back into the application, so they stay serial.
+            for (index, draw_list) in fixed.by_ref().take(count) {
+                self.flip_texture_uvs(&draw_list, self.scratch.offsets[index].2);
+                if self.extra_vertex_stride > 0 {
+                    self.append_extra_vertex_data(index, draw_list.vtx_buffer().len());
+                }
+            }
+            self.upload_buffers(device, queue, viewport_id, chunk);
+            start += count;
+        }
+        Ok(last_chunk)
+    }
+}
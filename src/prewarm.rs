@@ -0,0 +1,78 @@
+use crate::{PipelineFactory, Pipelines, RenderTarget, Renderer};
+use std::sync::Arc;
+
+/// Compiles the pipelines for render targets ahead of time, from `Renderer::prewarm_job`. It
+/// can be sent to a worker thread so the first frame drawn to a new target format or sample
+/// count doesn't stall on pipeline creation.
+pub struct PipelinePrewarm {
+    factory: Arc<PipelineFactory>,
+    targets: Vec<RenderTarget>,
+}
+impl PipelinePrewarm {
+    /// Takes the renderer's device, from any thread.
+    pub fn compile(self, device: &wgpu::Device) -> PrewarmedPipelines {
+        let factory = &self.factory;
+        PrewarmedPipelines(
+            self.targets
+                .iter()
+                .map(|&target| (target, factory.create_all(device, target)))
+                .collect(),
+        )
+    }
+}
+
+/// Pipelines compiled by `PipelinePrewarm`, to hand to `Renderer::insert_prewarmed`.
+pub struct PrewarmedPipelines(Vec<(RenderTarget, Pipelines)>);
+
+// Every combination of the formats and sample counts that has no pipelines yet.
+fn missing_targets(
+    renderer: &Renderer,
+    formats: &[wgpu::TextureFormat],
+    sample_counts: &[u32],
+) -> Vec<RenderTarget> {
+    formats
+        .iter()
+        .flat_map(|&format| {
+            sample_counts.iter().map(move |&sample_count| RenderTarget {
+                format,
+                sample_count,
+            })
+        })
+        .filter(|target| !renderer.pipelines.contains_key(target))
+        .collect()
+}
+
+impl Renderer {
+    /// Creates the pipelines for every combination of `formats` and `sample_counts` now, like
+    /// `add_render_target` does for one, e.g. behind a loading screen.
+    pub fn prewarm(
+        &mut self,
+        device: &wgpu::Device,
+        formats: &[wgpu::TextureFormat],
+        sample_counts: &[u32],
+    ) {
+        for target in missing_targets(self, formats, sample_counts) {
+            self.add_render_target(device, target);
+        }
+    }
+    /// Like `prewarm`, but returns the work to run on another thread. Pass the result of
+    /// `PipelinePrewarm::compile` to `insert_prewarmed`.
+    pub fn prewarm_job(
+        &self,
+        formats: &[wgpu::TextureFormat],
+        sample_counts: &[u32],
+    ) -> PipelinePrewarm {
+        PipelinePrewarm {
+            factory: self.pipeline_factory.clone(),
+            targets: missing_targets(self, formats, sample_counts),
+        }
+    }
+    /// Adds pipelines compiled by a `PipelinePrewarm` as render targets. Targets added in the
+    /// meantime keep their pipelines.
+    pub fn insert_prewarmed(&mut self, device: &wgpu::Device, prewarmed: PrewarmedPipelines) {
+        for (target, pipelines) in prewarmed.0 {
+            self.pipelines.entry(target).or_insert(pipelines);
+            self.add_render_target(device, target);
+        }
+    }
+}
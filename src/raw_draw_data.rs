@@ -0,0 +1,33 @@
+use crate::{Renderer, RendererResult};
+use imgui::internal::RawCast;
+
+impl Renderer {
+    /// `render` for draw data that comes straight from Dear ImGui over FFI, e.g. from
+    /// `igGetDrawData` of a docking branch build or custom bindings, rather than from an
+    /// `imgui::Context`. Nothing is drawn if `draw_data` is null. Such applications upload
+    /// the font atlas themselves, e.g. with `upload_texture`, and set its id on `ImFontAtlas`.
+    ///
+    /// # Safety
+    ///
+    /// `draw_data` must be null or point to a valid `ImDrawData` that is not modified or freed
+    /// until this returns. Its vertices must have imgui's default `ImDrawVert` layout and
+    /// its indices the size of `imgui::DrawIdx`, and its texture ids must be registered with
+    /// this renderer.
+    pub unsafe fn render_raw<'a>(
+        &'a mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        draw_data: *const imgui_sys::ImDrawData,
+    ) -> RendererResult<()> {
+        match draw_data.as_ref() {
+            Some(draw_data) => self.render(
+                device,
+                queue,
+                render_pass,
+                imgui::DrawData::from_raw(draw_data),
+            ),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{BlendMode, Renderer, RendererError, RendererResult};
+
+/// The renderer's public surface, so engines can swap in a mock or `NullRenderer`.
+pub trait ImguiRenderer {
+    fn upload_texture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        data: &[u8],
+    ) -> RendererResult<imgui::TextureId>;
+    fn remove_texture(&mut self, texture_id: imgui::TextureId) -> RendererResult<()>;
+    fn set_texture_blend_mode(
+        &mut self,
+        texture_id: imgui::TextureId,
+        blend_mode: BlendMode,
+    ) -> RendererResult<()>;
+    fn reload_font_texture(
+        &mut self,
+        imgui: &mut imgui::Context,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> RendererResult<()>;
+    fn render<'a>(
+        &'a mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        draw_data: &imgui::DrawData,
+    ) -> RendererResult<()>;
+}
+
+impl ImguiRenderer for Renderer {
+    fn upload_texture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        data: &[u8],
+    ) -> RendererResult<imgui::TextureId> {
+        Renderer::upload_texture(self, device, queue, width, height, data)
+    }
+    fn remove_texture(&mut self, texture_id: imgui::TextureId) -> RendererResult<()> {
+        Renderer::remove_texture(self, texture_id)
+    }
+    fn set_texture_blend_mode(
+        &mut self,
+        texture_id: imgui::TextureId,
+        blend_mode: BlendMode,
+    ) -> RendererResult<()> {
+        Renderer::set_texture_blend_mode(self, texture_id, blend_mode)
+    }
+    fn reload_font_texture(
+        &mut self,
+        imgui: &mut imgui::Context,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> RendererResult<()> {
+        Renderer::reload_font_texture(self, imgui, device, queue)
+    }
+    fn render<'a>(
+        &'a mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        draw_data: &imgui::DrawData,
+    ) -> RendererResult<()> {
+        Renderer::render(self, device, queue, render_pass, draw_data)
+    }
+}
+
+/// A renderer that keeps texture ids consistent but never touches the GPU, for headless
+/// builds and tests.
+pub struct NullRenderer {
+    textures: imgui::Textures<()>,
+}
+impl NullRenderer {
+    pub fn new(imgui: &mut imgui::Context) -> Self {
+        let mut renderer = Self {
+            textures: imgui::Textures::new(),
+        };
+        imgui.fonts().tex_id = renderer.textures.insert(());
+        renderer
+    }
+}
+impl ImguiRenderer for NullRenderer {
+    fn upload_texture(
+        &mut self,
+        _device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        _width: u32,
+        _height: u32,
+        _data: &[u8],
+    ) -> RendererResult<imgui::TextureId> {
+        Ok(self.textures.insert(()))
+    }
+    fn remove_texture(&mut self, texture_id: imgui::TextureId) -> RendererResult<()> {
+        self.textures
+            .remove(texture_id)
+            .ok_or(RendererError::BadTexture(texture_id))
+    }
+    fn set_texture_blend_mode(
+        &mut self,
+        texture_id: imgui::TextureId,
+        _blend_mode: BlendMode,
+    ) -> RendererResult<()> {
+        self.textures
+            .get(texture_id)
+            .map(|_| ())
+            .ok_or(RendererError::BadTexture(texture_id))
+    }
+    fn reload_font_texture(
+        &mut self,
+        imgui: &mut imgui::Context,
+        _device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+    ) -> RendererResult<()> {
+        let mut fonts = imgui.fonts();
+        fonts.build_rgba32_texture();
+        fonts.clear_tex_data();
+        Ok(())
+    }
+    fn render<'a>(
+        &'a mut self,
+        _device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        _render_pass: &mut wgpu::RenderPass<'a>,
+        _draw_data: &imgui::DrawData,
+    ) -> RendererResult<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{RendererError, RendererResult};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Numbers the `Samplers` of each renderer, so ids from another renderer are told apart.
+static NEXT_OWNER: AtomicUsize = AtomicUsize::new(0);
+
+/// A sampler owned by a `Renderer`, from `Renderer::register_sampler` or
+/// `Renderer::cached_sampler`. Only valid with the renderer that created it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SamplerId {
+    owner: usize,
+    index: usize,
+}
+
+// The hashable parts of a `wgpu::SamplerDescriptor`; the label is ignored.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct SamplerKey {
+    address_modes: [wgpu::AddressMode; 3],
+    filters: [wgpu::FilterMode; 3],
+    lod_clamp: [u32; 2],
+    compare: Option<wgpu::CompareFunction>,
+    anisotropy_clamp: Option<std::num::NonZeroU8>,
+}
+impl From<&wgpu::SamplerDescriptor<'_>> for SamplerKey {
+    fn from(descriptor: &wgpu::SamplerDescriptor) -> Self {
+        Self {
+            address_modes: [
+                descriptor.address_mode_u,
+                descriptor.address_mode_v,
+                descriptor.address_mode_w,
+            ],
+            filters: [
+                descriptor.mag_filter,
+                descriptor.min_filter,
+                descriptor.mipmap_filter,
+            ],
+            lod_clamp: [
+                descriptor.lod_min_clamp.to_bits(),
+                descriptor.lod_max_clamp.to_bits(),
+            ],
+            compare: descriptor.compare,
+            anisotropy_clamp: descriptor.anisotropy_clamp,
+        }
+    }
+}
+
+impl SamplerKey {
+    fn descriptor(&self) -> wgpu::SamplerDescriptor<'static> {
+        wgpu::SamplerDescriptor {
+            label: None,
+            address_mode_u: self.address_modes[0],
+            address_mode_v: self.address_modes[1],
+            address_mode_w: self.address_modes[2],
+            mag_filter: self.filters[0],
+            min_filter: self.filters[1],
+            mipmap_filter: self.filters[2],
+            lod_min_clamp: f32::from_bits(self.lod_clamp[0]),
+            lod_max_clamp: f32::from_bits(self.lod_clamp[1]),
+            compare: self.compare,
+            anisotropy_clamp: self.anisotropy_clamp,
+        }
+    }
+}
+
+/// Every sampler the renderer hands out, so textures with the same sampling share one
+/// `wgpu::Sampler` instead of each creating their own.
+pub(crate) struct Samplers {
+    owner: usize,
+    samplers: Vec<wgpu::Sampler>,
+    // The settings of each sampler, unless it was registered ready-made.
+    keys: Vec<Option<SamplerKey>>,
+    cache: HashMap<SamplerKey, SamplerId>,
+}
+impl Default for Samplers {
+    fn default() -> Self {
+        Self {
+            owner: NEXT_OWNER.fetch_add(1, Ordering::Relaxed),
+            samplers: Vec::new(),
+            keys: Vec::new(),
+            cache: HashMap::new(),
+        }
+    }
+}
+impl Samplers {
+    pub(crate) fn register(&mut self, sampler: wgpu::Sampler) -> SamplerId {
+        self.samplers.push(sampler);
+        self.keys.push(None);
+        SamplerId {
+            owner: self.owner,
+            index: self.samplers.len() - 1,
+        }
+    }
+    pub(crate) fn cached(
+        &mut self,
+        device: &wgpu::Device,
+        descriptor: &wgpu::SamplerDescriptor,
+    ) -> SamplerId {
+        let key = SamplerKey::from(descriptor);
+        if let Some(&sampler_id) = self.cache.get(&key) {
+            return sampler_id;
+        }
+        let sampler_id = self.register(device.create_sampler(descriptor));
+        self.keys[sampler_id.index] = Some(key.clone());
+        self.cache.insert(key, sampler_id);
+        sampler_id
+    }
+    /// The settings `sampler_id` was created with, unless it was registered ready-made.
+    pub(crate) fn descriptor(
+        &self,
+        sampler_id: SamplerId,
+    ) -> Option<wgpu::SamplerDescriptor<'static>> {
+        self.keys[sampler_id.index]
+            .as_ref()
+            .map(SamplerKey::descriptor)
+    }
+    /// The sampler of an id this renderer handed out itself.
+    pub(crate) fn get(&self, sampler_id: SamplerId) -> &wgpu::Sampler {
+        debug_assert_eq!(sampler_id.owner, self.owner);
+        &self.samplers[sampler_id.index]
+    }
+    /// The sampler of an id from the application, which may belong to another renderer.
+    pub(crate) fn checked(&self, sampler_id: SamplerId) -> RendererResult<&wgpu::Sampler> {
+        if sampler_id.owner == self.owner {
+            Ok(&self.samplers[sampler_id.index])
+        } else {
+            Err(RendererError::UnknownSampler(sampler_id))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_of_another_renderer_are_rejected() {
+        let (samplers, other) = (Samplers::default(), Samplers::default());
+        let sampler_id = SamplerId {
+            owner: other.owner,
+            index: 0,
+        };
+        assert_eq!(
+            samplers.checked(sampler_id).err(),
+            Some(RendererError::UnknownSampler(sampler_id))
+        );
+    }
+}
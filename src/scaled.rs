@@ -0,0 +1,180 @@
+use crate::shaders::{self, Shader};
+use crate::{Renderer, RendererResult, UiRenderTarget};
+
+/// Renders the UI into an internal texture at `scale` times the output resolution and then
+/// draws it onto the output with a scaled blit. Above 1.0 this supersamples the UI for
+/// smoother text and edges; below 1.0 it saves fill rate on weak GPUs.
+pub struct ScaledComposite {
+    format: wgpu::TextureFormat,
+    scale: f32,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    pipeline: wgpu::RenderPipeline,
+    // The internal target and its bind group, recreated when the size changes.
+    target: Option<(UiRenderTarget, wgpu::BindGroup)>,
+}
+impl ScaledComposite {
+    /// `format` is the format of the output view passed to `render`; the internal target uses
+    /// it too, so gamma is handled as if rendering directly. `filter` is used for the blit.
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        scale: f32,
+        filter: wgpu::FilterMode,
+    ) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::SampledTexture {
+                        multisampled: false,
+                        component_type: wgpu::TextureComponentType::Float,
+                        dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler { comparison: false },
+                    count: None,
+                },
+            ],
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let vs_module = shaders::create_module(device, Shader::FullscreenVertex);
+        let fs_module = shaders::create_module(device, Shader::BlitFragment);
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex_stage: wgpu::ProgrammableStageDescriptor {
+                module: &vs_module,
+                entry_point: "main",
+            },
+            fragment_stage: Some(wgpu::ProgrammableStageDescriptor {
+                module: &fs_module,
+                entry_point: "main",
+            }),
+            rasterization_state: None,
+            primitive_topology: wgpu::PrimitiveTopology::TriangleList,
+            color_states: &[wgpu::ColorStateDescriptor {
+                format,
+                color_blend: wgpu::BlendDescriptor {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha_blend: wgpu::BlendDescriptor {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                write_mask: wgpu::ColorWrite::ALL,
+            }],
+            depth_stencil_state: None,
+            vertex_state: wgpu::VertexStateDescriptor {
+                index_format: wgpu::IndexFormat::Uint16,
+                vertex_buffers: &[],
+            },
+            sample_count: 1,
+            sample_mask: !0,
+            alpha_to_coverage_enabled: false,
+        });
+        Self {
+            format,
+            scale,
+            bind_group_layout,
+            sampler,
+            pipeline,
+            target: None,
+        }
+    }
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+    /// The internal target is reallocated on the next `render`.
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale;
+    }
+    /// Renders `draw_data` at the scaled resolution and submits it, then records the blit onto
+    /// `output_view` into `encoder`, blending over its current contents.
+    pub fn render(
+        &mut self,
+        renderer: &mut Renderer,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        output_view: &wgpu::TextureView,
+        draw_data: &imgui::DrawData,
+    ) -> RendererResult<()> {
+        let width = (draw_data.display_size[0] * draw_data.framebuffer_scale[0] * self.scale)
+            .round() as u32;
+        let height = (draw_data.display_size[1] * draw_data.framebuffer_scale[1] * self.scale)
+            .round() as u32;
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+        let stale = match &self.target {
+            Some((target, _)) => target.size() != (width, height),
+            None => true,
+        };
+        if stale {
+            if let Some((target, _)) = &self.target {
+                renderer.remove_viewport(target.viewport_id());
+            }
+            let target = UiRenderTarget::new(device, renderer, width, height, self.format);
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(target.view()),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            });
+            self.target = Some((target, bind_group));
+        }
+        let (target, bind_group) = self.target.as_ref().unwrap();
+
+        // Map the whole display onto the internal target, whatever its scale.
+        crate::ui_target::fit_viewport(renderer, width, height, |renderer| {
+            target.render(renderer, device, queue, draw_data, wgpu::Color::TRANSPARENT)
+        })?;
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: output_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+// Converts coverage bitmaps such as imgui's alpha8 font atlas into signed distance fields,
+// using the separable exact Euclidean distance transform of Felzenszwalb and Huttenlocher.
+
+const FAR: f32 = 1e20;
+
+/// Returns white RGBA texels whose alpha is the distance to the nearest edge of `alpha`,
+/// mapped so 0.5 lies on the outline and `spread` pixels inside or outside saturate.
+pub(crate) fn alpha_to_sdf(alpha: &[u8], width: u32, height: u32, spread: f32) -> Vec<u8> {
+    let inside: Vec<bool> = alpha.iter().map(|&a| a >= 128).collect();
+    let to_inside = distance_transform(&inside, width as usize, height as usize, true);
+    let to_outside = distance_transform(&inside, width as usize, height as usize, false);
+    to_inside
+        .iter()
+        .zip(&to_outside)
+        .flat_map(|(&to_inside, &to_outside)| {
+            // Pixel centers lie half a pixel from the edge between them.
+            let signed = if to_inside == 0.0 {
+                to_outside.sqrt() - 0.5
+            } else {
+                0.5 - to_inside.sqrt()
+            };
+            let value = (0.5 + signed / (2.0 * spread)).clamp(0.0, 1.0);
+            vec![255, 255, 255, (value * 255.0).round() as u8]
+        })
+        .collect()
+}
+
+// Squared distance from every pixel to the nearest pixel where `mask == seed`.
+fn distance_transform(mask: &[bool], width: usize, height: usize, seed: bool) -> Vec<f32> {
+    let mut grid: Vec<f32> = mask
+        .iter()
+        .map(|&m| if m == seed { 0.0 } else { FAR })
+        .collect();
+    let n = width.max(height);
+    let mut f = vec![0.0; n];
+    let mut d = vec![0.0; n];
+    let mut v = vec![0; n];
+    let mut z = vec![0.0; n + 1];
+    for x in 0..width {
+        for y in 0..height {
+            f[y] = grid[y * width + x];
+        }
+        transform_1d(&f[..height], &mut d, &mut v, &mut z);
+        for y in 0..height {
+            grid[y * width + x] = d[y];
+        }
+    }
+    for y in 0..height {
+        f[..width].copy_from_slice(&grid[y * width..(y + 1) * width]);
+        transform_1d(&f[..width], &mut d, &mut v, &mut z);
+        grid[y * width..(y + 1) * width].copy_from_slice(&d[..width]);
+    }
+    grid
+}
+
+// The lower envelope of the parabolas rooted at each sample of `f`.
+fn transform_1d(f: &[f32], d: &mut [f32], v: &mut [usize], z: &mut [f32]) {
+    if f.is_empty() {
+        return;
+    }
+    let intersection = |q: usize, p: usize| {
+        ((f[q] + (q * q) as f32) - (f[p] + (p * p) as f32)) / (2 * q - 2 * p) as f32
+    };
+    let mut k = 0;
+    v[0] = 0;
+    z[0] = -FAR;
+    z[1] = FAR;
+    for q in 1..f.len() {
+        let mut s = intersection(q, v[k]);
+        while s <= z[k] {
+            k -= 1;
+            s = intersection(q, v[k]);
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = FAR;
+    }
+    k = 0;
+    for (q, distance) in d.iter_mut().enumerate().take(f.len()) {
+        while z[k + 1] < q as f32 {
+            k += 1;
+        }
+        let offset = q as f32 - v[k] as f32;
+        *distance = offset * offset + f[v[k]];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The alpha of a pixel `signed` pixels inside the outline, with a spread of 2.
+    fn expected(signed: f32) -> u8 {
+        ((0.5 + signed / 4.0).clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+
+    #[test]
+    fn alpha_falls_off_across_the_edge() {
+        let alpha = [255, 255, 255, 255, 0, 0, 0, 0];
+        let sdf = alpha_to_sdf(&alpha, 8, 1, 2.0);
+        assert_eq!(sdf.len(), 8 * 4);
+        assert!(sdf.chunks(4).all(|texel| texel[..3] == [255, 255, 255]));
+        let values: Vec<u8> = sdf.chunks(4).map(|texel| texel[3]).collect();
+        assert_eq!(
+            values,
+            [
+                expected(3.5),
+                expected(2.5),
+                expected(1.5),
+                expected(0.5),
+                expected(-0.5),
+                expected(-1.5),
+                expected(-2.5),
+                expected(-3.5),
+            ]
+        );
+        assert_eq!(values[..4], [255, 255, 223, 159]);
+        assert_eq!(values[4..], [96, 32, 0, 0]);
+    }
+
+    #[test]
+    fn distances_are_euclidean() {
+        let mut alpha = [0; 25];
+        alpha[2 * 5 + 2] = 255;
+        let sdf = alpha_to_sdf(&alpha, 5, 5, 2.0);
+        let value = |x: usize, y: usize| sdf[(y * 5 + x) * 4 + 3];
+        assert_eq!(value(2, 2), expected(0.5));
+        assert_eq!(value(1, 2), expected(-0.5));
+        assert_eq!(value(1, 1), expected(0.5 - 2f32.sqrt()));
+        assert_eq!(value(0, 1), expected(0.5 - 5f32.sqrt()));
+    }
+
+    #[test]
+    fn uniform_bitmaps_saturate() {
+        let outside = alpha_to_sdf(&[0; 12], 4, 3, 2.0);
+        assert!(outside.chunks(4).all(|texel| texel[3] == 0));
+        let inside = alpha_to_sdf(&[200; 12], 4, 3, 2.0);
+        assert!(inside.chunks(4).all(|texel| texel[3] == 255));
+    }
+}
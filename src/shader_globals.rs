@@ -0,0 +1,124 @@
+use crate::{Layer, Renderer};
+
+// The uniform block custom shaders see, laid out as std140.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Globals {
+    time: f32,
+    _padding: f32,
+    resolution: [f32; 2],
+    framebuffer_scale: [f32; 2],
+    mouse: [f32; 2],
+}
+
+unsafe impl bytemuck::Zeroable for Globals {}
+
+unsafe impl bytemuck::Pod for Globals {}
+
+/// The layout of `RendererConfig::shader_globals` and the inputs gathered for it between
+/// frames.
+pub(crate) struct ShaderGlobals {
+    pub(crate) layout: wgpu::BindGroupLayout,
+    time: f32,
+    mouse: [f32; 2],
+}
+impl ShaderGlobals {
+    pub(crate) fn new(device: &wgpu::Device) -> Self {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
+                ty: wgpu::BindingType::UniformBuffer {
+                    dynamic: false,
+                    min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<Globals>() as u64),
+                },
+                count: None,
+            }],
+        });
+        Self {
+            layout,
+            time: 0.0,
+            mouse: [0.0; 2],
+        }
+    }
+}
+
+/// A viewport's copy of the globals; viewports are drawn at different resolutions.
+pub(crate) struct GlobalsBuffer {
+    buffer: wgpu::Buffer,
+    pub(crate) bind_group: wgpu::BindGroup,
+}
+
+impl Renderer {
+    /// Advances the time and takes the mouse position that custom shaders see through
+    /// `RendererConfig::shader_globals`. Call once per frame, e.g. with `ui.io()` before
+    /// `ui.render()`.
+    pub fn update_shader_globals(&mut self, io: &imgui::Io) {
+        if let Some(globals) = &mut self.shader_globals {
+            globals.time += io.delta_time;
+            globals.mouse = io.mouse_pos;
+        }
+    }
+    /// The group index of the `RendererConfig::shader_globals` block, after any
+    /// `user_bind_group_layouts`.
+    pub fn shader_globals_bind_group_index(&self) -> Option<u32> {
+        self.shader_globals
+            .as_ref()
+            .map(|_| self.user_bind_group_index() + self.user_bind_group_layouts.len() as u32)
+    }
+    // Fills in the viewport's globals for the frame about to be drawn, creating its buffer the
+    // first time.
+    pub(crate) fn write_shader_globals(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layers: &[Layer],
+        viewport_id: u32,
+    ) {
+        let shader_globals = match &self.shader_globals {
+            Some(shader_globals) => shader_globals,
+            None => return,
+        };
+        let frame = &layers[0].frame;
+        let resolution = match self.viewport {
+            Some(viewport) => [viewport.width, viewport.height],
+            None => [
+                (frame.display_size[0] * frame.framebuffer_scale[0]).round(),
+                (frame.display_size[1] * frame.framebuffer_scale[1]).round(),
+            ],
+        };
+        let globals = Globals {
+            time: shader_globals.time,
+            _padding: 0.0,
+            resolution,
+            framebuffer_scale: frame.framebuffer_scale,
+            mouse: shader_globals.mouse,
+        };
+        let resources = self.viewports.get_mut(&viewport_id).unwrap();
+        let globals_buffer = resources.globals.get_or_insert_with(|| {
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: std::mem::size_of::<Globals>() as u64,
+                usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &shader_globals.layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(buffer.slice(..)),
+                }],
+            });
+            GlobalsBuffer { buffer, bind_group }
+        });
+        crate::write_buffer(
+            device,
+            queue,
+            self.upload_encoder.as_mut(),
+            &globals_buffer.buffer,
+            bytemuck::bytes_of(&globals),
+        );
+    }
+}
@@ -0,0 +1,243 @@
+// The built-in shaders. With the `runtime-shaders` feature they are compiled from the GLSL
+// sources next to this file when the renderer is created, instead of using the SPIR-V
+// checked into the repository. Run `compile_shaders.sh` to regenerate that SPIR-V after
+// changing a source.
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Shader {
+    Vertex,
+    PushVertex,
+    Fragment,
+    PushFragment,
+    BindlessVertex,
+    BindlessFragment,
+    BgraVertex,
+    PushBgraVertex,
+    BindlessBgraVertex,
+    SdfFragment,
+    PushSdfFragment,
+    BindlessSdfFragment,
+    CheckerFragment,
+    PushCheckerFragment,
+    BindlessCheckerFragment,
+    FullscreenVertex,
+    YuvFragment,
+    BlitFragment,
+    OverdrawFragment,
+    ClipOutlineFragment,
+    BlurFragment,
+    DepthFragment,
+    UintFragment,
+    SintFragment,
+    SwizzleFragment,
+}
+
+#[cfg(not(feature = "runtime-shaders"))]
+pub(crate) fn create_module(device: &wgpu::Device, shader: Shader) -> wgpu::ShaderModule {
+    device.create_shader_module(match shader {
+        Shader::Vertex => wgpu::include_spirv!("imgui.vert.spv"),
+        Shader::PushVertex => wgpu::include_spirv!("imgui.push.vert.spv"),
+        Shader::Fragment => wgpu::include_spirv!("imgui.frag.spv"),
+        Shader::PushFragment => wgpu::include_spirv!("imgui.push.frag.spv"),
+        Shader::BindlessVertex => wgpu::include_spirv!("imgui.bindless.vert.spv"),
+        Shader::BindlessFragment => wgpu::include_spirv!("imgui.bindless.frag.spv"),
+        Shader::BgraVertex => wgpu::include_spirv!("imgui.bgra.vert.spv"),
+        Shader::PushBgraVertex => wgpu::include_spirv!("imgui.push.bgra.vert.spv"),
+        Shader::BindlessBgraVertex => wgpu::include_spirv!("imgui.bindless.bgra.vert.spv"),
+        Shader::SdfFragment => wgpu::include_spirv!("imgui.sdf.frag.spv"),
+        Shader::PushSdfFragment => wgpu::include_spirv!("imgui.push.sdf.frag.spv"),
+        Shader::BindlessSdfFragment => wgpu::include_spirv!("imgui.bindless.sdf.frag.spv"),
+        Shader::CheckerFragment => wgpu::include_spirv!("imgui.checker.frag.spv"),
+        Shader::PushCheckerFragment => wgpu::include_spirv!("imgui.push.checker.frag.spv"),
+        Shader::BindlessCheckerFragment => {
+            wgpu::include_spirv!("imgui.bindless.checker.frag.spv")
+        }
+        Shader::FullscreenVertex => wgpu::include_spirv!("fullscreen.vert.spv"),
+        Shader::YuvFragment => wgpu::include_spirv!("yuv.frag.spv"),
+        Shader::BlitFragment => wgpu::include_spirv!("blit.frag.spv"),
+        Shader::OverdrawFragment => wgpu::include_spirv!("overdraw.frag.spv"),
+        Shader::ClipOutlineFragment => wgpu::include_spirv!("clip_outline.frag.spv"),
+        Shader::BlurFragment => wgpu::include_spirv!("blur.frag.spv"),
+        Shader::DepthFragment => wgpu::include_spirv!("depth.frag.spv"),
+        Shader::UintFragment => wgpu::include_spirv!("integer.uint.frag.spv"),
+        Shader::SintFragment => wgpu::include_spirv!("integer.sint.frag.spv"),
+        Shader::SwizzleFragment => wgpu::include_spirv!("swizzle.frag.spv"),
+    })
+}
+
+#[cfg(feature = "runtime-shaders")]
+pub(crate) fn create_module(device: &wgpu::Device, shader: Shader) -> wgpu::ShaderModule {
+    let (name, source, kind, defines): (_, _, _, &[&str]) = match shader {
+        Shader::Vertex => (
+            "imgui.vert",
+            include_str!("imgui.vert"),
+            shaderc::ShaderKind::Vertex,
+            &[],
+        ),
+        Shader::PushVertex => (
+            "imgui.vert",
+            include_str!("imgui.vert"),
+            shaderc::ShaderKind::Vertex,
+            &["PUSH_CONSTANTS"],
+        ),
+        Shader::Fragment => (
+            "imgui.frag",
+            include_str!("imgui.frag"),
+            shaderc::ShaderKind::Fragment,
+            &[],
+        ),
+        Shader::PushFragment => (
+            "imgui.frag",
+            include_str!("imgui.frag"),
+            shaderc::ShaderKind::Fragment,
+            &["PUSH_CONSTANTS"],
+        ),
+        Shader::BindlessVertex => (
+            "imgui.vert",
+            include_str!("imgui.vert"),
+            shaderc::ShaderKind::Vertex,
+            &["BINDLESS"],
+        ),
+        Shader::BindlessFragment => (
+            "imgui.frag",
+            include_str!("imgui.frag"),
+            shaderc::ShaderKind::Fragment,
+            &["BINDLESS"],
+        ),
+        Shader::BgraVertex => (
+            "imgui.vert",
+            include_str!("imgui.vert"),
+            shaderc::ShaderKind::Vertex,
+            &["BGRA"],
+        ),
+        Shader::PushBgraVertex => (
+            "imgui.vert",
+            include_str!("imgui.vert"),
+            shaderc::ShaderKind::Vertex,
+            &["PUSH_CONSTANTS", "BGRA"],
+        ),
+        Shader::BindlessBgraVertex => (
+            "imgui.vert",
+            include_str!("imgui.vert"),
+            shaderc::ShaderKind::Vertex,
+            &["BINDLESS", "BGRA"],
+        ),
+        Shader::SdfFragment => (
+            "imgui.frag",
+            include_str!("imgui.frag"),
+            shaderc::ShaderKind::Fragment,
+            &["SDF"],
+        ),
+        Shader::PushSdfFragment => (
+            "imgui.frag",
+            include_str!("imgui.frag"),
+            shaderc::ShaderKind::Fragment,
+            &["PUSH_CONSTANTS", "SDF"],
+        ),
+        Shader::BindlessSdfFragment => (
+            "imgui.frag",
+            include_str!("imgui.frag"),
+            shaderc::ShaderKind::Fragment,
+            &["BINDLESS", "SDF"],
+        ),
+        Shader::CheckerFragment => (
+            "imgui.frag",
+            include_str!("imgui.frag"),
+            shaderc::ShaderKind::Fragment,
+            &["CHECKERBOARD"],
+        ),
+        Shader::PushCheckerFragment => (
+            "imgui.frag",
+            include_str!("imgui.frag"),
+            shaderc::ShaderKind::Fragment,
+            &["PUSH_CONSTANTS", "CHECKERBOARD"],
+        ),
+        Shader::BindlessCheckerFragment => (
+            "imgui.frag",
+            include_str!("imgui.frag"),
+            shaderc::ShaderKind::Fragment,
+            &["BINDLESS", "CHECKERBOARD"],
+        ),
+        Shader::FullscreenVertex => (
+            "fullscreen.vert",
+            include_str!("fullscreen.vert"),
+            shaderc::ShaderKind::Vertex,
+            &[],
+        ),
+        Shader::YuvFragment => (
+            "yuv.frag",
+            include_str!("yuv.frag"),
+            shaderc::ShaderKind::Fragment,
+            &[],
+        ),
+        Shader::BlitFragment => (
+            "blit.frag",
+            include_str!("blit.frag"),
+            shaderc::ShaderKind::Fragment,
+            &[],
+        ),
+        Shader::OverdrawFragment => (
+            "overdraw.frag",
+            include_str!("overdraw.frag"),
+            shaderc::ShaderKind::Fragment,
+            &[],
+        ),
+        Shader::ClipOutlineFragment => (
+            "clip_outline.frag",
+            include_str!("clip_outline.frag"),
+            shaderc::ShaderKind::Fragment,
+            &[],
+        ),
+        Shader::BlurFragment => (
+            "blur.frag",
+            include_str!("blur.frag"),
+            shaderc::ShaderKind::Fragment,
+            &[],
+        ),
+        Shader::DepthFragment => (
+            "depth.frag",
+            include_str!("depth.frag"),
+            shaderc::ShaderKind::Fragment,
+            &[],
+        ),
+        Shader::UintFragment => (
+            "integer.frag",
+            include_str!("integer.frag"),
+            shaderc::ShaderKind::Fragment,
+            &[],
+        ),
+        Shader::SintFragment => (
+            "integer.frag",
+            include_str!("integer.frag"),
+            shaderc::ShaderKind::Fragment,
+            &["SIGNED"],
+        ),
+        Shader::SwizzleFragment => (
+            "swizzle.frag",
+            include_str!("swizzle.frag"),
+            shaderc::ShaderKind::Fragment,
+            &[],
+        ),
+    };
+    let spirv = compile(name, source, kind, defines)
+        .unwrap_or_else(|error| panic!("failed to compile {}: {}", name, error));
+    device.create_shader_module(wgpu::ShaderModuleSource::SpirV(spirv.into()))
+}
+
+/// Compiles GLSL to SPIR-V the way the built-in shaders are compiled, with `defines` such as
+/// `PUSH_CONSTANTS` selecting a variant.
+#[cfg(feature = "runtime-shaders")]
+pub(crate) fn compile(
+    name: &str,
+    source: &str,
+    kind: shaderc::ShaderKind,
+    defines: &[&str],
+) -> Result<Vec<u32>, shaderc::Error> {
+    let mut compiler = shaderc::Compiler::new().expect("failed to initialize shaderc");
+    let mut options = shaderc::CompileOptions::new().expect("failed to initialize shaderc");
+    for define in defines {
+        options.add_macro_definition(define, None);
+    }
+    let artifact = compiler.compile_into_spirv(source, kind, name, "main", Some(&options))?;
+    Ok(artifact.as_binary().to_vec())
+}
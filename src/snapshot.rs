@@ -0,0 +1,393 @@
+use crate::{Frame, FrameDrawLists};
+use imgui::{DrawIdx, DrawVert};
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+const MAGIC: &[u8; 4] = b"IMDD";
+const VERSION: u32 = 1;
+
+/// An owned copy of one frame's `imgui::DrawData` that can be replayed with
+/// `Renderer::render_snapshot` or saved to disk. Raw callbacks are not recorded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawDataSnapshot {
+    pub display_pos: [f32; 2],
+    pub display_size: [f32; 2],
+    pub framebuffer_scale: [f32; 2],
+    pub draw_lists: Vec<DrawListSnapshot>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawListSnapshot {
+    pub vtx_buffer: Vec<DrawVert>,
+    pub idx_buffer: Vec<DrawIdx>,
+    pub commands: Vec<DrawCmdSnapshot>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DrawCmdSnapshot {
+    Elements {
+        count: usize,
+        clip_rect: [f32; 4],
+        texture_id: imgui::TextureId,
+        vtx_offset: usize,
+        idx_offset: usize,
+    },
+    ResetRenderState,
+}
+impl DrawCmdSnapshot {
+    pub(crate) fn from_draw_cmd(draw_cmd: imgui::DrawCmd) -> Option<Self> {
+        match draw_cmd {
+            imgui::DrawCmd::Elements { count, cmd_params } => Some(DrawCmdSnapshot::Elements {
+                count,
+                clip_rect: cmd_params.clip_rect,
+                texture_id: cmd_params.texture_id,
+                vtx_offset: cmd_params.vtx_offset,
+                idx_offset: cmd_params.idx_offset,
+            }),
+            imgui::DrawCmd::ResetRenderState => Some(DrawCmdSnapshot::ResetRenderState),
+            imgui::DrawCmd::RawCallback { .. } => None,
+        }
+    }
+    pub(crate) fn to_draw_cmd(self) -> imgui::DrawCmd {
+        match self {
+            DrawCmdSnapshot::Elements {
+                count,
+                clip_rect,
+                texture_id,
+                vtx_offset,
+                idx_offset,
+            } => imgui::DrawCmd::Elements {
+                count,
+                cmd_params: imgui::DrawCmdParams {
+                    clip_rect,
+                    texture_id,
+                    vtx_offset,
+                    idx_offset,
+                },
+            },
+            DrawCmdSnapshot::ResetRenderState => imgui::DrawCmd::ResetRenderState,
+        }
+    }
+}
+
+impl DrawDataSnapshot {
+    pub fn new(draw_data: &imgui::DrawData) -> Self {
+        let draw_lists = draw_data
+            .draw_lists()
+            .map(|draw_list| DrawListSnapshot {
+                vtx_buffer: draw_list.vtx_buffer().to_vec(),
+                idx_buffer: draw_list.idx_buffer().to_vec(),
+                commands: draw_list
+                    .commands()
+                    .filter_map(DrawCmdSnapshot::from_draw_cmd)
+                    .collect(),
+            })
+            .collect();
+        Self {
+            display_pos: draw_data.display_pos,
+            display_size: draw_data.display_size,
+            framebuffer_scale: draw_data.framebuffer_scale,
+            draw_lists,
+        }
+    }
+
+    /// Writes the snapshot in a small little-endian binary format that `read_from` loads.
+    /// Fails with `InvalidInput` if a buffer has more than `u32::MAX` elements.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let w = &mut writer;
+        w.write_all(MAGIC)?;
+        write_u32(w, VERSION)?;
+        for value in self
+            .display_pos
+            .iter()
+            .chain(&self.display_size)
+            .chain(&self.framebuffer_scale)
+        {
+            write_f32(w, *value)?;
+        }
+        write_len(w, self.draw_lists.len())?;
+        for draw_list in &self.draw_lists {
+            write_len(w, draw_list.vtx_buffer.len())?;
+            for vertex in &draw_list.vtx_buffer {
+                for value in vertex.pos.iter().chain(&vertex.uv) {
+                    write_f32(w, *value)?;
+                }
+                w.write_all(&vertex.col)?;
+            }
+            write_len(w, draw_list.idx_buffer.len())?;
+            for index in &draw_list.idx_buffer {
+                write_u32(w, u32::from(*index))?;
+            }
+            write_len(w, draw_list.commands.len())?;
+            for command in &draw_list.commands {
+                match *command {
+                    DrawCmdSnapshot::Elements {
+                        count,
+                        clip_rect,
+                        texture_id,
+                        vtx_offset,
+                        idx_offset,
+                    } => {
+                        w.write_all(&[0])?;
+                        write_u64(w, count as u64)?;
+                        for value in &clip_rect {
+                            write_f32(w, *value)?;
+                        }
+                        write_u64(w, texture_id.id() as u64)?;
+                        write_u64(w, vtx_offset as u64)?;
+                        write_u64(w, idx_offset as u64)?;
+                    }
+                    DrawCmdSnapshot::ResetRenderState => w.write_all(&[1])?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Panics if a buffer has more than `u32::MAX` elements.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes).expect("snapshot buffer too long");
+        bytes
+    }
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<Self> {
+        let r = &mut reader;
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC || read_u32(r)? != VERSION {
+            return Err(invalid_data("not a draw data snapshot"));
+        }
+        let mut display = [0.0; 6];
+        for value in display.iter_mut() {
+            *value = read_f32(r)?;
+        }
+        let draw_list_count = read_len(r)?;
+        let mut draw_lists = Vec::new();
+        for _ in 0..draw_list_count {
+            let vtx_count = read_len(r)?;
+            let mut vtx_buffer = Vec::new();
+            for _ in 0..vtx_count {
+                let pos = [read_f32(r)?, read_f32(r)?];
+                let uv = [read_f32(r)?, read_f32(r)?];
+                let mut col = [0; 4];
+                r.read_exact(&mut col)?;
+                vtx_buffer.push(DrawVert { pos, uv, col });
+            }
+            let idx_count = read_len(r)?;
+            let mut idx_buffer = Vec::new();
+            for _ in 0..idx_count {
+                let index = DrawIdx::try_from(read_u32(r)?)
+                    .map_err(|_| invalid_data("index does not fit DrawIdx"))?;
+                idx_buffer.push(index);
+            }
+            let command_count = read_len(r)?;
+            let mut commands = Vec::new();
+            for _ in 0..command_count {
+                let mut tag = [0; 1];
+                r.read_exact(&mut tag)?;
+                commands.push(match tag[0] {
+                    0 => DrawCmdSnapshot::Elements {
+                        count: read_u64(r)? as usize,
+                        clip_rect: [read_f32(r)?, read_f32(r)?, read_f32(r)?, read_f32(r)?],
+                        texture_id: imgui::TextureId::new(read_u64(r)? as usize),
+                        vtx_offset: read_u64(r)? as usize,
+                        idx_offset: read_u64(r)? as usize,
+                    },
+                    1 => DrawCmdSnapshot::ResetRenderState,
+                    _ => return Err(invalid_data("unknown draw command")),
+                });
+            }
+            // The renderer trusts draw commands to stay within their buffers, as imgui's do.
+            for command in &commands {
+                if let DrawCmdSnapshot::Elements {
+                    count,
+                    vtx_offset,
+                    idx_offset,
+                    ..
+                } = *command
+                {
+                    let indices = idx_offset
+                        .checked_add(count)
+                        .and_then(|end| idx_buffer.get(idx_offset..end));
+                    let in_range = match indices {
+                        Some(indices) => indices.iter().all(|&index| {
+                            matches!(
+                                vtx_offset.checked_add(index as usize),
+                                Some(vertex) if vertex < vtx_buffer.len()
+                            )
+                        }),
+                        None => false,
+                    };
+                    if !in_range {
+                        return Err(invalid_data("draw command out of range"));
+                    }
+                }
+            }
+            draw_lists.push(DrawListSnapshot {
+                vtx_buffer,
+                idx_buffer,
+                commands,
+            });
+        }
+        Ok(Self {
+            display_pos: [display[0], display[1]],
+            display_size: [display[2], display[3]],
+            framebuffer_scale: [display[4], display[5]],
+            draw_lists,
+        })
+    }
+}
+
+impl<'a> From<&'a DrawDataSnapshot> for Frame<'a> {
+    fn from(snapshot: &'a DrawDataSnapshot) -> Self {
+        Self {
+            display_pos: snapshot.display_pos,
+            display_size: snapshot.display_size,
+            framebuffer_scale: snapshot.framebuffer_scale,
+            draw_lists: FrameDrawLists::Snapshot(&snapshot.draw_lists),
+        }
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+fn write_u32<W: Write>(w: &mut W, value: u32) -> io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+fn write_u64<W: Write>(w: &mut W, value: u64) -> io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+fn write_f32<W: Write>(w: &mut W, value: f32) -> io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+fn write_len<W: Write>(w: &mut W, len: usize) -> io::Result<()> {
+    let len = u32::try_from(len).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "buffer too long for a snapshot",
+        )
+    })?;
+    write_u32(w, len)
+}
+fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+fn read_f32<R: Read>(r: &mut R) -> io::Result<f32> {
+    Ok(f32::from_bits(read_u32(r)?))
+}
+fn read_len<R: Read>(r: &mut R) -> io::Result<usize> {
+    Ok(read_u32(r)? as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> DrawDataSnapshot {
+        let vertex = |x: f32, y: f32| DrawVert {
+            pos: [x, y],
+            uv: [x / 8.0, y / 8.0],
+            col: [255, 128, 0, 255],
+        };
+        DrawDataSnapshot {
+            display_pos: [0.0, 0.0],
+            display_size: [640.0, 480.0],
+            framebuffer_scale: [2.0, 2.0],
+            draw_lists: vec![DrawListSnapshot {
+                vtx_buffer: vec![vertex(0.0, 0.0), vertex(8.0, 0.0), vertex(8.0, 8.0)],
+                idx_buffer: vec![0, 1, 2, 2, 1, 0],
+                commands: vec![
+                    DrawCmdSnapshot::Elements {
+                        count: 3,
+                        clip_rect: [0.0, 0.0, 640.0, 480.0],
+                        texture_id: imgui::TextureId::from(7),
+                        vtx_offset: 0,
+                        idx_offset: 0,
+                    },
+                    DrawCmdSnapshot::ResetRenderState,
+                    DrawCmdSnapshot::Elements {
+                        count: 3,
+                        clip_rect: [8.0, 8.0, 16.0, 16.0],
+                        texture_id: imgui::TextureId::from(0),
+                        vtx_offset: 0,
+                        idx_offset: 3,
+                    },
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let snapshot = snapshot();
+        let bytes = snapshot.to_bytes();
+        assert_eq!(DrawDataSnapshot::read_from(&bytes[..]).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn truncated_input_fails() {
+        let bytes = snapshot().to_bytes();
+        for len in 0..bytes.len() {
+            assert!(DrawDataSnapshot::read_from(&bytes[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn bad_header_fails() {
+        let mut bytes = snapshot().to_bytes();
+        bytes[0] = b'X';
+        let error = DrawDataSnapshot::read_from(&bytes[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let mut bytes = snapshot().to_bytes();
+        bytes[4] = 2;
+        assert!(DrawDataSnapshot::read_from(&bytes[..]).is_err());
+    }
+
+    #[test]
+    fn out_of_range_commands_fail() {
+        let mut past_indices = snapshot();
+        if let DrawCmdSnapshot::Elements { idx_offset, .. } =
+            &mut past_indices.draw_lists[0].commands[2]
+        {
+            *idx_offset = 4;
+        }
+        let mut past_vertices = snapshot();
+        if let DrawCmdSnapshot::Elements { vtx_offset, .. } =
+            &mut past_vertices.draw_lists[0].commands[0]
+        {
+            *vtx_offset = 1;
+        }
+        let mut overflowing = snapshot();
+        if let DrawCmdSnapshot::Elements { count, .. } = &mut overflowing.draw_lists[0].commands[0]
+        {
+            *count = usize::MAX;
+        }
+        for snapshot in &[past_indices, past_vertices, overflowing] {
+            let error = DrawDataSnapshot::read_from(&snapshot.to_bytes()[..]).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn huge_counts_fail_at_the_end_of_input() {
+        let mut bytes = snapshot().to_bytes();
+        // The draw list count follows the magic, version and six display floats.
+        bytes[32..36].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(DrawDataSnapshot::read_from(&bytes[..]).is_err());
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn lengths_past_u32_fail_to_write() {
+        let error = write_len(&mut Vec::new(), u32::MAX as usize + 1).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
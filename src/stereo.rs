@@ -0,0 +1,159 @@
+use crate::{DrawLayer, RenderTarget, Renderer, RendererResult};
+
+// Each eye gets its own viewport resources: both eyes are recorded before the queue is
+// submitted, so sharing one uniform buffer would leave the right eye's projection in both.
+const EYE_VIEWPORT_IDS: [u32; 2] = [0x5354_4c00, 0x5354_5200];
+
+/// A two-layer array texture the UI is rendered into once per eye, layer 0 for the left eye and
+/// layer 1 for the right, e.g. the swapchain image of an OpenXR quad layer. wgpu has no
+/// multiview, so each eye is its own render pass, with the draw data uploaded to the eye's
+/// own buffers.
+pub struct StereoUiTarget {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    eye_views: [wgpu::TextureView; 2],
+    depth_views: Option<[wgpu::TextureView; 2]>,
+    target: RenderTarget,
+    width: u32,
+    height: u32,
+}
+impl StereoUiTarget {
+    /// `width` x `height` is the size of each eye's layer.
+    pub fn new(
+        device: &wgpu::Device,
+        renderer: &mut Renderer,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth: 2,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT
+                | wgpu::TextureUsage::SAMPLED
+                | wgpu::TextureUsage::COPY_SRC,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        let layer_views = |texture: &wgpu::Texture| {
+            let layer_view = |layer| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    dimension: Some(wgpu::TextureViewDimension::D2),
+                    base_array_layer: layer,
+                    array_layer_count: std::num::NonZeroU32::new(1),
+                    ..Default::default()
+                })
+            };
+            [layer_view(0), layer_view(1)]
+        };
+        let eye_views = layer_views(&texture);
+        let depth_views = renderer
+            .pipeline_factory
+            .depth_stencil_state
+            .as_ref()
+            .map(|state| {
+                layer_views(&device.create_texture(&wgpu::TextureDescriptor {
+                    label: None,
+                    size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: state.format,
+                    usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+                }))
+            });
+        let target = RenderTarget {
+            format,
+            sample_count: 1,
+        };
+        renderer.add_render_target(device, target);
+        Self {
+            texture,
+            view,
+            eye_views,
+            depth_views,
+            target,
+            width,
+            height,
+        }
+    }
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+    /// Both layers as a `D2Array` view.
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+    /// The layer of one eye, 0 for the left and 1 for the right.
+    pub fn eye_view(&self, eye: usize) -> &wgpu::TextureView {
+        &self.eye_views[eye]
+    }
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+    /// Clears both layers to `clear_color`, renders `draw_data` into each and submits the work.
+    /// `projections` overrides the projection of the left and right eye, e.g. with a
+    /// horizontal offset for the depth the overlay should appear at; `None` keeps the
+    /// renderer's projection for that eye.
+    pub fn render(
+        &self,
+        renderer: &mut Renderer,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        draw_data: &imgui::DrawData,
+        clear_color: wgpu::Color,
+        projections: [Option<[f32; 16]>; 2],
+    ) -> RendererResult<()> {
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        for (eye, projection) in projections.iter().enumerate() {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: &self.eye_views[eye],
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(clear_color),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: self.depth_views.as_ref().map(|depth_views| {
+                    wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                        attachment: &depth_views[eye],
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: false,
+                        }),
+                        stencil_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(0),
+                            store: false,
+                        }),
+                    }
+                }),
+            });
+            renderer.render_draw_layers(
+                device,
+                queue,
+                &mut render_pass,
+                &[DrawLayer {
+                    draw_data,
+                    projection: *projection,
+                }],
+                EYE_VIEWPORT_IDS[eye],
+                self.target,
+            )?;
+        }
+        queue.submit(Some(encoder.finish()));
+        Ok(())
+    }
+}
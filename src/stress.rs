@@ -0,0 +1,119 @@
+use crate::{DrawCmdSnapshot, DrawDataSnapshot, DrawListSnapshot};
+use imgui::{DrawIdx, DrawVert};
+
+/// Describes a synthetic frame for benchmarking the renderer without an imgui frame loop.
+/// Every window becomes one draw list of textured quads scattered over its rectangle, and
+/// commands cycle through `textures` every `quads_per_command` quads.
+#[derive(Clone, Debug)]
+pub struct StressConfig {
+    pub windows: usize,
+    pub vertices_per_window: usize,
+    /// Textures to draw with; must not be empty. Include the font atlas id to mix in text.
+    pub textures: Vec<imgui::TextureId>,
+    pub quads_per_command: usize,
+    pub display_size: [f32; 2],
+    pub seed: u64,
+}
+impl StressConfig {
+    pub fn new(
+        windows: usize,
+        vertices_per_window: usize,
+        textures: Vec<imgui::TextureId>,
+    ) -> Self {
+        Self {
+            windows,
+            vertices_per_window,
+            textures,
+            quads_per_command: 64,
+            display_size: [1920.0, 1080.0],
+            seed: 1,
+        }
+    }
+    /// Builds the frame; feed it to `Renderer::render_snapshot`. The same config always
+    /// produces the same frame.
+    pub fn generate(&self) -> DrawDataSnapshot {
+        assert!(!self.textures.is_empty(), "StressConfig needs a texture");
+        let mut rng = XorShift(self.seed.max(1));
+        let quads_per_command = self.quads_per_command.max(1);
+        // Keep every command's indices addressable by `DrawIdx` through `vtx_offset`.
+        let max_quads_per_batch = (DrawIdx::MAX as usize + 1) / 4;
+        let draw_lists = (0..self.windows)
+            .map(|_| {
+                let width = self.display_size[0] * rng.range(0.1, 0.5);
+                let height = self.display_size[1] * rng.range(0.1, 0.5);
+                let x = rng.range(0.0, self.display_size[0] - width);
+                let y = rng.range(0.0, self.display_size[1] - height);
+                let clip_rect = [x, y, x + width, y + height];
+
+                let quads = self.vertices_per_window / 4;
+                let mut vtx_buffer = Vec::with_capacity(quads * 4);
+                let mut idx_buffer = Vec::with_capacity(quads * 6);
+                let mut commands = Vec::new();
+                let mut batch_start = 0;
+                let mut quad = 0;
+                while quad < quads {
+                    if quad - batch_start == max_quads_per_batch {
+                        batch_start = quad;
+                    }
+                    let count = quads_per_command
+                        .min(quads - quad)
+                        .min(batch_start + max_quads_per_batch - quad);
+                    commands.push(DrawCmdSnapshot::Elements {
+                        count: count * 6,
+                        clip_rect,
+                        texture_id: self.textures[commands.len() % self.textures.len()],
+                        vtx_offset: batch_start * 4,
+                        idx_offset: idx_buffer.len(),
+                    });
+                    for _ in 0..count {
+                        let size = rng.range(4.0, 64.0);
+                        let qx = x + rng.range(-size, width);
+                        let qy = y + rng.range(-size, height);
+                        let col = (rng.next() as u32).to_le_bytes();
+                        let base = ((quad - batch_start) * 4) as DrawIdx;
+                        for &(u, v) in &[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+                            vtx_buffer.push(DrawVert {
+                                pos: [qx + u * size, qy + v * size],
+                                uv: [u, v],
+                                col,
+                            });
+                        }
+                        idx_buffer.extend_from_slice(&[
+                            base,
+                            base + 1,
+                            base + 2,
+                            base,
+                            base + 2,
+                            base + 3,
+                        ]);
+                        quad += 1;
+                    }
+                }
+                DrawListSnapshot {
+                    vtx_buffer,
+                    idx_buffer,
+                    commands,
+                }
+            })
+            .collect();
+        DrawDataSnapshot {
+            display_pos: [0.0, 0.0],
+            display_size: self.display_size,
+            framebuffer_scale: [1.0, 1.0],
+            draw_lists,
+        }
+    }
+}
+
+struct XorShift(u64);
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (self.next() >> 40) as f32 / (1u64 << 24) as f32 * (max - min)
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{Renderer, RendererError};
+use std::collections::HashMap;
+
+/// Why an SVG icon could not be uploaded.
+#[derive(Debug)]
+pub enum SvgError {
+    Parse(usvg::Error),
+    Renderer(RendererError),
+    /// The icon would be rasterized to zero pixels.
+    Empty,
+}
+impl std::fmt::Display for SvgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SvgError::Parse(error) => write!(f, "failed to parse svg: {}", error),
+            SvgError::Renderer(error) => error.fmt(f),
+            SvgError::Empty => write!(f, "svg rasterizes to an empty image"),
+        }
+    }
+}
+impl std::error::Error for SvgError {}
+impl From<usvg::Error> for SvgError {
+    fn from(error: usvg::Error) -> Self {
+        SvgError::Parse(error)
+    }
+}
+impl From<RendererError> for SvgError {
+    fn from(error: RendererError) -> Self {
+        SvgError::Renderer(error)
+    }
+}
+
+// `usvg::Tree` is reference counted and can't be sent between threads, so icons keep their
+// source and are parsed again when the scale changes.
+struct SvgIcon {
+    bytes: Vec<u8>,
+    px_size: u32,
+}
+
+/// The icons uploaded with `Renderer::upload_svg`, re-rasterized by `set_svg_scale`.
+pub(crate) struct SvgIcons {
+    scale: f32,
+    icons: HashMap<imgui::TextureId, SvgIcon>,
+}
+impl Default for SvgIcons {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            icons: HashMap::new(),
+        }
+    }
+}
+
+// Fits the icon into a square of `size` pixels, centered, as straight-alpha RGBA.
+fn rasterize(bytes: &[u8], size: u32) -> Result<Vec<u8>, SvgError> {
+    let tree = usvg::Tree::from_data(bytes, &usvg::Options::default().to_ref())?;
+    let icon_size = tree.svg_node().size;
+    let mut pixmap = tiny_skia::Pixmap::new(size, size).ok_or(SvgError::Empty)?;
+    let scale = (size as f64 / icon_size.width()).min(size as f64 / icon_size.height());
+    let transform = tiny_skia::Transform::from_row(
+        scale as f32,
+        0.0,
+        0.0,
+        scale as f32,
+        ((size as f64 - icon_size.width() * scale) / 2.0) as f32,
+        ((size as f64 - icon_size.height() * scale) / 2.0) as f32,
+    );
+    resvg::render(&tree, usvg::FitTo::Original, transform, pixmap.as_mut())
+        .ok_or(SvgError::Empty)?;
+    Ok(pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            vec![color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect())
+}
+
+fn scaled_size(px_size: u32, scale: f32) -> u32 {
+    (px_size as f32 * scale).round() as u32
+}
+
+impl Renderer {
+    /// Rasterizes an SVG icon to fit a square of `target_px_size` logical pixels, at the scale
+    /// set with `set_svg_scale`. Draw it at `target_px_size` logical pixels; the texture is
+    /// re-rasterized under the same id when the scale changes.
+    pub fn upload_svg(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        svg_bytes: &[u8],
+        target_px_size: u32,
+    ) -> Result<imgui::TextureId, SvgError> {
+        let size = scaled_size(target_px_size, self.svg_icons.scale);
+        let pixels = rasterize(svg_bytes, size)?;
+        let texture_id = self.upload_texture(device, queue, size, size, &pixels)?;
+        self.svg_icons.icons.insert(
+            texture_id,
+            SvgIcon {
+                bytes: svg_bytes.to_vec(),
+                px_size: target_px_size,
+            },
+        );
+        Ok(texture_id)
+    }
+    /// Sets the DPI scale SVG icons are rasterized at, usually the framebuffer scale, and
+    /// re-rasterizes the icons uploaded so far if it changed.
+    pub fn set_svg_scale(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        scale: f32,
+    ) -> Result<(), SvgError> {
+        if scale == self.svg_icons.scale {
+            return Ok(());
+        }
+        self.svg_icons.scale = scale;
+        let textures = &self.textures;
+        // Forget icons whose texture was removed.
+        self.svg_icons
+            .icons
+            .retain(|texture_id, _| textures.get(*texture_id).is_some());
+        let mut rasterized = Vec::new();
+        for (&texture_id, icon) in &self.svg_icons.icons {
+            let size = scaled_size(icon.px_size, scale);
+            crate::validate_texture_size(size, size)?;
+            rasterized.push((texture_id, size, rasterize(&icon.bytes, size)?));
+        }
+        for (texture_id, size, pixels) in rasterized {
+            if let Some(texture) = self.reallocate_texture(device, texture_id, size, size) {
+                texture.write(queue, &pixels);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,20 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+layout(location = 0) in vec2 fragUv;
+layout(location = 0) out vec4 outColor;
+
+layout(set = 0, binding = 0) uniform Swizzle {
+    // Column i picks the source channel of output channel i.
+    mat4 select;
+    // Added to the output, for channels that are constant zero or one.
+    vec4 constant;
+} swizzle;
+layout(set = 0, binding = 1) uniform sampler sourceSampler;
+layout(set = 0, binding = 2) uniform texture2D source;
+
+void main() {
+    // Fetched rather than sampled, so formats that can't be filtered work too.
+    vec4 texel = texelFetch(sampler2D(source, sourceSampler), ivec2(gl_FragCoord.xy), 0);
+    outColor = texel * swizzle.select + swizzle.constant;
+}
@@ -0,0 +1,106 @@
+use crate::fullscreen_pass::FullscreenPass;
+use crate::shaders::Shader;
+use crate::Renderer;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct SwizzleMatrix {
+    // Column i is one-hot on the source channel of output channel i.
+    select: [[f32; 4]; 4],
+    constant: [f32; 4],
+}
+
+unsafe impl bytemuck::Zeroable for SwizzleMatrix {}
+
+unsafe impl bytemuck::Pod for SwizzleMatrix {}
+
+/// Where an output channel of a `Swizzle` comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwizzleChannel {
+    R,
+    G,
+    B,
+    A,
+    Zero,
+    One,
+}
+
+/// The source of the red, green, blue and alpha channel shown by a `SwizzleView`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Swizzle(pub [SwizzleChannel; 4]);
+impl Swizzle {
+    pub const IDENTITY: Swizzle = Swizzle([
+        SwizzleChannel::R,
+        SwizzleChannel::G,
+        SwizzleChannel::B,
+        SwizzleChannel::A,
+    ]);
+    /// Shows one channel as opaque grayscale, e.g. `SwizzleChannel::A` for a mask.
+    pub fn grayscale(channel: SwizzleChannel) -> Self {
+        Swizzle([channel, channel, channel, SwizzleChannel::One])
+    }
+    fn matrix(self) -> SwizzleMatrix {
+        let mut matrix = SwizzleMatrix {
+            select: [[0.0; 4]; 4],
+            constant: [0.0; 4],
+        };
+        for (output, channel) in self.0.iter().enumerate() {
+            match channel {
+                SwizzleChannel::R => matrix.select[output][0] = 1.0,
+                SwizzleChannel::G => matrix.select[output][1] = 1.0,
+                SwizzleChannel::B => matrix.select[output][2] = 1.0,
+                SwizzleChannel::A => matrix.select[output][3] = 1.0,
+                SwizzleChannel::Zero => {}
+                SwizzleChannel::One => matrix.constant[output] = 1.0,
+            }
+        }
+        matrix
+    }
+}
+
+/// Shows a texture with its channels rearranged, e.g. a single G-buffer channel or a mask's
+/// alpha as grayscale, with `imgui::Image` by copying it through a swizzle into a texture
+/// registered with the renderer. wgpu has no texture view swizzles, so this takes a pass.
+pub struct SwizzleView {
+    swizzle: Swizzle,
+    pass: FullscreenPass<SwizzleMatrix>,
+}
+impl SwizzleView {
+    /// `source` is a view of a `width` x `height` texture with `SAMPLED` usage and a `Float`
+    /// `component_type`. Create a new `SwizzleView` when the source texture is recreated.
+    pub fn new(
+        device: &wgpu::Device,
+        renderer: &mut Renderer,
+        source: &wgpu::TextureView,
+        width: u32,
+        height: u32,
+        swizzle: Swizzle,
+    ) -> Self {
+        Self {
+            swizzle,
+            pass: FullscreenPass::new(
+                device,
+                renderer,
+                source,
+                wgpu::TextureComponentType::Float,
+                width,
+                height,
+                Shader::SwizzleFragment,
+            ),
+        }
+    }
+    pub fn texture_id(&self) -> imgui::TextureId {
+        self.pass.texture_id()
+    }
+    pub fn swizzle(&self) -> Swizzle {
+        self.swizzle
+    }
+    /// Takes effect at the next `update`.
+    pub fn set_swizzle(&mut self, swizzle: Swizzle) {
+        self.swizzle = swizzle;
+    }
+    /// Records the swizzled copy of the source texture's current contents into `encoder`.
+    pub fn update(&self, queue: &wgpu::Queue, encoder: &mut wgpu::CommandEncoder) {
+        self.pass.update(queue, encoder, &self.swizzle.matrix());
+    }
+}
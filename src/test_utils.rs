@@ -0,0 +1,105 @@
+use crate::{Renderer, RendererConfig, RendererResult};
+
+/// Headless rendering for tests of UI code. Every frame is rendered into an RGBA texture and
+/// read back, so the pixels can be checked with the assertion helpers below.
+pub struct TestRenderer {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub imgui: imgui::Context,
+    pub renderer: Renderer,
+    width: u32,
+    height: u32,
+}
+impl TestRenderer {
+    /// Returns `None` when no adapter is available, e.g. on CI machines without a GPU, so
+    /// tests can skip themselves. imgui allows one context at a time, so keep a single
+    /// `TestRenderer` alive per thread.
+    pub fn new(width: u32, height: u32) -> Option<Self> {
+        let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::Default,
+            compatible_surface: None,
+        }))?;
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                features: wgpu::Features::empty(),
+                limits: wgpu::Limits::default(),
+                shader_validation: true,
+            },
+            None,
+        ))
+        .ok()?;
+        let mut imgui = imgui::Context::create();
+        imgui.set_ini_filename(None);
+        imgui.io_mut().display_size = [width as f32, height as f32];
+        imgui.io_mut().delta_time = 1.0 / 60.0;
+        let renderer = Renderer::new(
+            &mut imgui,
+            &device,
+            &queue,
+            RendererConfig::new(wgpu::TextureFormat::Rgba8Unorm),
+        )
+        .ok()?;
+        Some(Self {
+            device,
+            queue,
+            imgui,
+            renderer,
+            width,
+            height,
+        })
+    }
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+    /// Builds one frame with `build`, renders it and returns its tightly packed RGBA pixels.
+    /// Windows only settle into their final layout on the second frame.
+    pub fn render_frame<F: FnOnce(&imgui::Ui)>(&mut self, build: F) -> RendererResult<Vec<u8>> {
+        let ui = self.imgui.frame();
+        build(&ui);
+        let draw_data = ui.render();
+        let pixels = self.renderer.capture_frame(
+            &self.device,
+            &self.queue,
+            draw_data,
+            self.width,
+            self.height,
+        )?;
+        self.renderer.end_frame();
+        Ok(pixels)
+    }
+}
+
+/// The RGBA value at `(x, y)` of pixels returned by `TestRenderer::render_frame`.
+pub fn pixel(pixels: &[u8], width: u32, x: u32, y: u32) -> [u8; 4] {
+    let offset = ((y * width + x) * 4) as usize;
+    [
+        pixels[offset],
+        pixels[offset + 1],
+        pixels[offset + 2],
+        pixels[offset + 3],
+    ]
+}
+
+/// Panics unless every channel at `(x, y)` is within `tolerance` of `expected`.
+pub fn assert_pixel(pixels: &[u8], width: u32, x: u32, y: u32, expected: [u8; 4], tolerance: u8) {
+    let actual = pixel(pixels, width, x, y);
+    let matches = actual
+        .iter()
+        .zip(&expected)
+        .all(|(a, e)| (*a as i16 - *e as i16).abs() <= tolerance as i16);
+    assert!(
+        matches,
+        "pixel ({}, {}) is {:?}, expected {:?}",
+        x, y, actual, expected
+    );
+}
+
+/// Panics unless some pixel differs from `background`, i.e. something was drawn at all.
+pub fn assert_not_blank(pixels: &[u8], background: [u8; 4]) {
+    assert!(
+        pixels.chunks(4).any(|texel| texel != background),
+        "every pixel is {:?}",
+        background
+    );
+}
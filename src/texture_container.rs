@@ -0,0 +1,768 @@
+use crate::{BlendMode, Renderer, RendererError, Texture};
+use std::borrow::Cow;
+use std::convert::TryInto;
+
+const KTX2_MAGIC: &[u8] = b"\xABKTX 20\xBB\r\n\x1A\n";
+const DDS_MAGIC: &[u8] = b"DDS ";
+
+/// Why a KTX2 or DDS file could not be uploaded.
+#[derive(Debug)]
+pub enum TextureContainerError {
+    Ktx2(ktx2::ParseError),
+    Dds(ddsfile::Error),
+    /// The data starts with neither the KTX2 nor the DDS magic number.
+    UnknownContainer,
+    /// A pixel format there is no loader for, named as in the container.
+    UnsupportedFormat(String),
+    /// A cube map, array, volume or supercompressed texture; only plain 2D textures load.
+    UnsupportedLayout(&'static str),
+    Renderer(RendererError),
+}
+impl std::fmt::Display for TextureContainerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextureContainerError::Ktx2(error) => write!(f, "failed to parse ktx2 file: {}", error),
+            TextureContainerError::Dds(error) => write!(f, "failed to parse dds file: {}", error),
+            TextureContainerError::UnknownContainer => {
+                write!(f, "data is neither a ktx2 nor a dds file")
+            }
+            TextureContainerError::UnsupportedFormat(format) => {
+                write!(f, "unsupported texture container format {}", format)
+            }
+            TextureContainerError::UnsupportedLayout(layout) => {
+                write!(f, "{} are not supported", layout)
+            }
+            TextureContainerError::Renderer(error) => error.fmt(f),
+        }
+    }
+}
+impl std::error::Error for TextureContainerError {}
+impl From<ktx2::ParseError> for TextureContainerError {
+    fn from(error: ktx2::ParseError) -> Self {
+        TextureContainerError::Ktx2(error)
+    }
+}
+impl From<ddsfile::Error> for TextureContainerError {
+    fn from(error: ddsfile::Error) -> Self {
+        TextureContainerError::Dds(error)
+    }
+}
+impl From<RendererError> for TextureContainerError {
+    fn from(error: RendererError) -> Self {
+        TextureContainerError::Renderer(error)
+    }
+}
+
+// A 2D texture read from a container, with its mip levels, largest first. sRGB formats are
+// read as their UNORM counterparts: the renderer treats all texture data as sRGB encoded and
+// decodes it in the shader, so sampling must not decode it too.
+struct Image<'a> {
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    levels: Vec<&'a [u8]>,
+    premultiplied: bool,
+}
+
+// Checks the data format descriptor and level ranges of a KTX2 file before handing it to
+// `ktx2::Reader`, which adds the offsets to the lengths unchecked and bounds checks only the
+// last level, so a corrupt index would overflow or slice out of bounds.
+fn ktx2_reader(bytes: &[u8]) -> Result<ktx2::Reader<&[u8]>, ktx2::ParseError> {
+    const HEADER_LENGTH: usize = 80;
+    const LEVEL_INDEX_LENGTH: usize = 24;
+    let u32_at = |at: usize| {
+        bytes
+            .get(at..at + 4)
+            .map(|data| u32::from_le_bytes(data.try_into().unwrap()))
+    };
+    let u64_at = |at: usize| {
+        bytes
+            .get(at..at + 8)
+            .map(|data| u64::from_le_bytes(data.try_into().unwrap()))
+    };
+    let in_bounds = |offset: u64, length: u64| matches!(offset.checked_add(length), Some(end) if end <= bytes.len() as u64);
+    if let (Some(level_count), Some(dfd_offset), Some(dfd_length)) =
+        (u32_at(40), u32_at(48), u32_at(52))
+    {
+        if dfd_offset.checked_add(dfd_length).is_none()
+            || !in_bounds(dfd_offset.into(), dfd_length.into())
+        {
+            return Err(ktx2::ParseError::UnexpectedEnd);
+        }
+        for level in 0..level_count.max(1) as usize {
+            let at = HEADER_LENGTH + level * LEVEL_INDEX_LENGTH;
+            match (u64_at(at), u64_at(at + 8)) {
+                (Some(offset), Some(length)) if in_bounds(offset, length) => {}
+                _ => return Err(ktx2::ParseError::UnexpectedEnd),
+            }
+        }
+    }
+    ktx2::Reader::new(bytes)
+}
+
+fn ktx2_image<'a>(reader: &'a ktx2::Reader<&[u8]>) -> Result<Image<'a>, TextureContainerError> {
+    let header = reader.header();
+    if header.supercompression_scheme.is_some() {
+        return Err(TextureContainerError::UnsupportedLayout(
+            "supercompressed ktx2 files",
+        ));
+    } else if header.face_count > 1 {
+        return Err(TextureContainerError::UnsupportedLayout("cube maps"));
+    } else if header.layer_count > 1 {
+        return Err(TextureContainerError::UnsupportedLayout("texture arrays"));
+    } else if header.pixel_depth > 1 {
+        return Err(TextureContainerError::UnsupportedLayout("volume textures"));
+    }
+    let format = match header.format {
+        Some(ktx2::Format::R8G8B8A8_UNORM) | Some(ktx2::Format::R8G8B8A8_SRGB) => {
+            wgpu::TextureFormat::Rgba8Unorm
+        }
+        Some(ktx2::Format::B8G8R8A8_UNORM) | Some(ktx2::Format::B8G8R8A8_SRGB) => {
+            wgpu::TextureFormat::Bgra8Unorm
+        }
+        Some(ktx2::Format::BC1_RGBA_UNORM_BLOCK) | Some(ktx2::Format::BC1_RGBA_SRGB_BLOCK) => {
+            wgpu::TextureFormat::Bc1RgbaUnorm
+        }
+        Some(ktx2::Format::BC2_UNORM_BLOCK) | Some(ktx2::Format::BC2_SRGB_BLOCK) => {
+            wgpu::TextureFormat::Bc2RgbaUnorm
+        }
+        Some(ktx2::Format::BC3_UNORM_BLOCK) | Some(ktx2::Format::BC3_SRGB_BLOCK) => {
+            wgpu::TextureFormat::Bc3RgbaUnorm
+        }
+        Some(ktx2::Format::BC4_UNORM_BLOCK) => wgpu::TextureFormat::Bc4RUnorm,
+        Some(ktx2::Format::BC5_UNORM_BLOCK) => wgpu::TextureFormat::Bc5RgUnorm,
+        Some(ktx2::Format::BC7_UNORM_BLOCK) | Some(ktx2::Format::BC7_SRGB_BLOCK) => {
+            wgpu::TextureFormat::Bc7RgbaUnorm
+        }
+        Some(format) => {
+            return Err(TextureContainerError::UnsupportedFormat(format!(
+                "{:?}",
+                format
+            )))
+        }
+        // Basis Universal files leave the format undefined.
+        None => {
+            return Err(TextureContainerError::UnsupportedFormat(
+                "UNDEFINED".to_string(),
+            ))
+        }
+    };
+    Ok(Image {
+        format,
+        width: header.pixel_width,
+        height: header.pixel_height.max(1),
+        levels: reader.levels().collect(),
+        premultiplied: false,
+    })
+}
+
+fn dds_image(dds: &ddsfile::Dds) -> Result<Image<'_>, TextureContainerError> {
+    if dds.header.caps2.contains(ddsfile::Caps2::CUBEMAP)
+        || matches!(
+            &dds.header10,
+            Some(header10) if header10.misc_flag.contains(ddsfile::MiscFlag::TEXTURECUBE)
+        )
+    {
+        return Err(TextureContainerError::UnsupportedLayout("cube maps"));
+    } else if dds.get_num_array_layers() > 1 {
+        return Err(TextureContainerError::UnsupportedLayout("texture arrays"));
+    } else if dds.get_depth() > 1 {
+        return Err(TextureContainerError::UnsupportedLayout("volume textures"));
+    }
+    let mut premultiplied = matches!(
+        &dds.header10,
+        Some(header10) if header10.alpha_mode == ddsfile::AlphaMode::PreMultiplied
+    );
+    let format = if let Some(format) = dds.get_dxgi_format() {
+        match format {
+            ddsfile::DxgiFormat::R8G8B8A8_UNorm | ddsfile::DxgiFormat::R8G8B8A8_UNorm_sRGB => {
+                wgpu::TextureFormat::Rgba8Unorm
+            }
+            ddsfile::DxgiFormat::B8G8R8A8_UNorm | ddsfile::DxgiFormat::B8G8R8A8_UNorm_sRGB => {
+                wgpu::TextureFormat::Bgra8Unorm
+            }
+            ddsfile::DxgiFormat::BC1_UNorm | ddsfile::DxgiFormat::BC1_UNorm_sRGB => {
+                wgpu::TextureFormat::Bc1RgbaUnorm
+            }
+            ddsfile::DxgiFormat::BC2_UNorm | ddsfile::DxgiFormat::BC2_UNorm_sRGB => {
+                wgpu::TextureFormat::Bc2RgbaUnorm
+            }
+            ddsfile::DxgiFormat::BC3_UNorm | ddsfile::DxgiFormat::BC3_UNorm_sRGB => {
+                wgpu::TextureFormat::Bc3RgbaUnorm
+            }
+            ddsfile::DxgiFormat::BC4_UNorm => wgpu::TextureFormat::Bc4RUnorm,
+            ddsfile::DxgiFormat::BC5_UNorm => wgpu::TextureFormat::Bc5RgUnorm,
+            ddsfile::DxgiFormat::BC7_UNorm | ddsfile::DxgiFormat::BC7_UNorm_sRGB => {
+                wgpu::TextureFormat::Bc7RgbaUnorm
+            }
+            format => {
+                return Err(TextureContainerError::UnsupportedFormat(format!(
+                    "{:?}",
+                    format
+                )))
+            }
+        }
+    } else if let Some(format) = dds.get_d3d_format() {
+        match format {
+            ddsfile::D3DFormat::A8B8G8R8 => wgpu::TextureFormat::Rgba8Unorm,
+            ddsfile::D3DFormat::A8R8G8B8 => wgpu::TextureFormat::Bgra8Unorm,
+            ddsfile::D3DFormat::DXT1 => wgpu::TextureFormat::Bc1RgbaUnorm,
+            ddsfile::D3DFormat::DXT3 => wgpu::TextureFormat::Bc2RgbaUnorm,
+            ddsfile::D3DFormat::DXT5 => wgpu::TextureFormat::Bc3RgbaUnorm,
+            // DXT2 and DXT4 are DXT3 and DXT5 with premultiplied alpha.
+            ddsfile::D3DFormat::DXT2 => {
+                premultiplied = true;
+                wgpu::TextureFormat::Bc2RgbaUnorm
+            }
+            ddsfile::D3DFormat::DXT4 => {
+                premultiplied = true;
+                wgpu::TextureFormat::Bc3RgbaUnorm
+            }
+            format => {
+                return Err(TextureContainerError::UnsupportedFormat(format!(
+                    "{:?}",
+                    format
+                )))
+            }
+        }
+    } else {
+        return Err(TextureContainerError::UnsupportedFormat(
+            "unknown".to_string(),
+        ));
+    };
+
+    // DDS stores the levels back to back.
+    let (width, height) = (dds.get_width(), dds.get_height());
+    let level_count = dds
+        .get_num_mipmap_levels()
+        .min(mip_chain_length(width, height))
+        .max(1);
+    let mut data = &dds.data[..];
+    let mut levels = Vec::new();
+    for level in 0..level_count {
+        let (level_width, level_height) = mip_size(width, height, level);
+        let size = level_size(format, level_width, level_height);
+        if data.len() < size {
+            return Err(RendererError::TextureDataSize {
+                expected: size,
+                actual: data.len(),
+            }
+            .into());
+        }
+        let (level, rest) = data.split_at(size);
+        levels.push(level);
+        data = rest;
+    }
+    Ok(Image {
+        format,
+        width,
+        height,
+        levels,
+        premultiplied,
+    })
+}
+
+// The number of levels of a full mip chain.
+fn mip_chain_length(width: u32, height: u32) -> u32 {
+    32 - (width | height).leading_zeros()
+}
+
+fn mip_size(width: u32, height: u32, level: u32) -> (u32, u32) {
+    ((width >> level).max(1), (height >> level).max(1))
+}
+
+// The bytes of a 4x4 block and its width, or of a pixel and 1 for uncompressed formats.
+fn block_layout(format: wgpu::TextureFormat) -> (u32, u32) {
+    match format {
+        wgpu::TextureFormat::Bc1RgbaUnorm | wgpu::TextureFormat::Bc4RUnorm => (8, 4),
+        wgpu::TextureFormat::Bc2RgbaUnorm
+        | wgpu::TextureFormat::Bc3RgbaUnorm
+        | wgpu::TextureFormat::Bc5RgUnorm
+        | wgpu::TextureFormat::Bc7RgbaUnorm => (16, 4),
+        _ => (4, 1),
+    }
+}
+
+fn level_size(format: wgpu::TextureFormat, width: u32, height: u32) -> usize {
+    let (block_bytes, block_size) = block_layout(format);
+    let blocks_wide = width.div_ceil(block_size);
+    let blocks_high = height.div_ceil(block_size);
+    // Saturating, as the size of a corrupt header's level is compared against the data only.
+    (blocks_wide as usize)
+        .saturating_mul(blocks_high as usize)
+        .saturating_mul(block_bytes as usize)
+}
+
+// Decodes a level of BC1 to BC5 blocks to tightly packed RGBA; None for BC7. BC4 and BC5
+// decode to red and red-green, as the GPU samples them.
+fn decode_bc(format: wgpu::TextureFormat, width: u32, height: u32, data: &[u8]) -> Option<Vec<u8>> {
+    let (block_bytes, _) = block_layout(format);
+    let (width, height) = (width as usize, height as usize);
+    let blocks_wide = width.div_ceil(4);
+    let mut pixels = vec![0; width * height * 4];
+    for (index, block) in data.chunks_exact(block_bytes as usize).enumerate() {
+        let texels = match format {
+            wgpu::TextureFormat::Bc1RgbaUnorm => color_block(block, false),
+            wgpu::TextureFormat::Bc2RgbaUnorm => {
+                let mut texels = color_block(&block[8..], true);
+                let alpha = u64::from_le_bytes(block[..8].try_into().unwrap());
+                for (i, texel) in texels.iter_mut().enumerate() {
+                    texel[3] = ((alpha >> (4 * i)) & 0xF) as u8 * 17;
+                }
+                texels
+            }
+            wgpu::TextureFormat::Bc3RgbaUnorm => {
+                let mut texels = color_block(&block[8..], true);
+                let alpha = channel_block(&block[..8]);
+                for (texel, alpha) in texels.iter_mut().zip(&alpha) {
+                    texel[3] = *alpha;
+                }
+                texels
+            }
+            wgpu::TextureFormat::Bc4RUnorm => {
+                let mut texels = [[0, 0, 0, 255]; 16];
+                for (texel, red) in texels.iter_mut().zip(&channel_block(block)) {
+                    texel[0] = *red;
+                }
+                texels
+            }
+            wgpu::TextureFormat::Bc5RgUnorm => {
+                let red = channel_block(&block[..8]);
+                let green = channel_block(&block[8..]);
+                let mut texels = [[0, 0, 0, 255]; 16];
+                for (i, texel) in texels.iter_mut().enumerate() {
+                    texel[0] = red[i];
+                    texel[1] = green[i];
+                }
+                texels
+            }
+            _ => return None,
+        };
+        let (block_x, block_y) = (index % blocks_wide * 4, index / blocks_wide * 4);
+        for (i, texel) in texels.iter().enumerate() {
+            let (x, y) = (block_x + i % 4, block_y + i / 4);
+            // Blocks overhang the edges of sizes that aren't multiples of 4.
+            if x < width && y < height {
+                let offset = (y * width + x) * 4;
+                pixels[offset..offset + 4].copy_from_slice(texel);
+            }
+        }
+    }
+    Some(pixels)
+}
+
+// The texels of a BC1 color block. BC2 and BC3 always use the four-color mode, where BC1
+// switches to three colors and transparent black when the first endpoint isn't greater.
+fn color_block(block: &[u8], four_colors: bool) -> [[u8; 4]; 16] {
+    let endpoint0 = u16::from_le_bytes([block[0], block[1]]);
+    let endpoint1 = u16::from_le_bytes([block[2], block[3]]);
+    let (color0, color1) = (rgb565(endpoint0), rgb565(endpoint1));
+    let mix = |weight0: u16, weight1: u16| {
+        let mut color = [0, 0, 0, 255];
+        for channel in 0..3 {
+            color[channel] = ((color0[channel] as u16 * weight0 + color1[channel] as u16 * weight1)
+                / (weight0 + weight1)) as u8;
+        }
+        color
+    };
+    let palette = if four_colors || endpoint0 > endpoint1 {
+        [color0, color1, mix(2, 1), mix(1, 2)]
+    } else {
+        [color0, color1, mix(1, 1), [0, 0, 0, 0]]
+    };
+    let indices = u32::from_le_bytes(block[4..8].try_into().unwrap());
+    let mut texels = [[0; 4]; 16];
+    for (i, texel) in texels.iter_mut().enumerate() {
+        *texel = palette[(indices >> (2 * i)) as usize & 3];
+    }
+    texels
+}
+
+fn rgb565(color: u16) -> [u8; 4] {
+    let red = (color >> 11) as u8 & 0x1F;
+    let green = (color >> 5) as u8 & 0x3F;
+    let blue = color as u8 & 0x1F;
+    [
+        red << 3 | red >> 2,
+        green << 2 | green >> 4,
+        blue << 3 | blue >> 2,
+        255,
+    ]
+}
+
+// The values of a BC3 alpha block, or of a BC4 or BC5 channel.
+fn channel_block(block: &[u8]) -> [u8; 16] {
+    let (value0, value1) = (block[0] as u32, block[1] as u32);
+    let palette = if value0 > value1 {
+        [
+            value0,
+            value1,
+            (6 * value0 + value1) / 7,
+            (5 * value0 + 2 * value1) / 7,
+            (4 * value0 + 3 * value1) / 7,
+            (3 * value0 + 4 * value1) / 7,
+            (2 * value0 + 5 * value1) / 7,
+            (value0 + 6 * value1) / 7,
+        ]
+    } else {
+        [
+            value0,
+            value1,
+            (4 * value0 + value1) / 5,
+            (3 * value0 + 2 * value1) / 5,
+            (2 * value0 + 3 * value1) / 5,
+            (value0 + 4 * value1) / 5,
+            0,
+            255,
+        ]
+    };
+    let mut bits = [0; 8];
+    bits[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(bits);
+    let mut values = [0; 16];
+    for (i, value) in values.iter_mut().enumerate() {
+        *value = palette[(indices >> (3 * i)) as usize & 7] as u8;
+    }
+    values
+}
+
+impl Renderer {
+    /// Uploads a KTX2 or DDS file with its mip chain, telling them apart by their magic
+    /// numbers. Only 2D textures in RGBA8, BGRA8 and BC1 to BC5 and BC7 formats load.
+    ///
+    /// BC textures stay compressed when the device has `TEXTURE_COMPRESSION_BC`, and are
+    /// decoded to RGBA otherwise, except for BC7, which fails with
+    /// `RendererError::UnsupportedTextureFormat`. wgpu copies whole blocks only, so BC
+    /// textures whose size isn't a multiple of 4 are decoded too, and mip levels from the
+    /// first one that isn't a multiple of 4 are dropped.
+    pub fn upload_texture_container(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bytes: &[u8],
+    ) -> Result<imgui::TextureId, TextureContainerError> {
+        if bytes.starts_with(KTX2_MAGIC) {
+            let reader = ktx2_reader(bytes)?;
+            self.upload_image(device, queue, ktx2_image(&reader)?)
+        } else if bytes.starts_with(DDS_MAGIC) {
+            let dds = ddsfile::Dds::read(bytes)?;
+            self.upload_image(device, queue, dds_image(&dds)?)
+        } else {
+            Err(TextureContainerError::UnknownContainer)
+        }
+    }
+    fn upload_image(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: Image<'_>,
+    ) -> Result<imgui::TextureId, TextureContainerError> {
+        crate::validate_texture_size(image.width, image.height)?;
+        let (width, height) = (image.width, image.height);
+        let mut levels = Vec::new();
+        let level_count = mip_chain_length(width, height) as usize;
+        for (level, data) in image.levels.iter().take(level_count).enumerate() {
+            let (level_width, level_height) = mip_size(width, height, level as u32);
+            let size = level_size(image.format, level_width, level_height);
+            if data.len() < size {
+                return Err(RendererError::TextureDataSize {
+                    expected: size,
+                    actual: data.len(),
+                }
+                .into());
+            }
+            levels.push(Cow::Borrowed(&data[..size]));
+        }
+
+        let (_, block_size) = block_layout(image.format);
+        let compressed = block_size > 1;
+        let format = if compressed
+            && (!device
+                .features()
+                .contains(wgpu::Features::TEXTURE_COMPRESSION_BC)
+                || width % block_size != 0
+                || height % block_size != 0)
+        {
+            for (level, data) in levels.iter_mut().enumerate() {
+                let (level_width, level_height) = mip_size(width, height, level as u32);
+                let pixels = decode_bc(image.format, level_width, level_height, data)
+                    .ok_or(RendererError::UnsupportedTextureFormat(image.format))?;
+                *data = Cow::Owned(pixels);
+            }
+            wgpu::TextureFormat::Rgba8Unorm
+        } else {
+            let whole_blocks = (0..levels.len() as u32)
+                .take_while(|&level| {
+                    let (level_width, level_height) = mip_size(width, height, level);
+                    level_width % block_size == 0 && level_height % block_size == 0
+                })
+                .count();
+            levels.truncate(whole_blocks);
+            image.format
+        };
+
+        let descriptor = wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth: 1,
+            },
+            mip_level_count: levels.len() as u32,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            // Not `UPLOAD_USAGE`, so the texture pool never hands the mip chain to
+            // `upload_texture`, which writes the base level only.
+            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+        };
+        crate::validate_texture_descriptor(device, &descriptor)?;
+        let mut texture = Texture::with_sampler(
+            device,
+            &self.texture_bind_layout,
+            &descriptor,
+            self.samplers.get(self.default_sampler),
+        );
+        texture.sampler = Some(self.default_sampler);
+        if image.premultiplied {
+            texture.blend_mode = BlendMode::PremultipliedAlpha;
+        }
+        let (block_bytes, block_size) = block_layout(format);
+        for (level, data) in levels.iter().enumerate() {
+            let (level_width, level_height) = mip_size(width, height, level as u32);
+            queue.write_texture(
+                wgpu::TextureCopyView {
+                    texture: texture.texture().unwrap(),
+                    mip_level: level as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                data,
+                wgpu::TextureDataLayout {
+                    offset: 0,
+                    bytes_per_row: level_width / block_size * block_bytes,
+                    rows_per_image: 0,
+                },
+                wgpu::Extent3d {
+                    width: level_width,
+                    height: level_height,
+                    depth: 1,
+                },
+            );
+        }
+        Ok(self.insert_texture(texture))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A KTX2 file with the given Vulkan format and levels, and a data format descriptor of
+    // just its length.
+    fn ktx2_file(format: u32, width: u32, height: u32, levels: &[&[u8]]) -> Vec<u8> {
+        let dfd_offset = 80 + 24 * levels.len() as u32;
+        let mut file = KTX2_MAGIC.to_vec();
+        let fields = [
+            format,
+            1,
+            width,
+            height,
+            0,
+            0,
+            1,
+            levels.len() as u32,
+            0,
+            dfd_offset,
+            4,
+            0,
+            0,
+        ];
+        for field in fields.iter() {
+            file.extend_from_slice(&field.to_le_bytes());
+        }
+        file.extend_from_slice(&[0; 16]);
+        let mut offset = dfd_offset as u64 + 4;
+        for level in levels {
+            let length = level.len() as u64;
+            for field in [offset, length, length].iter() {
+                file.extend_from_slice(&field.to_le_bytes());
+            }
+            offset += length;
+        }
+        file.extend_from_slice(&4u32.to_le_bytes());
+        for level in levels {
+            file.extend_from_slice(level);
+        }
+        file
+    }
+
+    fn set_u64(file: &mut [u8], at: usize, value: u64) {
+        file[at..at + 8].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn dds_file(dds: &ddsfile::Dds) -> Vec<u8> {
+        let mut file = Vec::new();
+        dds.write(&mut file).unwrap();
+        file
+    }
+
+    fn bc1_dds() -> ddsfile::Dds {
+        let mut dds = ddsfile::Dds::new_dxgi(ddsfile::NewDxgiParams {
+            height: 8,
+            width: 8,
+            depth: None,
+            format: ddsfile::DxgiFormat::BC1_UNorm,
+            mipmap_levels: Some(4),
+            array_layers: None,
+            caps2: None,
+            is_cubemap: false,
+            resource_dimension: ddsfile::D3D10ResourceDimension::Texture2D,
+            alpha_mode: ddsfile::AlphaMode::PreMultiplied,
+        })
+        .unwrap();
+        for (i, byte) in dds.data.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        dds
+    }
+
+    #[test]
+    fn mip_and_block_math() {
+        assert_eq!(mip_chain_length(1, 1), 1);
+        assert_eq!(mip_chain_length(4, 2), 3);
+        assert_eq!(mip_chain_length(256, 17), 9);
+        assert_eq!(mip_size(5, 3, 1), (2, 1));
+        assert_eq!(mip_size(5, 3, 4), (1, 1));
+        assert_eq!(block_layout(wgpu::TextureFormat::Bc1RgbaUnorm), (8, 4));
+        assert_eq!(block_layout(wgpu::TextureFormat::Bc7RgbaUnorm), (16, 4));
+        assert_eq!(block_layout(wgpu::TextureFormat::Rgba8Unorm), (4, 1));
+        assert_eq!(level_size(wgpu::TextureFormat::Rgba8Unorm, 3, 2), 24);
+        // Partial blocks take a whole block.
+        assert_eq!(level_size(wgpu::TextureFormat::Bc1RgbaUnorm, 5, 3), 16);
+        assert_eq!(level_size(wgpu::TextureFormat::Bc3RgbaUnorm, 1, 1), 16);
+        assert_eq!(
+            level_size(wgpu::TextureFormat::Rgba8Unorm, u32::MAX, u32::MAX),
+            usize::MAX
+        );
+    }
+
+    #[test]
+    fn ktx2_levels_are_read() {
+        let levels: [&[u8]; 3] = [&[1; 32], &[2; 8], &[3; 4]];
+        let file = ktx2_file(37, 4, 2, &levels);
+        let reader = ktx2_reader(&file).unwrap();
+        let image = ktx2_image(&reader).unwrap();
+        assert_eq!(image.format, wgpu::TextureFormat::Rgba8Unorm);
+        assert_eq!((image.width, image.height), (4, 2));
+        assert_eq!(image.levels, levels);
+        assert!(!image.premultiplied);
+
+        let file = ktx2_file(145, 4, 4, &[&[0; 16]]);
+        let reader = ktx2_reader(&file).unwrap();
+        let image = ktx2_image(&reader).unwrap();
+        assert_eq!(image.format, wgpu::TextureFormat::Bc7RgbaUnorm);
+        assert_eq!(image.levels.len(), 1);
+    }
+
+    #[test]
+    fn dds_levels_are_read() {
+        let dds = ddsfile::Dds::read(&dds_file(&bc1_dds())[..]).unwrap();
+        let image = dds_image(&dds).unwrap();
+        assert_eq!(image.format, wgpu::TextureFormat::Bc1RgbaUnorm);
+        assert_eq!((image.width, image.height), (8, 8));
+        // 2x2 blocks, then one block for each of the 4x4, 2x2 and 1x1 levels.
+        let lengths: Vec<usize> = image.levels.iter().map(|level| level.len()).collect();
+        assert_eq!(lengths, [32, 8, 8, 8]);
+        assert_eq!(image.levels[1][0], 32);
+        assert!(image.premultiplied);
+
+        let dds = ddsfile::Dds::new_d3d(ddsfile::NewD3dParams {
+            height: 4,
+            width: 4,
+            depth: None,
+            format: ddsfile::D3DFormat::DXT4,
+            mipmap_levels: None,
+            caps2: None,
+        })
+        .unwrap();
+        let dds = ddsfile::Dds::read(&dds_file(&dds)[..]).unwrap();
+        let image = dds_image(&dds).unwrap();
+        assert_eq!(image.format, wgpu::TextureFormat::Bc3RgbaUnorm);
+        assert_eq!(image.levels, [&[0; 16][..]]);
+        assert!(image.premultiplied);
+    }
+
+    #[test]
+    fn truncated_files_fail() {
+        let file = ktx2_file(37, 4, 2, &[&[1; 32], &[2; 8], &[3; 4]]);
+        for length in 0..file.len() {
+            assert!(ktx2_reader(&file[..length]).is_err(), "{} bytes", length);
+        }
+
+        let file = dds_file(&bc1_dds());
+        for length in 0..file.len() {
+            let result = ddsfile::Dds::read(&file[..length])
+                .map_err(TextureContainerError::from)
+                .and_then(|dds| dds_image(&dds).map(|_| ()));
+            assert!(result.is_err(), "{} bytes", length);
+        }
+    }
+
+    #[test]
+    fn bad_magic_fails() {
+        let mut file = ktx2_file(37, 1, 1, &[&[0; 4]]);
+        file[1] = b'X';
+        assert!(matches!(
+            ktx2_reader(&file),
+            Err(ktx2::ParseError::BadMagic)
+        ));
+
+        let mut file = dds_file(&bc1_dds());
+        file[0] = b'X';
+        assert!(matches!(
+            ddsfile::Dds::read(&file[..]),
+            Err(ddsfile::Error::BadMagicNumber)
+        ));
+    }
+
+    #[test]
+    fn overflowing_ranges_fail() {
+        let file = ktx2_file(37, 4, 2, &[&[1; 32], &[2; 8], &[3; 4]]);
+        let level = |index: usize| 80 + 24 * index;
+        let corrupt = |at: usize, value: u64| {
+            let mut file = file.clone();
+            set_u64(&mut file, at, value);
+            ktx2_reader(&file).map(|_| ())
+        };
+        assert!(corrupt(level(2), u64::MAX - 1).is_err());
+        assert!(corrupt(level(2) + 8, u64::MAX).is_err());
+        // Only the last level's range is checked by the ktx2 crate itself.
+        assert!(corrupt(level(0), 1 << 40).is_err());
+        assert!(corrupt(level(0) + 8, u64::MAX).is_err());
+        // The data format descriptor offset and length are both u32.
+        assert!(corrupt(48, u64::from(u32::MAX)).is_err());
+        assert!(corrupt(48, u64::from(u32::MAX) << 32 | 4).is_err());
+        // A level count past the end of the index.
+        assert!(corrupt(40, u64::from(u32::MAX)).is_err());
+
+        let dds = ddsfile::Dds::new_d3d(ddsfile::NewD3dParams {
+            height: 1,
+            width: 1,
+            depth: None,
+            format: ddsfile::D3DFormat::A8B8G8R8,
+            mipmap_levels: None,
+            caps2: None,
+        })
+        .unwrap();
+        let mut file = dds_file(&dds);
+        // The height and width.
+        set_u64(&mut file, 12, u64::MAX);
+        let dds = ddsfile::Dds::read(&file[..]).unwrap();
+        assert!(matches!(
+            dds_image(&dds),
+            Err(TextureContainerError::Renderer(
+                RendererError::TextureDataSize { .. }
+            ))
+        ));
+    }
+}
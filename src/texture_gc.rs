@@ -0,0 +1,155 @@
+use crate::Frame;
+use std::collections::{HashMap, VecDeque};
+
+type EvictCallback = Box<dyn FnMut(imgui::TextureId) + Send>;
+
+/// Remembers when each user texture was last drawn, and evicts the stale ones while a policy
+/// is set.
+#[derive(Default)]
+pub(crate) struct TextureGc {
+    policy: Option<(u64, EvictCallback)>,
+    last_used: HashMap<imgui::TextureId, u64>,
+    frame: u64,
+}
+impl TextureGc {
+    pub(crate) fn track(&mut self, texture_id: imgui::TextureId) {
+        self.last_used.insert(texture_id, self.frame);
+    }
+    pub(crate) fn untrack(&mut self, texture_id: imgui::TextureId) {
+        self.last_used.remove(&texture_id);
+    }
+    /// Every tracked id that hasn't been removed through the renderer or evicted.
+    pub(crate) fn tracked(&self) -> impl Iterator<Item = imgui::TextureId> + '_ {
+        self.last_used.keys().copied()
+    }
+    pub(crate) fn set_policy(&mut self, policy: Option<(u64, EvictCallback)>) {
+        // Frames rendered without a policy don't count against anybody.
+        for last_used in self.last_used.values_mut() {
+            *last_used = self.frame;
+        }
+        self.policy = policy;
+    }
+    /// Marks the textures drawn by `frames` as used in the current frame. Called for every
+    /// render, so viewports of the same frame all count.
+    pub(crate) fn mark_used<'f>(&mut self, frames: impl Iterator<Item = &'f Frame<'f>>) {
+        if self.policy.is_none() {
+            return;
+        }
+        for draw_cmd in frames
+            .flat_map(|frame| frame.draw_lists.iter())
+            .flat_map(|draw_list| draw_list.commands())
+        {
+            if let imgui::DrawCmd::Elements { cmd_params, .. } = draw_cmd {
+                self.mark(cmd_params.texture_id);
+            }
+        }
+    }
+    fn mark(&mut self, texture_id: imgui::TextureId) {
+        if let Some(last_used) = self.last_used.get_mut(&texture_id) {
+            *last_used = self.frame;
+        }
+    }
+    /// Moves every stale texture into `evicted` and starts the next frame.
+    pub(crate) fn end_frame<T>(
+        &mut self,
+        textures: &mut imgui::Textures<T>,
+        evicted: &mut Vec<(imgui::TextureId, T)>,
+    ) {
+        let (max_unused_frames, on_evict) = match &mut self.policy {
+            Some(policy) => policy,
+            None => return,
+        };
+        let frame = self.frame;
+        self.frame += 1;
+        let max_unused_frames = *max_unused_frames;
+        self.last_used.retain(|&texture_id, &mut last_used| {
+            if frame - last_used <= max_unused_frames {
+                // Textures removed by the application are simply forgotten.
+                return textures.get(texture_id).is_some();
+            }
+            if let Some(texture) = textures.remove(texture_id) {
+                evicted.push((texture_id, texture));
+                on_evict(texture_id);
+            }
+            false
+        });
+    }
+}
+
+/// How many removed texture ids `Renderer::checked_texture` remembers to report as stale.
+pub(crate) const REMEMBERED_REMOVALS: usize = 1024;
+
+/// The ids of the last `REMEMBERED_REMOVALS` removed textures, oldest first.
+#[derive(Default)]
+pub(crate) struct RemovedTextures(VecDeque<imgui::TextureId>);
+impl RemovedTextures {
+    pub(crate) fn insert(&mut self, texture_id: imgui::TextureId) {
+        if self.0.len() == REMEMBERED_REMOVALS {
+            self.0.pop_front();
+        }
+        self.0.push_back(texture_id);
+    }
+    pub(crate) fn contains(&self, texture_id: &imgui::TextureId) -> bool {
+        self.0.contains(texture_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn untrack_forgets_removed_textures() {
+        let mut gc = TextureGc::default();
+        for id in 0..3 {
+            gc.track(imgui::TextureId::from(id));
+        }
+        gc.untrack(imgui::TextureId::from(1));
+        gc.untrack(imgui::TextureId::from(7));
+        let mut tracked: Vec<usize> = gc.tracked().map(|texture_id| texture_id.id()).collect();
+        tracked.sort_unstable();
+        assert_eq!(tracked, [0, 2]);
+    }
+
+    #[test]
+    fn viewports_of_one_frame_count_once() {
+        let evicted_ids = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let on_evict = {
+            let evicted_ids = evicted_ids.clone();
+            move |texture_id: imgui::TextureId| evicted_ids.lock().unwrap().push(texture_id)
+        };
+        let mut gc = TextureGc::default();
+        gc.set_policy(Some((0, Box::new(on_evict))));
+        let mut textures = imgui::Textures::new();
+        let main = textures.insert(());
+        let secondary = textures.insert(());
+        gc.track(main);
+        gc.track(secondary);
+        let mut evicted = Vec::new();
+        // Each frame renders the main viewport, then a second one showing another texture.
+        for _ in 0..3 {
+            gc.mark(main);
+            gc.mark(secondary);
+            gc.end_frame(&mut textures, &mut evicted);
+        }
+        assert!(evicted.is_empty());
+
+        gc.mark(main);
+        gc.end_frame(&mut textures, &mut evicted);
+        assert_eq!(evicted, [(secondary, ())]);
+        assert_eq!(*evicted_ids.lock().unwrap(), [secondary]);
+        assert!(textures.get(main).is_some());
+    }
+
+    #[test]
+    fn removed_textures_forget_the_oldest() {
+        let mut removed = RemovedTextures::default();
+        for id in 0..REMEMBERED_REMOVALS + 2 {
+            removed.insert(imgui::TextureId::from(id));
+        }
+        assert_eq!(removed.0.len(), REMEMBERED_REMOVALS);
+        assert!(!removed.contains(&imgui::TextureId::from(1)));
+        assert!(removed.contains(&imgui::TextureId::from(2)));
+        assert!(removed.contains(&imgui::TextureId::from(REMEMBERED_REMOVALS + 1)));
+    }
+}
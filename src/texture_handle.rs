@@ -0,0 +1,53 @@
+use std::sync::{Arc, Mutex};
+
+/// A registered texture that is removed from its renderer when the handle is dropped, from
+/// `Renderer::texture_handle`. The removal happens at the start of the renderer's next frame,
+/// so a handle may be dropped on any thread.
+pub struct TextureHandle {
+    texture_id: imgui::TextureId,
+    dropped: Arc<Mutex<Vec<imgui::TextureId>>>,
+}
+impl TextureHandle {
+    /// The id to draw the texture with, e.g. in `imgui::Image`.
+    pub fn id(&self) -> imgui::TextureId {
+        self.texture_id
+    }
+    /// Gives up the handle without removing the texture, going back to removing it by id.
+    pub fn into_id(self) -> imgui::TextureId {
+        let texture_id = self.texture_id;
+        std::mem::forget(self);
+        texture_id
+    }
+}
+impl Drop for TextureHandle {
+    fn drop(&mut self) {
+        if let Ok(mut dropped) = self.dropped.lock() {
+            dropped.push(self.texture_id);
+        }
+    }
+}
+impl std::fmt::Debug for TextureHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("TextureHandle")
+            .field(&self.texture_id)
+            .finish()
+    }
+}
+
+/// The ids of dropped handles, waiting for the renderer to remove their textures.
+#[derive(Default)]
+pub(crate) struct DroppedTextures(Arc<Mutex<Vec<imgui::TextureId>>>);
+impl DroppedTextures {
+    pub(crate) fn handle(&self, texture_id: imgui::TextureId) -> TextureHandle {
+        TextureHandle {
+            texture_id,
+            dropped: self.0.clone(),
+        }
+    }
+    pub(crate) fn take(&self) -> Vec<imgui::TextureId> {
+        match self.0.lock() {
+            Ok(mut dropped) if !dropped.is_empty() => std::mem::take(&mut *dropped),
+            _ => Vec::new(),
+        }
+    }
+}
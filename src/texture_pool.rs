@@ -0,0 +1,62 @@
+use crate::Texture;
+
+const DEFAULT_CAPACITY: usize = 32;
+
+/// Removed textures kept around so uploads of the same shape can reuse their allocation and
+/// bind group. The oldest texture is freed once more than `capacity` are pooled.
+pub(crate) struct TexturePool {
+    free: Vec<Texture>,
+    // Textures recycled since the last render. The frame being recorded may still draw them,
+    // so they only become free at the start of the next render, like `retired_textures`.
+    pending: Vec<Texture>,
+    capacity: usize,
+}
+impl Default for TexturePool {
+    fn default() -> Self {
+        Self {
+            free: Vec::new(),
+            pending: Vec::new(),
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+}
+impl TexturePool {
+    /// Takes a free texture created with the same `descriptor`, label aside.
+    pub(crate) fn take(&mut self, descriptor: &wgpu::TextureDescriptor) -> Option<Texture> {
+        let index = self
+            .free
+            .iter()
+            .position(|texture| match &texture.storage {
+                Some(storage) => {
+                    storage.size == descriptor.size
+                        && storage.mip_level_count == descriptor.mip_level_count
+                        && storage.sample_count == descriptor.sample_count
+                        && storage.dimension == descriptor.dimension
+                        && storage.format == descriptor.format
+                        && storage.usage == descriptor.usage
+                }
+                None => false,
+            })?;
+        Some(self.free.remove(index))
+    }
+    /// Pools `texture` from the next `release` on if the renderer owns its allocation, and
+    /// drops it otherwise.
+    pub(crate) fn recycle(&mut self, texture: Texture) {
+        if texture.storage.is_some() && self.capacity > 0 {
+            self.pending.push(texture);
+        }
+    }
+    /// Frees the textures recycled since the last call. Called at the start of every render.
+    pub(crate) fn release(&mut self) {
+        self.free.append(&mut self.pending);
+        self.trim();
+    }
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.trim();
+    }
+    fn trim(&mut self) {
+        let excess = self.free.len().saturating_sub(self.capacity);
+        self.free.drain(..excess);
+    }
+}
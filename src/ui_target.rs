@@ -0,0 +1,457 @@
+use crate::{Layer, RenderTarget, Renderer, RendererError, RendererResult, Viewport};
+use std::future::Future;
+use std::num::NonZeroU32;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+// Subresource renders and captures are submitted right away, but get buffers of their own so
+// they don't overwrite a main viewport frame that is still being recorded.
+const SUBRESOURCE_VIEWPORT_ID: u32 = 0x5355_4252;
+const CAPTURE_FRAME_VIEWPORT_ID: u32 = 0x4346_524d;
+// Each `UiRenderTarget` draws from buffers of its own, numbered up from here.
+static NEXT_TARGET_VIEWPORT_ID: AtomicU32 = AtomicU32::new(0x5554_0000);
+
+/// An off-screen color texture the UI can be rendered into, e.g. for world-space panels or
+/// VR overlays. The texture is created with `SAMPLED` usage so `view()` can be bound directly,
+/// and with `COPY_SRC` so it can be read back.
+pub struct UiRenderTarget {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    depth_view: Option<wgpu::TextureView>,
+    target: RenderTarget,
+    viewport_id: u32,
+    width: u32,
+    height: u32,
+}
+impl UiRenderTarget {
+    pub fn new(
+        device: &wgpu::Device,
+        renderer: &mut Renderer,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let texture = color_texture(device, width, height, format);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let depth_view = depth_view(renderer, device, width, height);
+        let target = RenderTarget {
+            format,
+            sample_count: 1,
+        };
+        renderer.add_render_target(device, target);
+        Self {
+            texture,
+            view,
+            depth_view,
+            target,
+            viewport_id: NEXT_TARGET_VIEWPORT_ID.fetch_add(1, Ordering::Relaxed),
+            width,
+            height,
+        }
+    }
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+    /// The viewport id `render` keeps this target's vertex and index buffers under. Pass it
+    /// to `Renderer::remove_viewport` to free them once the target is no longer rendered to.
+    pub fn viewport_id(&self) -> u32 {
+        self.viewport_id
+    }
+    /// Clears the texture to `clear_color`, renders `draw_data` into it and submits the work.
+    pub fn render(
+        &self,
+        renderer: &mut Renderer,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        draw_data: &imgui::DrawData,
+        clear_color: wgpu::Color,
+    ) -> RendererResult<()> {
+        self.render_layers(
+            renderer,
+            device,
+            queue,
+            &[Layer::from(draw_data)],
+            self.viewport_id,
+            clear_color,
+        )
+    }
+    pub(crate) fn render_layers(
+        &self,
+        renderer: &mut Renderer,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layers: &[Layer],
+        viewport_id: u32,
+        clear_color: wgpu::Color,
+    ) -> RendererResult<()> {
+        submit_pass(
+            renderer,
+            device,
+            queue,
+            Attachments {
+                view: &self.view,
+                depth_view: self.depth_view.as_ref(),
+                load: wgpu::LoadOp::Clear(clear_color),
+                target: self.target,
+            },
+            layers,
+            viewport_id,
+        )
+    }
+    /// Copies the texture back to the CPU as tightly packed rows of texels, blocking until the
+    /// GPU is done. Expects a 4-byte-per-texel format.
+    pub fn read_pixels(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> RendererResult<Vec<u8>> {
+        read_texture(device, queue, &self.texture, self.width, self.height)
+    }
+}
+
+impl Renderer {
+    /// Renders `draw_data` into a new `width` x `height` RGBA texture and reads the pixels back.
+    /// The UI is scaled to fill the texture.
+    pub fn capture_frame(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        draw_data: &imgui::DrawData,
+        width: u32,
+        height: u32,
+    ) -> RendererResult<Vec<u8>> {
+        crate::validate_texture_size(width, height)?;
+        let format = wgpu::TextureFormat::Rgba8Unorm;
+        let texture = color_texture(device, width, height, format);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let depth_view = depth_view(self, device, width, height);
+        fit_viewport(self, width, height, |renderer| {
+            submit_pass(
+                renderer,
+                device,
+                queue,
+                Attachments {
+                    view: &view,
+                    depth_view: depth_view.as_ref(),
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    target: RenderTarget {
+                        format,
+                        sample_count: 1,
+                    },
+                },
+                &[Layer::from(draw_data)],
+                CAPTURE_FRAME_VIEWPORT_ID,
+            )
+        })?;
+        read_texture(device, queue, &texture, width, height)
+    }
+    /// Reads a registered texture back from the GPU as tightly packed RGBA rows, e.g. to
+    /// export a texture edited in the UI. Blocks until the copy is done. BGRA textures are
+    /// swizzled to RGBA; other formats than 8-bit RGBA and BGRA are not supported.
+    pub fn download_texture(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture_id: imgui::TextureId,
+    ) -> RendererResult<Vec<u8>> {
+        let storage = self
+            .textures
+            .get(texture_id)
+            .ok_or(RendererError::BadTexture(texture_id))?
+            .storage
+            .as_ref()
+            .filter(|storage| storage.usage.contains(wgpu::TextureUsage::COPY_SRC))
+            .ok_or(RendererError::TextureNotReadable(texture_id))?;
+        let bgra = match storage.format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            format => return Err(RendererError::UnsupportedTextureFormat(format)),
+        };
+        let mut pixels = read_texture(
+            device,
+            queue,
+            &storage.texture,
+            storage.size.width,
+            storage.size.height,
+        )?;
+        if bgra {
+            for texel in pixels.chunks_exact_mut(4) {
+                texel.swap(0, 2);
+            }
+        }
+        Ok(pixels)
+    }
+}
+
+/// A mip level and array layer of a texture owned by the application, for
+/// `Renderer::render_to_subresource`. The texture needs `OUTPUT_ATTACHMENT` usage.
+#[derive(Clone, Copy)]
+pub struct TextureSubresource<'t> {
+    pub texture: &'t wgpu::Texture,
+    pub format: wgpu::TextureFormat,
+    /// The size of the texture's first mip level.
+    pub size: (u32, u32),
+    pub mip_level: u32,
+    pub array_layer: u32,
+}
+
+impl Renderer {
+    /// Renders `draw_data` into a single mip level and array layer of a texture, e.g. a page
+    /// of an atlas or a slice of a layered render target, and submits the work. The UI is
+    /// scaled to fill the level.
+    pub fn render_to_subresource(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        draw_data: &imgui::DrawData,
+        subresource: TextureSubresource,
+        load: wgpu::LoadOp<wgpu::Color>,
+    ) -> RendererResult<()> {
+        let width = (subresource.size.0 >> subresource.mip_level).max(1);
+        let height = (subresource.size.1 >> subresource.mip_level).max(1);
+        let view = subresource
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor {
+                format: Some(subresource.format),
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                base_mip_level: subresource.mip_level,
+                level_count: NonZeroU32::new(1),
+                base_array_layer: subresource.array_layer,
+                array_layer_count: NonZeroU32::new(1),
+                ..Default::default()
+            });
+        let depth_view = depth_view(self, device, width, height);
+        let target = RenderTarget {
+            format: subresource.format,
+            sample_count: 1,
+        };
+        self.add_render_target(device, target);
+        // The level is usually smaller than the display.
+        fit_viewport(self, width, height, |renderer| {
+            submit_pass(
+                renderer,
+                device,
+                queue,
+                Attachments {
+                    view: &view,
+                    depth_view: depth_view.as_ref(),
+                    load,
+                    target,
+                },
+                &[Layer::from(draw_data)],
+                SUBRESOURCE_VIEWPORT_ID,
+            )
+        })
+    }
+}
+
+// Runs `render` with the display mapped onto a `width` x `height` target through the viewport
+// instead of the framebuffer scale, so the UI fills the target and scissor rects stay inside it.
+// The projection override is set up for the main frame, so it is left out as well.
+pub(crate) fn fit_viewport<R>(
+    renderer: &mut Renderer,
+    width: u32,
+    height: u32,
+    render: impl FnOnce(&mut Renderer) -> R,
+) -> R {
+    let projection = renderer.projection.take();
+    let viewport = renderer.viewport.replace(Viewport {
+        x: 0.0,
+        y: 0.0,
+        width: width as f32,
+        height: height as f32,
+    });
+    let result = render(renderer);
+    renderer.projection = projection;
+    renderer.viewport = viewport;
+    result
+}
+
+struct Attachments<'v> {
+    view: &'v wgpu::TextureView,
+    depth_view: Option<&'v wgpu::TextureView>,
+    load: wgpu::LoadOp<wgpu::Color>,
+    target: RenderTarget,
+}
+
+// A color texture for UI to be rendered into, sampled and read back.
+fn color_texture(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT
+            | wgpu::TextureUsage::SAMPLED
+            | wgpu::TextureUsage::COPY_SRC,
+    })
+}
+
+// A depth buffer for a `width` x `height` color target, if the renderer's pipelines test depth.
+fn depth_view(
+    renderer: &Renderer,
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+) -> Option<wgpu::TextureView> {
+    renderer
+        .pipeline_factory
+        .depth_stencil_state
+        .as_ref()
+        .map(|state| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: None,
+                    size: wgpu::Extent3d {
+                        width,
+                        height,
+                        depth: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: state.format,
+                    usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        })
+}
+
+// Renders `layers` into `attachments` in a render pass of its own and submits it.
+fn submit_pass(
+    renderer: &mut Renderer,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    attachments: Attachments,
+    layers: &[Layer],
+    viewport_id: u32,
+) -> RendererResult<()> {
+    let mut encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: attachments.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: attachments.load,
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: attachments.depth_view.map(|depth_view| {
+                wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                    attachment: depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: false,
+                    }),
+                    stencil_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(0),
+                        store: false,
+                    }),
+                }
+            }),
+        });
+        renderer.render_frame(
+            device,
+            queue,
+            &mut render_pass,
+            layers.iter().copied(),
+            viewport_id,
+            attachments.target,
+        )?;
+    }
+    queue.submit(Some(encoder.finish()));
+    Ok(())
+}
+
+// Copies mip 0 of a texture with 4 bytes per texel back to the CPU as tightly packed rows,
+// blocking until the GPU is done. The texture needs `COPY_SRC` usage.
+fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    width: u32,
+    height: u32,
+) -> RendererResult<Vec<u8>> {
+    let row_bytes = width * 4;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let padded_row_bytes = row_bytes.div_ceil(align) * align;
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: (padded_row_bytes * height) as u64,
+        usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    encoder.copy_texture_to_buffer(
+        wgpu::TextureCopyView {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+        },
+        wgpu::BufferCopyView {
+            buffer: &buffer,
+            layout: wgpu::TextureDataLayout {
+                offset: 0,
+                bytes_per_row: padded_row_bytes,
+                rows_per_image: height,
+            },
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth: 1,
+        },
+    );
+    queue.submit(Some(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let mut mapping = Box::pin(slice.map_async(wgpu::MapMode::Read));
+    device.poll(wgpu::Maintain::Wait);
+    match poll_once(mapping.as_mut()) {
+        Poll::Ready(Ok(())) => {}
+        _ => return Err(RendererError::ReadbackFailed),
+    }
+    let padded = slice.get_mapped_range();
+    Ok(padded
+        .chunks(padded_row_bytes as usize)
+        .flat_map(|row| &row[..row_bytes as usize])
+        .copied()
+        .collect())
+}
+
+// `Maintain::Wait` has already resolved the mapping by the time this is called, so a single
+// poll with a waker that does nothing is enough.
+fn poll_once<F: Future>(future: Pin<&mut F>) -> Poll<F::Output> {
+    future.poll(&mut Context::from_waker(&noop_waker()))
+}
+
+// A waker for the map futures, which complete from `Device::poll`, so there is nothing to wake.
+pub(crate) fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+    unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) }
+}
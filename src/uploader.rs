@@ -0,0 +1,165 @@
+use imgui::{DrawIdx, DrawVert};
+use std::mem::size_of;
+
+/// One `DrawCmd::Elements` of the draw data uploaded by a `DrawDataUploader`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawRange {
+    /// The index of the draw list the command belongs to, e.g. to run its callbacks in order.
+    pub draw_list: usize,
+    /// The range of the index buffer to draw.
+    pub indices: std::ops::Range<u32>,
+    pub base_vertex: i32,
+    pub texture_id: imgui::TextureId,
+    /// imgui's clip rectangle as `[x1, y1, x2, y2]`, in the coordinates of `display_pos`.
+    pub clip_rect: [f32; 4],
+}
+
+/// The vertex and index buffer management of `Renderer` on its own, for engines that draw
+/// imgui geometry with their own pipelines and materials. Every upload packs all draw lists
+/// into one vertex and one index buffer of `imgui::DrawIdx`, which grow as needed, and lists
+/// the ranges each draw command covers in them. Draw with the index format of that size,
+/// `Uint16` unless imgui is built with 32-bit indices.
+pub struct DrawDataUploader {
+    index_buffer: Option<(wgpu::Buffer, u64)>,
+    vertex_buffer: Option<(wgpu::Buffer, u64)>,
+    indices: Vec<u8>,
+    vertices: Vec<u8>,
+    commands: Vec<DrawRange>,
+}
+impl DrawDataUploader {
+    pub fn new() -> Self {
+        Self {
+            index_buffer: None,
+            vertex_buffer: None,
+            indices: Vec::new(),
+            vertices: Vec::new(),
+            commands: Vec::new(),
+        }
+    }
+    /// The layout of the vertex buffer: the position at `location = 0`, the UV at
+    /// `location = 1` and the RGBA8 color packed into a `uint` at `location = 2`.
+    pub fn vertex_attributes() -> [wgpu::VertexAttributeDescriptor; 3] {
+        // `ImDrawVert` is `pos`, `uv` and `col`, without padding.
+        let float2 = size_of::<[f32; 2]>() as wgpu::BufferAddress;
+        [
+            wgpu::VertexAttributeDescriptor {
+                format: wgpu::VertexFormat::Float2,
+                offset: 0,
+                shader_location: 0,
+            },
+            wgpu::VertexAttributeDescriptor {
+                format: wgpu::VertexFormat::Float2,
+                offset: float2,
+                shader_location: 1,
+            },
+            wgpu::VertexAttributeDescriptor {
+                format: wgpu::VertexFormat::Uint,
+                offset: 2 * float2,
+                shader_location: 2,
+            },
+        ]
+    }
+    /// The distance between vertices in the vertex buffer.
+    pub fn vertex_stride() -> wgpu::BufferAddress {
+        size_of::<DrawVert>() as wgpu::BufferAddress
+    }
+    /// Writes the geometry of `draw_data` to the buffers through `queue`, replacing the
+    /// previous upload. Submit the draws before uploading the next frame.
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        draw_data: &imgui::DrawData,
+    ) {
+        self.indices.clear();
+        self.vertices.clear();
+        self.commands.clear();
+        for (draw_list_index, draw_list) in draw_data.draw_lists().enumerate() {
+            let first_index = (self.indices.len() / size_of::<DrawIdx>()) as u32;
+            let first_vertex = (self.vertices.len() / size_of::<DrawVert>()) as i32;
+            self.indices
+                .extend_from_slice(bytemuck::cast_slice(draw_list.idx_buffer()));
+            let vertices = draw_list.vtx_buffer();
+            self.vertices.extend_from_slice(unsafe {
+                std::slice::from_raw_parts(
+                    vertices.as_ptr() as *const u8,
+                    std::mem::size_of_val(vertices),
+                )
+            });
+            for draw_cmd in draw_list.commands() {
+                if let imgui::DrawCmd::Elements { count, cmd_params } = draw_cmd {
+                    let start = first_index + cmd_params.idx_offset as u32;
+                    self.commands.push(DrawRange {
+                        draw_list: draw_list_index,
+                        indices: start..start + count as u32,
+                        base_vertex: first_vertex + cmd_params.vtx_offset as i32,
+                        texture_id: cmd_params.texture_id,
+                        clip_rect: cmd_params.clip_rect,
+                    });
+                }
+            }
+        }
+        write(
+            device,
+            queue,
+            &mut self.index_buffer,
+            wgpu::BufferUsage::INDEX,
+            &mut self.indices,
+        );
+        write(
+            device,
+            queue,
+            &mut self.vertex_buffer,
+            wgpu::BufferUsage::VERTEX,
+            &mut self.vertices,
+        );
+    }
+    /// `None` until something was uploaded.
+    pub fn index_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.index_buffer.as_ref().map(|(buffer, _)| buffer)
+    }
+    pub fn vertex_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.vertex_buffer.as_ref().map(|(buffer, _)| buffer)
+    }
+    /// The draw commands of the last upload, in order.
+    pub fn commands(&self) -> &[DrawRange] {
+        &self.commands
+    }
+}
+impl Default for DrawDataUploader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Pads `data` for `write_buffer` and writes it to `buffer`, replacing the buffer with one of
+// the next power of two size if it is too small.
+fn write(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    buffer: &mut Option<(wgpu::Buffer, u64)>,
+    usage: wgpu::BufferUsage,
+    data: &mut Vec<u8>,
+) {
+    data.resize(
+        data.len().div_ceil(wgpu::COPY_BUFFER_ALIGNMENT as usize)
+            * wgpu::COPY_BUFFER_ALIGNMENT as usize,
+        0,
+    );
+    let size = (data.len() as u64).max(wgpu::COPY_BUFFER_ALIGNMENT);
+    if !matches!(buffer, Some((_, capacity)) if *capacity >= size) {
+        let capacity = size.next_power_of_two();
+        *buffer = Some((
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: capacity,
+                usage: usage | wgpu::BufferUsage::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            capacity,
+        ));
+    }
+    if !data.is_empty() {
+        queue.write_buffer(&buffer.as_ref().unwrap().0, 0, data);
+    }
+}
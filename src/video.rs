@@ -0,0 +1,315 @@
+use crate::fullscreen_pass;
+use crate::shaders::Shader;
+use crate::{Renderer, RendererError, RendererResult};
+use wgpu::util::DeviceExt;
+
+/// Layout of the planes passed to `VideoTexture::update`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum YuvFormat {
+    /// A full-resolution Y plane followed by a half-resolution interleaved UV plane.
+    Nv12,
+    /// Full-resolution Y, then half-resolution U and V planes.
+    I420,
+}
+
+/// The matrix used to convert limited-range YUV to RGB.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum YuvColorSpace {
+    Bt601,
+    Bt709,
+}
+impl YuvColorSpace {
+    // Column-major, applied to `(y, u, v, 1)`.
+    fn matrix(self) -> [f32; 16] {
+        let (kr, kb) = match self {
+            YuvColorSpace::Bt601 => (0.299, 0.114),
+            YuvColorSpace::Bt709 => (0.2126, 0.0722),
+        };
+        let kg = 1.0 - kr - kb;
+        let y_scale = 255.0 / 219.0;
+        let c_scale = 255.0 / 224.0;
+        let y_offset = 16.0 / 255.0;
+        let c_offset = 128.0 / 255.0;
+        let r_v = 2.0 * (1.0 - kr) * c_scale;
+        let g_u = -2.0 * kb * (1.0 - kb) / kg * c_scale;
+        let g_v = -2.0 * kr * (1.0 - kr) / kg * c_scale;
+        let b_u = 2.0 * (1.0 - kb) * c_scale;
+        let base = -y_scale * y_offset;
+        [
+            y_scale,
+            y_scale,
+            y_scale,
+            0.0,
+            0.0,
+            g_u,
+            b_u,
+            0.0,
+            r_v,
+            g_v,
+            0.0,
+            0.0,
+            base - r_v * c_offset,
+            base - (g_u + g_v) * c_offset,
+            base - b_u * c_offset,
+            1.0,
+        ]
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Conversion {
+    yuv_to_rgb: [f32; 16],
+    u_mask: [f32; 4],
+    v_mask: [f32; 4],
+}
+
+unsafe impl bytemuck::Zeroable for Conversion {}
+
+unsafe impl bytemuck::Pod for Conversion {}
+
+struct Plane {
+    texture: wgpu::Texture,
+    size: wgpu::Extent3d,
+    bytes_per_texel: u32,
+}
+
+/// A texture fed with YUV video frames, converted to RGBA on the GPU and drawable with
+/// `imgui::Image` like any other texture.
+pub struct VideoTexture {
+    planes: Vec<Plane>,
+    output: wgpu::TextureView,
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    texture_id: imgui::TextureId,
+}
+impl VideoTexture {
+    pub fn new(
+        device: &wgpu::Device,
+        renderer: &mut Renderer,
+        width: u32,
+        height: u32,
+        format: YuvFormat,
+        color_space: YuvColorSpace,
+    ) -> Self {
+        let chroma_width = width.div_ceil(2);
+        let chroma_height = height.div_ceil(2);
+        let planes: Vec<Plane> = match format {
+            YuvFormat::Nv12 => vec![
+                Plane::new(device, width, height, wgpu::TextureFormat::R8Unorm, 1),
+                Plane::new(
+                    device,
+                    chroma_width,
+                    chroma_height,
+                    wgpu::TextureFormat::Rg8Unorm,
+                    2,
+                ),
+            ],
+            YuvFormat::I420 => vec![
+                Plane::new(device, width, height, wgpu::TextureFormat::R8Unorm, 1),
+                Plane::new(
+                    device,
+                    chroma_width,
+                    chroma_height,
+                    wgpu::TextureFormat::R8Unorm,
+                    1,
+                ),
+                Plane::new(
+                    device,
+                    chroma_width,
+                    chroma_height,
+                    wgpu::TextureFormat::R8Unorm,
+                    1,
+                ),
+            ],
+        };
+        let plane_views: Vec<wgpu::TextureView> = planes
+            .iter()
+            .map(|plane| {
+                plane
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor::default())
+            })
+            .collect();
+        let (u_view, v_view, v_mask) = match format {
+            YuvFormat::Nv12 => (&plane_views[1], &plane_views[1], [0.0, 1.0, 0.0, 0.0]),
+            YuvFormat::I420 => (&plane_views[1], &plane_views[2], [1.0, 0.0, 0.0, 0.0]),
+        };
+        let conversion = Conversion {
+            yuv_to_rgb: color_space.matrix(),
+            u_mask: [1.0, 0.0, 0.0, 0.0],
+            v_mask,
+        };
+        let conversion_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::bytes_of(&conversion),
+            usage: wgpu::BufferUsage::UNIFORM,
+        });
+
+        let plane_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStage::FRAGMENT,
+            ty: wgpu::BindingType::SampledTexture {
+                multisampled: false,
+                component_type: wgpu::TextureComponentType::Float,
+                dimension: wgpu::TextureViewDimension::D2,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::UniformBuffer {
+                        dynamic: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<Conversion>() as u64
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler { comparison: false },
+                    count: None,
+                },
+                plane_entry(2),
+                plane_entry(3),
+                plane_entry(4),
+            ],
+        });
+        let sampler = renderer.samplers.get(renderer.default_sampler);
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(conversion_buffer.slice(..)),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&plane_views[0]),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(u_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(v_view),
+                },
+            ],
+        });
+
+        let format = crate::upload_descriptor(width, height).format;
+        let pipeline =
+            fullscreen_pass::pipeline(device, &bind_group_layout, Shader::YuvFragment, format);
+        let (output, texture_id) = fullscreen_pass::output_texture(device, renderer, width, height);
+        Self {
+            planes,
+            output,
+            pipeline,
+            bind_group,
+            texture_id,
+        }
+    }
+    pub fn texture_id(&self) -> imgui::TextureId {
+        self.texture_id
+    }
+    /// Uploads one frame and converts it, submitting the work to `queue`. `planes` holds the
+    /// Y and UV planes for `Nv12`, or the Y, U and V planes for `I420`, with tightly packed
+    /// rows. Nothing is uploaded if there are more or fewer planes or one has another size.
+    pub fn update(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        planes: &[&[u8]],
+    ) -> RendererResult<()> {
+        if planes.len() != self.planes.len() {
+            return Err(RendererError::PlaneCount {
+                expected: self.planes.len(),
+                actual: planes.len(),
+            });
+        }
+        for (plane, data) in self.planes.iter().zip(planes) {
+            let expected = plane.byte_size();
+            if data.len() != expected {
+                return Err(RendererError::TextureDataSize {
+                    expected,
+                    actual: data.len(),
+                });
+            }
+        }
+        for (plane, data) in self.planes.iter().zip(planes) {
+            plane.write(queue, data);
+        }
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        fullscreen_pass::draw(
+            &mut encoder,
+            &self.output,
+            wgpu::Color::BLACK,
+            &self.pipeline,
+            &self.bind_group,
+        );
+        queue.submit(Some(encoder.finish()));
+        Ok(())
+    }
+}
+
+impl Plane {
+    fn new(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        bytes_per_texel: u32,
+    ) -> Self {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+        });
+        Self {
+            texture,
+            size,
+            bytes_per_texel,
+        }
+    }
+    fn byte_size(&self) -> usize {
+        self.size.width as usize * self.size.height as usize * self.bytes_per_texel as usize
+    }
+    fn write(&self, queue: &wgpu::Queue, data: &[u8]) {
+        queue.write_texture(
+            wgpu::TextureCopyView {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            data,
+            wgpu::TextureDataLayout {
+                offset: 0,
+                bytes_per_row: self.size.width * self.bytes_per_texel,
+                rows_per_image: 0,
+            },
+            self.size,
+        );
+    }
+}
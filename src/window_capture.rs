@@ -0,0 +1,129 @@
+use crate::{Frame, FrameDrawLists, Layer, Renderer, RendererResult, UiRenderTarget};
+use imgui::internal::RawWrapper;
+use std::ffi::CStr;
+
+// Captures get viewport resources of their own, so capturing doesn't overwrite the uniforms of
+// a main viewport frame that hasn't been submitted yet.
+const CAPTURE_VIEWPORT_ID: u32 = 0x5743_4150;
+
+// Whether `draw_list` was drawn by the window named `window_name` or one of its child windows,
+// which imgui names `"<parent>/<child>_<id>"`.
+fn owned_by(draw_list: &imgui::DrawList, window_name: &str) -> bool {
+    let owner = unsafe { draw_list.raw()._OwnerName };
+    if owner.is_null() {
+        return false;
+    }
+    let owner = unsafe { CStr::from_ptr(owner) }.to_bytes();
+    let name = window_name.as_bytes();
+    owner == name || (owner.starts_with(name) && owner.get(name.len()) == Some(&b'/'))
+}
+
+// The rectangle the draw lists cover on screen, as min x, min y, max x, max y: the bounds of
+// the drawn vertices within the commands' clip rects. A top-level window's first clip rect is
+// the whole viewport, so the vertices narrow it down to the window.
+fn window_rect(draw_lists: &[&imgui::DrawList]) -> Option<[f32; 4]> {
+    let mut clip = [f32::MAX, f32::MAX, f32::MIN, f32::MIN];
+    let mut drawn = clip;
+    for draw_list in draw_lists {
+        let vertices = draw_list.vtx_buffer();
+        let indices = draw_list.idx_buffer();
+        for command in draw_list.commands() {
+            if let imgui::DrawCmd::Elements { count, cmd_params } = command {
+                let rect = cmd_params.clip_rect;
+                clip = [
+                    clip[0].min(rect[0]),
+                    clip[1].min(rect[1]),
+                    clip[2].max(rect[2]),
+                    clip[3].max(rect[3]),
+                ];
+                for &index in &indices[cmd_params.idx_offset..cmd_params.idx_offset + count] {
+                    let pos = vertices[cmd_params.vtx_offset + index as usize].pos;
+                    drawn = [
+                        drawn[0].min(pos[0]),
+                        drawn[1].min(pos[1]),
+                        drawn[2].max(pos[0]),
+                        drawn[3].max(pos[1]),
+                    ];
+                }
+            }
+        }
+    }
+    let rect = [
+        clip[0].max(drawn[0]),
+        clip[1].max(drawn[1]),
+        clip[2].min(drawn[2]),
+        clip[3].min(drawn[3]),
+    ];
+    if rect[2] <= rect[0] || rect[3] <= rect[1] {
+        return None;
+    }
+    Some(rect)
+}
+
+impl Renderer {
+    /// Renders only the window named `window_name`, with its child windows, into a new
+    /// texture the size of the window, e.g. for thumbnails or to stream a single panel to
+    /// another display. `window_name` is the full name passed to `imgui::Window::new`,
+    /// including any `##` suffix. Returns `None` if the window drew nothing this frame.
+    pub fn capture_window(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        draw_data: &imgui::DrawData,
+        window_name: &str,
+        format: wgpu::TextureFormat,
+        clear_color: wgpu::Color,
+    ) -> RendererResult<Option<UiRenderTarget>> {
+        let draw_lists: Vec<&imgui::DrawList> = draw_data
+            .draw_lists()
+            .filter(|draw_list| owned_by(draw_list, window_name))
+            .collect();
+        let display_min = draw_data.display_pos;
+        let display_max = [
+            display_min[0] + draw_data.display_size[0],
+            display_min[1] + draw_data.display_size[1],
+        ];
+        let rect = match window_rect(&draw_lists) {
+            Some(rect) => [
+                rect[0].max(display_min[0]),
+                rect[1].max(display_min[1]),
+                rect[2].min(display_max[0]),
+                rect[3].min(display_max[1]),
+            ],
+            None => return Ok(None),
+        };
+        let scale = draw_data.framebuffer_scale;
+        let width = ((rect[2] - rect[0]) * scale[0]).ceil() as u32;
+        let height = ((rect[3] - rect[1]) * scale[1]).ceil() as u32;
+        if width == 0 || height == 0 {
+            return Ok(None);
+        }
+        crate::validate_texture_size(width, height)?;
+
+        let target = UiRenderTarget::new(device, self, width, height, format);
+        let frame = Frame {
+            display_pos: [rect[0], rect[1]],
+            // Rounded up to whole pixels, so the projection maps one pixel to one texel.
+            display_size: [width as f32 / scale[0], height as f32 / scale[1]],
+            framebuffer_scale: scale,
+            draw_lists: FrameDrawLists::Imgui(&draw_lists),
+        };
+        // The projection and viewport overrides are set up for the main frame, not the
+        // window's texture.
+        let overrides = (self.projection.take(), self.viewport.take());
+        let result = target.render_layers(
+            self,
+            device,
+            queue,
+            &[Layer {
+                frame,
+                projection: None,
+            }],
+            CAPTURE_VIEWPORT_ID,
+            clear_color,
+        );
+        self.projection = overrides.0;
+        self.viewport = overrides.1;
+        result.map(|()| Some(target))
+    }
+}
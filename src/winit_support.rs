@@ -0,0 +1,59 @@
+use crate::{Renderer, RendererConfig, RendererResult};
+use imgui_winit_support::{HiDpiMode, WinitPlatform};
+use std::time::Instant;
+use winit::error::ExternalError;
+use winit::event::Event;
+use winit::window::Window;
+
+/// An imgui context, its winit platform and a `Renderer`, for tools that just want to show
+/// some UI in a winit window.
+pub struct ImguiWgpu {
+    pub imgui: imgui::Context,
+    pub platform: WinitPlatform,
+    pub renderer: Renderer,
+    last_frame: Instant,
+}
+impl ImguiWgpu {
+    pub fn new(
+        window: &Window,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        config: RendererConfig,
+    ) -> RendererResult<Self> {
+        let mut imgui = imgui::Context::create();
+        let mut platform = WinitPlatform::init(&mut imgui);
+        platform.attach_window(imgui.io_mut(), window, HiDpiMode::Default);
+        let renderer = Renderer::new(&mut imgui, device, queue, config)?;
+        Ok(Self {
+            imgui,
+            platform,
+            renderer,
+            last_frame: Instant::now(),
+        })
+    }
+    pub fn handle_event<T>(&mut self, window: &Window, event: &Event<T>) {
+        self.platform
+            .handle_event(self.imgui.io_mut(), window, event);
+    }
+    /// Updates the frame timing and lets the platform apply window size and cursor changes.
+    /// Call once per frame before `render`.
+    pub fn new_frame(&mut self, window: &Window) -> Result<(), ExternalError> {
+        self.last_frame = self.imgui.io_mut().update_delta_time(self.last_frame);
+        self.platform.prepare_frame(self.imgui.io_mut(), window)
+    }
+    /// Builds the frame's UI with `build` and records it into `render_pass`.
+    pub fn render<'a, F: FnOnce(&imgui::Ui)>(
+        &'a mut self,
+        window: &Window,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        build: F,
+    ) -> RendererResult<()> {
+        let ui = self.imgui.frame();
+        build(&ui);
+        self.platform.prepare_render(&ui, window);
+        self.renderer
+            .render(device, queue, render_pass, ui.render())
+    }
+}
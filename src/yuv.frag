@@ -0,0 +1,24 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+layout(location = 0) in vec2 fragUv;
+layout(location = 0) out vec4 outColor;
+
+layout(set = 0, binding = 0) uniform Conversion {
+    mat4 yuvToRgb;
+    // Select the chroma channel of each plane, so NV12 can bind its UV plane twice.
+    vec4 uMask;
+    vec4 vMask;
+} conversion;
+layout(set = 0, binding = 1) uniform sampler planeSampler;
+layout(set = 0, binding = 2) uniform texture2D yPlane;
+layout(set = 0, binding = 3) uniform texture2D uPlane;
+layout(set = 0, binding = 4) uniform texture2D vPlane;
+
+void main() {
+    float y = texture(sampler2D(yPlane, planeSampler), fragUv).r;
+    float u = dot(texture(sampler2D(uPlane, planeSampler), fragUv), conversion.uMask);
+    float v = dot(texture(sampler2D(vPlane, planeSampler), fragUv), conversion.vMask);
+    vec3 rgb = (conversion.yuvToRgb * vec4(y, u, v, 1.0)).rgb;
+    outColor = vec4(clamp(rgb, 0.0, 1.0), 1.0);
+}